@@ -0,0 +1,47 @@
+use std::io::Cursor;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use yarrs::resp::{
+    connection::Message,
+    types::{Frame, RespVersion},
+};
+
+fn nested_array(depth: usize) -> Frame {
+    (0..depth).fold(Frame::Integer(1), |child, _| {
+        Frame::Array(vec![child, Frame::Bulk("sibling".into())])
+    })
+}
+
+fn large_map(pairs: usize) -> Frame {
+    Frame::Map(
+        (0..pairs)
+            .map(|i| {
+                (
+                    Frame::Bulk(format!("key:{}", i).into()),
+                    Frame::Integer(i as i64),
+                )
+            })
+            .collect(),
+    )
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let inputs = [
+        ("small integer", Frame::Integer(42)),
+        ("1KB bulk string", Frame::Bulk(vec![b'x'; 1024].into())),
+        ("nested array (depth 100)", nested_array(100)),
+        ("map (10k pairs)", large_map(10_000)),
+    ]
+    .map(|(name, frame)| (name, frame.encode(RespVersion::V3)));
+
+    let mut group = c.benchmark_group("Frame::parse");
+    for (name, input) in &inputs {
+        group.bench_function(*name, |b| {
+            b.iter(|| Frame::parse(&mut Cursor::new(black_box(&input[..]))).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
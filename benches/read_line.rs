@@ -0,0 +1,51 @@
+use std::io::Cursor;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use yarrs::resp::{connection::Message, types::Frame};
+
+// The byte-by-byte CRLF scan read_line used before switching to memchr
+fn scan_bytewise(buf: &[u8]) -> Option<usize> {
+    (0..buf.len().saturating_sub(1)).find(|&i| buf[i] == b'\r' && buf[i + 1] == b'\n')
+}
+
+fn simple_string(len: usize, filler: u8) -> Vec<u8> {
+    let mut input = vec![b'+'];
+    input.extend(std::iter::repeat_n(filler, len));
+    input.extend_from_slice(b"\r\n");
+    input
+}
+
+fn bench_read_line(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read_line 64KB");
+    for (name, input) in [
+        ("no carriage returns", simple_string(64 * 1024, b'a')),
+        ("lone carriage returns", simple_string(64 * 1024, b'\r')),
+    ] {
+        group.bench_function(format!("bytewise scan, {}", name), |b| {
+            b.iter(|| scan_bytewise(black_box(&input)))
+        });
+        group.bench_function(format!("Frame::parse, {}", name), |b| {
+            b.iter(|| Frame::parse(&mut Cursor::new(black_box(&input[..]))).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_line_frames(c: &mut Criterion) {
+    let integers: String = (0..1000).map(|i| format!(":{}\r\n", i * 7919)).collect();
+    let integers = format!("*1000\r\n{}", integers);
+    let simple: String = (0..1000).map(|i| format!("+value-{}\r\n", i)).collect();
+    let simple = format!("*1000\r\n{}", simple);
+
+    let mut group = c.benchmark_group("line frames");
+    group.bench_function("1000 integers", |b| {
+        b.iter(|| Frame::parse(&mut Cursor::new(black_box(integers.as_bytes()))).unwrap())
+    });
+    group.bench_function("1000 simple strings", |b| {
+        b.iter(|| Frame::parse(&mut Cursor::new(black_box(simple.as_bytes()))).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_read_line, bench_line_frames);
+criterion_main!(benches);
@@ -0,0 +1,28 @@
+#![no_main]
+
+use std::io::Cursor;
+
+use libfuzzer_sys::fuzz_target;
+use yarrs::resp::{
+    connection::Message,
+    types::{Frame, ParseLimits},
+};
+
+// Small enough that a declared length or nesting level can't make a single
+// input spend the fuzzer's memory or time, while still exercising both limits
+const LIMITS: ParseLimits = ParseLimits {
+    max_bulk_len: 64 * 1024,
+    max_depth: 32,
+};
+
+fuzz_target!(|data: &[u8]| {
+    // Any outcome is fine, as long as it's a value and not a panic
+    let mut cursor = Cursor::new(data);
+    if Frame::parse_with_limits(&mut cursor, &LIMITS).is_ok() {
+        assert!(cursor.position() as usize <= data.len());
+    }
+
+    // The entry point used by connections, with the default limits
+    let mut cursor = Cursor::new(data);
+    let _ = Frame::parse(&mut cursor);
+});
@@ -0,0 +1,344 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use bytes::{Bytes, BytesMut};
+use tokio_util::codec::Decoder;
+
+use crate::{
+    cmd,
+    command::{
+        expire::unix_millis_now,
+        getex::GetExpire,
+        set::{Condition, SetOptions},
+        Command,
+    },
+    config::AppendFsync,
+    resp::{
+        codec::FrameCodec,
+        types::{Frame, ParseLimits, RespVersion},
+    },
+};
+
+// Append only file: every write command is logged as it was received (but
+// for relative expirations, see `logged_frame`), encoded as a RESP2 request,
+// so replaying them in order rebuilds the keyspace. SELECT is logged in
+// between when the database being written changes.
+pub struct Aof {
+    file: File,
+    pub fsync: AppendFsync,
+    synced_at: Instant,
+    // Database the logged commands apply to, unknown until one is selected
+    db: Option<usize>,
+}
+
+impl Aof {
+    pub fn open(path: &Path, fsync: AppendFsync) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file,
+            fsync,
+            synced_at: Instant::now(),
+            db: None,
+        })
+    }
+
+    // Log a write command run against the database. It's handed to the
+    // operating system right away, and synced to disk as fsync asks.
+    pub fn append(&mut self, db: usize, frame: &Frame) -> io::Result<()> {
+        let mut buf = BytesMut::new();
+        if self.db != Some(db) {
+            cmd!("SELECT", db.to_string()).encode_to(&mut buf, RespVersion::V2);
+        }
+        frame.encode_to(&mut buf, RespVersion::V2);
+        self.file.write_all(&buf)?;
+        self.db = Some(db);
+
+        let sync = match self.fsync {
+            AppendFsync::Always => true,
+            AppendFsync::EverySec => self.synced_at.elapsed() >= Duration::from_secs(1),
+            AppendFsync::No => false,
+        };
+        if sync {
+            self.file.sync_data()?;
+            self.synced_at = Instant::now();
+        }
+        Ok(())
+    }
+
+    // Sync whatever was logged to disk
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.file.sync_data()?;
+        self.synced_at = Instant::now();
+        Ok(())
+    }
+}
+
+// The frame to log for a write command. Expirations relative to now are
+// turned into unix times, or replaying the log later would start them over.
+// None for BLPOP, which logs the LPOP it ends up doing, if any.
+pub(crate) fn logged_frame(command: &Command, frame: &Frame) -> Option<Frame> {
+    let at = |after: Duration| {
+        unix_millis_now()
+            .saturating_add(after.as_millis() as i64)
+            .to_string()
+    };
+    let logged = match command {
+        Command::BLPop { .. } => return None,
+        Command::Expire { key, seconds } => match seconds.checked_mul(1000) {
+            Some(millis) => cmd!(
+                "PEXPIREAT",
+                key.clone(),
+                unix_millis_now().saturating_add(millis).to_string()
+            ),
+            None => frame.clone(),
+        },
+        Command::Set {
+            key,
+            value,
+            options:
+                SetOptions {
+                    expire: Some(after),
+                    condition,
+                    get,
+                },
+        } => {
+            let mut args = vec![
+                Bytes::from("SET"),
+                key.clone(),
+                value.clone(),
+                Bytes::from("PXAT"),
+                Bytes::from(at(*after)),
+            ];
+            match condition {
+                Condition::Always => {}
+                Condition::IfMissing => args.push(Bytes::from("NX")),
+                Condition::IfExists => args.push(Bytes::from("XX")),
+            }
+            if *get {
+                args.push(Bytes::from("GET"));
+            }
+            Frame::Array(args.into_iter().map(Frame::Bulk).collect())
+        }
+        Command::GetEx {
+            key,
+            expire: GetExpire::In(after),
+        } => cmd!("GETEX", key.clone(), "PXAT", at(*after)),
+        _ => frame.clone(),
+    };
+    Some(logged)
+}
+
+// Commands logged to the file at path, None when there is no such file. A
+// command cut short at the end, as left by a crash in the middle of a write,
+// is dropped.
+pub fn read_commands(path: &Path, limits: ParseLimits) -> io::Result<Option<Vec<Frame>>> {
+    let mut buf = match std::fs::read(path) {
+        Ok(content) => BytesMut::from(&content[..]),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut codec = FrameCodec::with_limits(RespVersion::V2, limits);
+    let mut commands = Vec::new();
+    while let Some(frame) = codec
+        .decode(&mut buf)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+    {
+        commands.push(frame);
+    }
+    if !buf.is_empty() {
+        eprintln!(
+            "Ignoring {} bytes of truncated command at the end of {}",
+            buf.len(),
+            path.display()
+        );
+    }
+    Ok(Some(commands))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, time::Duration};
+
+    use bytes::Bytes;
+
+    use super::{read_commands, Aof};
+    use crate::{
+        cmd,
+        command::{send_command, setup_command_test, temporary_dir},
+        config::AppendFsync,
+        resp::types::{Frame, ParseLimits},
+        server::Server,
+        store::Value,
+    };
+
+    #[test]
+    fn test_append_selects_db() {
+        let path = temporary_dir("aof-select").join("appendonly.aof");
+        let mut aof = Aof::open(&path, AppendFsync::Always).unwrap();
+
+        aof.append(0, &cmd!("SET", "a", "1")).unwrap();
+        aof.append(0, &cmd!("SET", "b", "2")).unwrap();
+        aof.append(3, &cmd!("DEL", "a")).unwrap();
+
+        assert_eq!(
+            Some(vec![
+                cmd!("SELECT", "0"),
+                cmd!("SET", "a", "1"),
+                cmd!("SET", "b", "2"),
+                cmd!("SELECT", "3"),
+                cmd!("DEL", "a"),
+            ]),
+            read_commands(&path, ParseLimits::default()).unwrap()
+        );
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_read_truncated() {
+        let path = temporary_dir("aof-truncated").join("appendonly.aof");
+        let mut file = std::fs::File::create(&path).unwrap();
+        file.write_all(b"*2\r\n$3\r\nDEL\r\n$1\r\na\r\n*2\r\n$3\r\nDEL")
+            .unwrap();
+
+        assert_eq!(
+            Some(vec![cmd!("DEL", "a")]),
+            read_commands(&path, ParseLimits::default()).unwrap()
+        );
+        assert_eq!(
+            None,
+            read_commands(&path.with_extension("missing"), ParseLimits::default()).unwrap()
+        );
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replay_rebuilds_keyspace() {
+        let dir = temporary_dir("aof-replay");
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("set"));
+        server.config.appendonly = true;
+        server.config.dir = dir.to_string_lossy().into_owned();
+        server.load().await.unwrap();
+
+        for frame in [
+            cmd!("SET", "string", "value"),
+            cmd!("GET", "string"),
+            cmd!("RPUSH", "list", "a", "b"),
+            cmd!("BLPOP", "list", "0"),
+            cmd!("INCR", "counter"),
+            cmd!("INCR", "counter"),
+            cmd!("SADD", "set", "a"),
+            cmd!("SELECT", "2"),
+            cmd!("HSET", "hash", "field", "value"),
+            cmd!("MULTI"),
+            cmd!("ZADD", "zset", "1.5", "a"),
+            cmd!("DEL", "hash"),
+            cmd!("EXEC"),
+        ] {
+            send_command(&mut server, &mut receiver, &request, frame).await;
+        }
+        let expected: Vec<_> = server.dbs.iter().map(|db| db.entries()).collect();
+
+        // As another server starting up with the same configuration
+        let mut restarted = Server::with_config("0.0.0.0".into(), 0, server.config.clone());
+        drop(server);
+        restarted.load().await.unwrap();
+
+        for (mut expected, db) in expected.into_iter().zip(restarted.dbs.iter()) {
+            let mut entries = db.entries();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            expected.sort_by(|a, b| a.0.cmp(&b.0));
+            assert_eq!(expected, entries);
+        }
+        assert_eq!(
+            Some(Value::String(Bytes::from("2"))),
+            restarted.dbs[0].get(b"counter")
+        );
+        assert_eq!(
+            Some(Value::List([Bytes::from("b")].into())),
+            restarted.dbs[0].get(b"list")
+        );
+        assert!(restarted.dbs[2].get(b"zset").is_some());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replay_keeps_deadlines() {
+        let dir = temporary_dir("aof-deadlines");
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("set"));
+        server.config.appendonly = true;
+        server.config.dir = dir.to_string_lossy().into_owned();
+        server.load().await.unwrap();
+
+        for frame in [
+            cmd!("SET", "set", "value", "PX", "50"),
+            cmd!("PSETEX", "psetex", "50", "value"),
+            cmd!("SET", "getex", "value"),
+            cmd!("GETEX", "getex", "PX", "50"),
+            cmd!("SET", "expire", "value"),
+            cmd!("EXPIRE", "expire", "100"),
+        ] {
+            send_command(&mut server, &mut receiver, &request, frame).await;
+        }
+        let config = server.config.clone();
+        drop(server);
+
+        // Relative expirations are logged as unix times
+        let commands = read_commands(&config.aof_path(), ParseLimits::default())
+            .unwrap()
+            .unwrap();
+        let Frame::Array(expire) = commands.last().unwrap() else {
+            panic!("expected the logged EXPIRE");
+        };
+        assert_eq!(Frame::Bulk(Bytes::from("PEXPIREAT")), expire[0]);
+
+        // Restarting after the deadlines doesn't bring the keys back
+        std::thread::sleep(Duration::from_millis(100));
+        let mut restarted = Server::with_config("0.0.0.0".into(), 0, config);
+        restarted.load().await.unwrap();
+
+        for key in ["set", "psetex", "getex"] {
+            assert_eq!(None, restarted.dbs[0].get(key.as_bytes()), "{}", key);
+        }
+        let ttl = restarted.dbs[0].ttl(b"expire").unwrap().unwrap();
+        assert!(ttl > Duration::from_secs(98) && ttl < Duration::from_secs(100));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_only_changes_are_logged() {
+        let dir = temporary_dir("aof-changes");
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("set"));
+        server.config.appendonly = true;
+        server.config.dir = dir.to_string_lossy().into_owned();
+        server.load().await.unwrap();
+
+        for frame in [
+            cmd!("SET", "key", "value", "NX"),
+            // Writes failing or with nothing to do
+            cmd!("SET", "key", "other", "NX"),
+            cmd!("LPUSH", "key", "a"),
+            cmd!("INCR", "key"),
+            cmd!("DEL", "missing"),
+            cmd!("SREM", "missing", "a"),
+            cmd!("DEL", "key"),
+        ] {
+            send_command(&mut server, &mut receiver, &request, frame).await;
+        }
+        let config = server.config.clone();
+        drop(server);
+
+        assert_eq!(
+            Some(vec![
+                cmd!("SELECT", "0"),
+                cmd!("SET", "key", "value", "NX"),
+                cmd!("DEL", "key"),
+            ]),
+            read_commands(&config.aof_path(), ParseLimits::default()).unwrap()
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,179 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bytes::Bytes;
+use tokio::sync::mpsc;
+
+use crate::{
+    messages::{ConnectionMessage, Request, ServerMessage},
+    resp::types::Frame,
+};
+
+// A client waiting in BLPOP for one of its keys to hold a list, or in DEBUG
+// SLEEP for its time to pass
+#[derive(Debug)]
+pub struct BlockedClient {
+    pub client_id: u64,
+    // Tells this wait apart from later ones of the same client, so a timeout
+    // firing late doesn't end the wrong one
+    pub token: u64,
+    pub db: usize,
+    pub keys: Vec<Bytes>,
+    pub connection: mpsc::Sender<ServerMessage>,
+    // What the client is told when the wait times out
+    pub timeout_reply: Frame,
+    // Requests the client sent while blocked, run once it's served
+    pub deferred: VecDeque<Request>,
+}
+
+// Clients blocked on lists, in the order they blocked, which is the order
+// they're served in
+#[derive(Debug, Default)]
+pub struct Blocked {
+    clients: Vec<BlockedClient>,
+    next_token: u64,
+}
+
+impl Blocked {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Block the client until one of the keys can be popped, returning the
+    // token of the wait
+    pub fn block(
+        &mut self,
+        client_id: u64,
+        db: usize,
+        keys: Vec<Bytes>,
+        connection: mpsc::Sender<ServerMessage>,
+    ) -> u64 {
+        self.push(client_id, db, keys, connection, Frame::Null)
+    }
+
+    // Park the client until its wait times out, with nothing to serve it
+    // before, returning the token of the wait
+    pub fn pause(&mut self, client_id: u64, connection: mpsc::Sender<ServerMessage>) -> u64 {
+        self.push(client_id, 0, Vec::new(), connection, Frame::ok())
+    }
+
+    fn push(
+        &mut self,
+        client_id: u64,
+        db: usize,
+        keys: Vec<Bytes>,
+        connection: mpsc::Sender<ServerMessage>,
+        timeout_reply: Frame,
+    ) -> u64 {
+        let token = self.next_token;
+        self.next_token += 1;
+        self.clients.push(BlockedClient {
+            client_id,
+            token,
+            db,
+            keys,
+            connection,
+            timeout_reply,
+            deferred: VecDeque::new(),
+        });
+        token
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+
+    pub fn is_blocked(&self, client_id: u64) -> bool {
+        self.clients
+            .iter()
+            .any(|client| client.client_id == client_id)
+    }
+
+    // Keep the request for when its client is unblocked. Returns it back if
+    // the client isn't blocked.
+    pub fn defer(&mut self, request: Request) -> Option<Request> {
+        match self
+            .clients
+            .iter_mut()
+            .find(|client| client.client_id == request.client_id)
+        {
+            Some(client) => {
+                client.deferred.push_back(request);
+                None
+            }
+            None => Some(request),
+        }
+    }
+
+    // Stop the wait with this token, if it's still going
+    pub fn unblock(&mut self, client_id: u64, token: u64) -> Option<BlockedClient> {
+        let index = self
+            .clients
+            .iter()
+            .position(|client| client.client_id == client_id && client.token == token)?;
+        Some(self.clients.remove(index))
+    }
+
+    // Unblock the first client, in the order they blocked, which `serve`
+    // managed to serve
+    pub fn serve_first(
+        &mut self,
+        serve: impl FnMut(&BlockedClient) -> bool,
+    ) -> Option<BlockedClient> {
+        let index = self.clients.iter().position(serve)?;
+        Some(self.clients.remove(index))
+    }
+
+    pub fn remove_client(&mut self, client_id: u64) {
+        self.clients.retain(|client| client.client_id != client_id);
+    }
+}
+
+// Tell the server the wait with this token timed out once the timeout
+// elapses
+pub fn time_out(
+    sender: mpsc::Sender<ConnectionMessage>,
+    client_id: u64,
+    token: u64,
+    timeout: Duration,
+) {
+    tokio::spawn(async move {
+        tokio::time::sleep(timeout).await;
+        // The server is gone if this fails, and the client with it
+        let _ = sender
+            .send(ConnectionMessage::BlockTimeout { client_id, token })
+            .await;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use tokio::sync::mpsc;
+
+    use super::Blocked;
+    use crate::{cmd, messages::Request};
+
+    #[test]
+    fn test_block_and_unblock() {
+        let (sender, _receiver) = mpsc::channel(1);
+        let mut blocked = Blocked::new();
+        let first = blocked.block(1, 0, vec![Bytes::from("a")], sender.clone());
+        let second = blocked.block(2, 0, vec![Bytes::from("a")], sender.clone());
+        assert!(blocked.is_blocked(1));
+
+        // A stale token leaves the wait alone
+        assert!(blocked.unblock(1, second).is_none());
+        assert_eq!(1, blocked.unblock(1, first).unwrap().client_id);
+        assert!(!blocked.is_blocked(1));
+
+        let request = Request {
+            client_id: 2,
+            frame: cmd!("PING"),
+            connection: sender.clone(),
+        };
+        assert!(blocked.defer(request).is_none());
+        let served = blocked.serve_first(|client| client.client_id == 2).unwrap();
+        assert_eq!(1, served.deferred.len());
+        assert!(blocked.is_empty());
+    }
+}
@@ -0,0 +1,135 @@
+use thiserror::Error;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufWriter, ReadHalf, WriteHalf},
+    net::{TcpStream, ToSocketAddrs},
+};
+
+use crate::resp::{
+    error::FrameParsingError,
+    reader::FrameReader,
+    types::{Frame, RespVersion},
+};
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Invalid reply: {0}")]
+    InvalidReply(#[from] FrameParsingError),
+    #[error("Connection closed by the server")]
+    Closed,
+}
+
+// Client side of a connection with a RESP server. Commands are frames, e.g.
+// built with `cmd!`, and error replies are returned as `Frame::Error` like
+// any other reply.
+pub struct Client<S = TcpStream>
+where
+    S: AsyncRead + AsyncWrite,
+{
+    reader: FrameReader<ReadHalf<S>>,
+    writer: BufWriter<WriteHalf<S>>,
+}
+
+impl Client {
+    pub async fn connect(address: impl ToSocketAddrs) -> Result<Self, ClientError> {
+        Ok(Self::new(TcpStream::connect(address).await?))
+    }
+}
+
+impl<S> Client<S>
+where
+    S: AsyncRead + AsyncWrite,
+{
+    pub fn new(stream: S) -> Self {
+        let (reader, writer) = tokio::io::split(stream);
+        Self {
+            reader: FrameReader::new(reader),
+            writer: BufWriter::new(writer),
+        }
+    }
+
+    // Send the command and wait for its reply
+    pub async fn send_command(&mut self, command: Frame) -> Result<Frame, ClientError> {
+        self.write_command(&command).await?;
+        self.writer.flush().await?;
+        self.read_reply().await
+    }
+
+    // Send all the commands in a single write, then read their replies in
+    // the same order
+    pub async fn send_pipeline(&mut self, commands: &[Frame]) -> Result<Vec<Frame>, ClientError> {
+        for command in commands {
+            self.write_command(command).await?;
+        }
+        self.writer.flush().await?;
+        let mut replies = Vec::with_capacity(commands.len());
+        for _ in commands {
+            replies.push(self.read_reply().await?);
+        }
+        Ok(replies)
+    }
+
+    // Next frame sent by the server, e.g. a message after SUBSCRIBE
+    pub async fn read_reply(&mut self) -> Result<Frame, ClientError> {
+        self.reader.read_frame().await?.ok_or(ClientError::Closed)
+    }
+
+    // Requests are arrays of bulk strings, encoded the same in both versions
+    async fn write_command(&mut self, command: &Frame) -> Result<(), ClientError> {
+        let encoded = command.encode(RespVersion::V2);
+        self.writer.write_all(&encoded).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::{Client, ClientError};
+    use crate::{cmd, resp::types::Frame};
+
+    #[tokio::test]
+    async fn test_send_pipeline() {
+        let (client, mut server) = tokio::io::duplex(256);
+        let mut client = Client::new(client);
+
+        server
+            .write_all(b"+OK\r\n-ERR unknown command 'NOPE'\r\n$5\r\nvalue\r\n")
+            .await
+            .unwrap();
+        let replies = client
+            .send_pipeline(&[
+                cmd!("SET", "key", "value"),
+                cmd!("NOPE"),
+                cmd!("GET", "key"),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            vec![
+                Frame::ok(),
+                Frame::Error("ERR unknown command 'NOPE'".into()),
+                Frame::Bulk("value".into()),
+            ],
+            replies
+        );
+        let mut buf = [0; 9];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(b"*3\r\n$3\r\nS", &buf);
+    }
+
+    #[tokio::test]
+    async fn test_connection_closed() {
+        let (client, server) = tokio::io::duplex(64);
+        let mut client = Client::new(client);
+        drop(server);
+
+        assert!(matches!(
+            client.send_command(cmd!("PING")).await,
+            Err(ClientError::Io(_) | ClientError::Closed)
+        ));
+    }
+}
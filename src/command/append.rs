@@ -0,0 +1,69 @@
+use bytes::{Bytes, BytesMut};
+
+use crate::{
+    command::CommandError, messages::Request, resp::types::Frame, server::Server, store::Value,
+};
+
+// Append to the string, a missing key counting as empty, and reply with its
+// new length
+pub async fn command(server: &Server, request: &Request, key: Bytes, suffix: Bytes) {
+    let result = server.db(request).update(key, |value| {
+        let current = match value {
+            Some(Value::String(bytes)) => bytes.clone(),
+            Some(_) => return Err(CommandError::WrongType),
+            None => Bytes::new(),
+        };
+        let mut appended = BytesMut::with_capacity(current.len() + suffix.len());
+        appended.extend_from_slice(&current);
+        appended.extend_from_slice(&suffix);
+        let len = appended.len();
+        *value = Some(Value::String(appended.freeze()));
+        Ok((len, true))
+    });
+
+    match result {
+        Ok(len) => request.data(Frame::Integer(len as i64)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{append::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_append() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("append", "key", "abc"));
+
+        command(&server, &request, Bytes::from("key"), Bytes::from("abc")).await;
+        command(
+            &server,
+            &request,
+            Bytes::from("key"),
+            Bytes::from_static(b"\x00\xff"),
+        )
+        .await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(3))
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(5))
+        );
+        assert_eq!(
+            Some(Value::String(Bytes::from_static(b"abc\x00\xff"))),
+            server.db(&request).get(b"key")
+        );
+    }
+}
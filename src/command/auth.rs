@@ -0,0 +1,122 @@
+use bytes::Bytes;
+
+use crate::{
+    messages::Request,
+    resp::types::Frame,
+    server::{Server, ServerError},
+};
+
+// Authenticate the connection with the password set by requirepass. The
+// only user is "default", which the password alone refers to.
+pub async fn command(
+    server: &mut Server,
+    request: &Request,
+    username: Option<Bytes>,
+    password: Bytes,
+) {
+    if server.config.requirepass.is_empty() {
+        request
+            .error(ServerError::InvalidValue(
+                "AUTH <password> called without any password configured for the default user. \
+                 Are you sure your configuration is correct?"
+                    .into(),
+            ))
+            .await;
+        return;
+    }
+    let user_matches = username.is_none_or(|username| username.as_ref() == b"default");
+    if !user_matches || password.as_ref() != server.config.requirepass.as_bytes() {
+        request.error(ServerError::WrongPass).await;
+        return;
+    }
+    if let Some(client) = server.clients.get_mut(&request.client_id) {
+        client.state.authenticated = true;
+    }
+    request.data(Frame::ok()).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{send_command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::{Frame, RespVersion},
+        server::ServerError,
+    };
+
+    #[tokio::test]
+    async fn test_commands_rejected_before_auth() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("get", "key"));
+        server.config.requirepass = "secret".into();
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        assert_eq!(
+            ServerMessage::Error(ServerError::NoAuth),
+            send(cmd!("GET", "key")).await
+        );
+        assert_eq!(
+            ServerMessage::Error(ServerError::NoAuth),
+            send(cmd!("MULTI")).await
+        );
+        // HELLO goes through, switching the protocol before replying
+        assert_eq!(
+            ServerMessage::Protocol(RespVersion::V2),
+            send(cmd!("HELLO")).await
+        );
+    }
+
+    #[rstest]
+    #[case(cmd!("AUTH", "wrong"))]
+    #[case(cmd!("AUTH", "default", "wrong"))]
+    #[case(cmd!("AUTH", "other", "secret"))]
+    #[tokio::test]
+    async fn test_wrong_password(#[case] auth: Frame) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("auth"));
+        server.config.requirepass = "secret".into();
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        assert_eq!(
+            ServerMessage::Error(ServerError::WrongPass),
+            send(auth).await
+        );
+        assert_eq!(
+            ServerMessage::Error(ServerError::NoAuth),
+            send(cmd!("PING")).await
+        );
+    }
+
+    #[rstest]
+    #[case(cmd!("AUTH", "secret"))]
+    #[case(cmd!("AUTH", "default", "secret"))]
+    #[tokio::test]
+    async fn test_auth(#[case] auth: Frame) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("auth"));
+        server.config.requirepass = "secret".into();
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        assert_eq!(ServerMessage::Data(Frame::ok()), send(auth).await);
+        assert_eq!(
+            ServerMessage::Data(Frame::simple("PONG")),
+            send(cmd!("PING")).await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_auth_without_password() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("auth"));
+
+        let reply =
+            send_command(&mut server, &mut receiver, &request, cmd!("AUTH", "secret")).await;
+
+        assert!(matches!(
+            reply,
+            ServerMessage::Error(ServerError::InvalidValue(_))
+        ));
+    }
+}
@@ -0,0 +1,167 @@
+use bytes::Bytes;
+
+use crate::{
+    command::{index_range, CommandError},
+    messages::Request,
+    resp::types::Frame,
+    server::Server,
+    store::Value,
+};
+
+// Strings as bitmaps: bit 0 is the most significant bit of the first byte
+
+// Byte holding the bit, and the mask selecting it in the byte
+fn position(offset: u64) -> (usize, u8) {
+    ((offset / 8) as usize, 0x80 >> (offset % 8))
+}
+
+// SETBIT: set or clear the bit, growing the string with zero bytes if it's
+// shorter, and reply with the bit's previous value
+pub async fn set(server: &Server, request: &Request, key: Bytes, offset: u64, bit: bool) {
+    let result = server.db(request).update(key, |value| {
+        let mut bytes = match value {
+            Some(Value::String(bytes)) => bytes.to_vec(),
+            Some(_) => return Err(CommandError::WrongType),
+            None => Vec::new(),
+        };
+        let (index, mask) = position(offset);
+        if bytes.len() <= index {
+            bytes.resize(index + 1, 0);
+        }
+        let previous = bytes[index] & mask != 0;
+        if bit {
+            bytes[index] |= mask;
+        } else {
+            bytes[index] &= !mask;
+        }
+        *value = Some(Value::String(Bytes::from(bytes)));
+        Ok((previous, true))
+    });
+
+    match result {
+        Ok(previous) => request.data(Frame::Integer(previous as i64)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+// GETBIT: bits past the end of the string, or of a missing key, are 0
+pub async fn get(server: &Server, request: &Request, key: Bytes, offset: u64) {
+    let (index, mask) = position(offset);
+    let result = server.db(request).view(&key, |value| match value {
+        Some(Value::String(bytes)) => Ok(bytes.get(index).is_some_and(|byte| byte & mask != 0)),
+        Some(_) => Err(CommandError::WrongType),
+        None => Ok(false),
+    });
+
+    match result {
+        Ok(bit) => request.data(Frame::Integer(bit as i64)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+// BITCOUNT: number of set bits in the bytes from `start` to `end` included,
+// with the same index rules as GETRANGE, or in the whole string
+pub async fn count(server: &Server, request: &Request, key: Bytes, range: Option<(i64, i64)>) {
+    let result = server.db(request).view(&key, |value| match value {
+        Some(Value::String(bytes)) => {
+            let (start, end) = range.unwrap_or((0, -1));
+            Ok(index_range(start, end, bytes.len()).map_or(0, |range| {
+                bytes[range].iter().map(|byte| byte.count_ones()).sum()
+            }))
+        }
+        Some(_) => Err(CommandError::WrongType),
+        None => Ok(0),
+    });
+
+    match result {
+        Ok(count) => request.data(Frame::Integer(count as i64)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{send_command, setup_command_test, CommandError},
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::ServerError,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_setbit_getbit() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("setbit"));
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        for offset in ["1", "7", "100"] {
+            assert_eq!(
+                ServerMessage::Data(Frame::Integer(0)),
+                send(cmd!("SETBIT", "key", offset, "1")).await
+            );
+        }
+        assert_eq!(
+            ServerMessage::Data(Frame::Integer(1)),
+            send(cmd!("SETBIT", "key", "7", "0")).await
+        );
+
+        for (offset, expected) in [("0", 0), ("1", 1), ("7", 0), ("100", 1), ("10000", 0)] {
+            assert_eq!(
+                ServerMessage::Data(Frame::Integer(expected)),
+                send(cmd!("GETBIT", "key", offset)).await,
+                "bit {}",
+                offset
+            );
+        }
+        // Grown to hold bit 100, zero padded
+        let mut expected = vec![0u8; 13];
+        expected[0] = 0x40;
+        expected[12] = 0x08;
+        assert_eq!(
+            ServerMessage::Data(Frame::Bulk(Bytes::from(expected))),
+            send(cmd!("GET", "key")).await
+        );
+    }
+
+    #[rstest]
+    #[case(cmd!("BITCOUNT", "key"), 32)]
+    #[case(cmd!("BITCOUNT", "key", "0", "0"), 4)]
+    #[case(cmd!("BITCOUNT", "key", "1", "1"), 6)]
+    #[case(cmd!("BITCOUNT", "key", "-2", "-1"), 16)]
+    #[case(cmd!("BITCOUNT", "key", "5", "10"), 0)]
+    #[case(cmd!("BITCOUNT", "missing"), 0)]
+    #[tokio::test]
+    async fn test_bitcount(#[case] frame: Frame, #[case] expected: i64) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("bitcount"));
+        // 4, 6, 6, 8 and 8 bits set
+        let value = Value::String(Bytes::from_static(b"foo\xff\xff"));
+        server.db(&request).set(Bytes::from("key"), value);
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Integer(expected)),
+            send_command(&mut server, &mut receiver, &request, frame).await
+        );
+    }
+
+    #[rstest]
+    #[case(cmd!("SETBIT", "key", "0", "1"))]
+    #[case(cmd!("GETBIT", "key", "0"))]
+    #[case(cmd!("BITCOUNT", "key"))]
+    #[tokio::test]
+    async fn test_bitmap_wrong_type(#[case] frame: Frame) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("setbit"));
+        let value = Value::List([Bytes::from("a")].into());
+        server.db(&request).set(Bytes::from("key"), value.clone());
+
+        assert_eq!(
+            ServerMessage::Error(ServerError::Command(CommandError::WrongType)),
+            send_command(&mut server, &mut receiver, &request, frame).await
+        );
+        assert_eq!(Some(value), server.db(&request).get(b"key"));
+    }
+}
@@ -0,0 +1,96 @@
+use bytes::Bytes;
+
+use crate::{
+    command::{CommandInfo, COMMANDS},
+    messages::Request,
+    resp::types::Frame,
+    server::Server,
+};
+
+// COMMAND: name, arity, flags and key positions of every supported command
+pub async fn list(_server: &Server, request: &Request) {
+    request
+        .data(Frame::Array(COMMANDS.iter().map(describe).collect()))
+        .await;
+}
+
+// COMMAND COUNT
+pub async fn count(_server: &Server, request: &Request) {
+    request.data(Frame::Integer(COMMANDS.len() as i64)).await;
+}
+
+fn describe(info: &CommandInfo) -> Frame {
+    let (first, last, step) = info.keys;
+    Frame::Array(vec![
+        Frame::Bulk(Bytes::from(info.name.to_lowercase())),
+        Frame::Integer(info.arity),
+        Frame::Set(
+            info.flags
+                .iter()
+                .map(|flag| Frame::Simple(flag.to_string()))
+                .collect(),
+        ),
+        Frame::Integer(first),
+        Frame::Integer(last),
+        Frame::Integer(step),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{send_command, setup_command_test, COMMANDS},
+        messages::ServerMessage,
+        resp::types::Frame,
+    };
+
+    #[tokio::test]
+    async fn test_command_count() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("command"));
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Integer(COMMANDS.len() as i64)),
+            send_command(
+                &mut server,
+                &mut receiver,
+                &request,
+                cmd!("COMMAND", "COUNT")
+            )
+            .await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_command_list() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("command"));
+
+        let ServerMessage::Data(Frame::Array(commands)) =
+            send_command(&mut server, &mut receiver, &request, cmd!("COMMAND")).await
+        else {
+            panic!("expected an array of commands");
+        };
+
+        assert_eq!(COMMANDS.len(), commands.len());
+        let mset = commands
+            .iter()
+            .find(|command| matches!(command, Frame::Array(info) if info[0] == Frame::Bulk(Bytes::from("mset"))))
+            .unwrap();
+        assert_eq!(
+            &Frame::Array(vec![
+                Frame::Bulk(Bytes::from("mset")),
+                Frame::Integer(-3),
+                Frame::Set(vec![
+                    Frame::Simple("write".into()),
+                    Frame::Simple("denyoom".into())
+                ]),
+                Frame::Integer(1),
+                Frame::Integer(-1),
+                Frame::Integer(2),
+            ]),
+            mset
+        );
+    }
+}
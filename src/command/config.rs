@@ -0,0 +1,158 @@
+use bytes::Bytes;
+
+use crate::{
+    messages::{Request, ServerMessage},
+    resp::types::Frame,
+    server::{Server, ServerError},
+};
+
+// CONFIG GET: parameters matching the glob pattern along with their value,
+// flattened to an array under RESP2
+pub async fn get(server: &Server, request: &Request, pattern: Bytes) {
+    let pairs = server
+        .config
+        .matching(&pattern)
+        .into_iter()
+        .map(|(name, value)| (Frame::from(name), Frame::from(value)))
+        .collect();
+    request.data(Frame::Map(pairs)).await;
+}
+
+// CONFIG SET: change the parameter, taking effect right away
+pub async fn set(server: &mut Server, request: &Request, parameter: Bytes, value: Bytes) {
+    let previous = server.config.clone();
+    let result = server.config.set(
+        &String::from_utf8_lossy(&parameter),
+        &String::from_utf8_lossy(&value),
+    );
+    if let Err(error) = result {
+        request
+            .error(ServerError::InvalidValue(error.to_string()))
+            .await;
+        return;
+    }
+
+    if server.config.limits != previous.limits {
+        // Connections waiting on the server can't be waited on in turn
+        for client in server.clients.values() {
+            let message = ServerMessage::Limits(server.config.limits);
+            if let Err(e) = client.sender.try_send(message) {
+                eprintln!("Error sending limits to client {}: {}", client.id, e);
+            }
+        }
+    }
+    if server.config.timeout != previous.timeout {
+        for client in server.clients.values() {
+            let message = ServerMessage::IdleTimeout(server.config.idle_timeout());
+            if let Err(e) = client.sender.try_send(message) {
+                eprintln!("Error sending idle timeout to client {}: {}", client.id, e);
+            }
+        }
+    }
+    if server.config.expiration != previous.expiration {
+        server.restart_expiration_task();
+    }
+    if let Some(aof) = server.aof.as_mut() {
+        aof.fsync = server.config.appendfsync;
+    }
+    request.data(Frame::ok()).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{config, setup_command_test},
+        messages::ServerMessage,
+        resp::types::{Frame, ParseLimits},
+        server::ServerError,
+    };
+
+    #[tokio::test]
+    async fn test_config_set_then_get() {
+        let (mut server, mut connection_receiver, request) =
+            setup_command_test(cmd!("config", "set", "maxmemory", "1mb"));
+
+        config::set(
+            &mut server,
+            &request,
+            Bytes::from("maxmemory"),
+            Bytes::from("1mb"),
+        )
+        .await;
+        config::get(&server, &request, Bytes::from("maxmemory")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::ok())
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Map(vec![(
+                Frame::from("maxmemory"),
+                Frame::from("1048576")
+            )]))
+        );
+        assert_eq!(1048576, server.config.maxmemory);
+    }
+
+    #[tokio::test]
+    async fn test_config_get_pattern() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("config", "get", "proto-*"));
+
+        config::get(&server, &request, Bytes::from("proto-*")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Map(vec![
+                (Frame::from("proto-max-bulk-len"), Frame::from("536870912")),
+                (Frame::from("proto-max-depth"), Frame::from("128")),
+            ]))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_config_set_limits_sent_to_clients() {
+        let (mut server, mut connection_receiver, request) =
+            setup_command_test(cmd!("config", "set", "proto-max-depth", "4"));
+
+        config::set(
+            &mut server,
+            &request,
+            Bytes::from("proto-max-depth"),
+            Bytes::from("4"),
+        )
+        .await;
+
+        let limits = ParseLimits {
+            max_depth: 4,
+            ..ParseLimits::default()
+        };
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Limits(limits)
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::ok())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_config_set_unknown() {
+        let (mut server, mut connection_receiver, request) =
+            setup_command_test(cmd!("config", "set", "nope", "1"));
+
+        config::set(&mut server, &request, Bytes::from("nope"), Bytes::from("1")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Error(ServerError::InvalidValue(
+                "Unknown option or number of arguments for CONFIG SET - 'nope'".into()
+            ))
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use std::convert::Infallible;
+
+use bytes::Bytes;
+
+use crate::{command::CommandError, messages::Request, resp::types::Frame, server::Server};
+
+// COPY: store a copy of the value and its expiration at the destination,
+// in the same database or the one given. Replies 1 if copied, 0 if the
+// source is missing or the destination exists without REPLACE.
+pub async fn command(
+    server: &Server,
+    request: &Request,
+    from: Bytes,
+    to: Bytes,
+    db: Option<i64>,
+    replace: bool,
+) {
+    let source = server.db(request);
+    let destination = match db {
+        None => source,
+        Some(index) => match usize::try_from(index)
+            .ok()
+            .and_then(|index| server.dbs.get(index))
+        {
+            Some(db) => db,
+            None => {
+                request
+                    .error(CommandError::OutOfRange("DB index".into()))
+                    .await;
+                return;
+            }
+        },
+    };
+    if std::ptr::eq(source, destination) && from == to {
+        request
+            .error(CommandError::Invalid(
+                "source and destination objects are the same".into(),
+            ))
+            .await;
+        return;
+    }
+
+    let (Some(value), Some(expire)) = (source.get(&from), source.ttl(&from)) else {
+        request.data(Frame::Integer(0)).await;
+        return;
+    };
+    let Ok(copied) = destination.set_if(to, value, expire, |current| {
+        Ok::<_, Infallible>(replace || current.is_none())
+    });
+    request.data(Frame::Integer(copied as i64)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{send_command, setup_command_test, CommandError},
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::ServerError,
+        store::Value,
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn test_copy_keeps_value_and_ttl() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("copy"));
+        let value = Value::List([Bytes::from("x"), Bytes::from("y")].into());
+        let db = server.db(&request).clone();
+        db.set(Bytes::from("a"), value.clone());
+        db.expire(b"a", Duration::from_secs(10));
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Integer(1)),
+            send_command(&mut server, &mut receiver, &request, cmd!("COPY", "a", "b")).await
+        );
+        assert_eq!(Some(value.clone()), db.get(b"a"));
+        assert_eq!(Some(value), db.get(b"b"));
+        assert_eq!(Some(Some(Duration::from_secs(10))), db.ttl(b"b"));
+    }
+
+    #[tokio::test]
+    async fn test_copy_to_other_db() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("copy"));
+        let value = Value::String(Bytes::from("value"));
+        server.db(&request).set(Bytes::from("a"), value.clone());
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Integer(1)),
+            send_command(
+                &mut server,
+                &mut receiver,
+                &request,
+                cmd!("COPY", "a", "a", "db", "1")
+            )
+            .await
+        );
+        assert_eq!(Some(value.clone()), server.dbs[0].get(b"a"));
+        assert_eq!(Some(value), server.dbs[1].get(b"a"));
+        assert_eq!(Some(None), server.dbs[1].ttl(b"a"));
+    }
+
+    #[rstest]
+    #[case(cmd!("COPY", "a", "b"), 0, "old")]
+    #[case(cmd!("COPY", "a", "b", "REPLACE"), 1, "new")]
+    #[case(cmd!("COPY", "missing", "b", "REPLACE"), 0, "old")]
+    #[tokio::test]
+    async fn test_copy_existing_destination(
+        #[case] frame: Frame,
+        #[case] copied: i64,
+        #[case] left: &'static str,
+    ) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("copy"));
+        let db = server.db(&request).clone();
+        db.set(Bytes::from("a"), Value::String(Bytes::from("new")));
+        db.set(Bytes::from("b"), Value::String(Bytes::from("old")));
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Integer(copied)),
+            send_command(&mut server, &mut receiver, &request, frame).await
+        );
+        assert_eq!(Some(Value::String(Bytes::from(left))), db.get(b"b"));
+    }
+
+    #[rstest]
+    #[case(cmd!("COPY", "a", "b", "DB", "16"), CommandError::OutOfRange("DB index".into()))]
+    #[case(cmd!("COPY", "a", "b", "DB", "-1"), CommandError::OutOfRange("DB index".into()))]
+    #[case(
+        cmd!("COPY", "a", "a"),
+        CommandError::Invalid("source and destination objects are the same".into())
+    )]
+    #[case(
+        cmd!("COPY", "a", "a", "DB", "0"),
+        CommandError::Invalid("source and destination objects are the same".into())
+    )]
+    #[tokio::test]
+    async fn test_copy_invalid(#[case] frame: Frame, #[case] expected: CommandError) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("copy"));
+        server
+            .db(&request)
+            .set(Bytes::from("a"), Value::String(Bytes::from("value")));
+
+        assert_eq!(
+            ServerMessage::Error(ServerError::Command(expected)),
+            send_command(&mut server, &mut receiver, &request, frame).await
+        );
+    }
+}
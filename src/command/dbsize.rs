@@ -0,0 +1,47 @@
+use crate::{messages::Request, resp::types::Frame, server::Server};
+
+// Number of keys in the selected database, leaving out the expired ones
+// which weren't deleted yet
+pub async fn command(server: &Server, request: &Request) {
+    let size = server.db(request).live_len();
+    request.data(Frame::Integer(size as i64)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{dbsize::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn test_dbsize() {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!("dbsize"));
+        for key in ["a", "b", "c"] {
+            server
+                .db(&request)
+                .set(Bytes::from(key), Value::String(Bytes::from("value")));
+        }
+        server.db(&request).expire(b"a", Duration::from_secs(5));
+
+        command(&server, &request).await;
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(3))
+        );
+
+        tokio::time::advance(Duration::from_secs(5)).await;
+        command(&server, &request).await;
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(2))
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+
+use crate::{
+    blocking,
+    command::CommandError,
+    messages::Request,
+    resp::types::Frame,
+    server::{Server, ServerError},
+};
+
+// DEBUG SLEEP: hold the reply for the given time. The client is parked like
+// in BLPOP, so the other clients are served meanwhile.
+pub async fn sleep(server: &mut Server, request: &Request, duration: Duration) {
+    if !server.config.enable_debug_command {
+        request.error(ServerError::DebugDisabled).await;
+        return;
+    }
+    // Commands run by EXEC reply elsewhere than the client's connection, and
+    // can't be parked, so the whole transaction sleeps
+    if !server
+        .clients
+        .get(&request.client_id)
+        .is_some_and(|client| client.sender.same_channel(&request.connection))
+    {
+        tokio::time::sleep(duration).await;
+        request.data(Frame::ok()).await;
+        return;
+    }
+    let token = server
+        .blocked
+        .pause(request.client_id, request.connection.clone());
+    blocking::time_out(server.sender.clone(), request.client_id, token, duration);
+}
+
+// DEBUG OBJECT: how the value at the key is stored, named after the Redis
+// encodings of the same type
+pub async fn object(server: &Server, request: &Request, key: Bytes) {
+    if !server.config.enable_debug_command {
+        request.error(ServerError::DebugDisabled).await;
+        return;
+    }
+    let description = server.db(request).view(&key, |value| {
+        value.map(|value| {
+            format!(
+                "Value refcount:1 encoding:{} memory_usage:{}",
+                value.encoding(),
+                value.memory_usage()
+            )
+        })
+    });
+    match description {
+        Some(description) => request.data(Frame::Simple(description)).await,
+        None => {
+            request
+                .error(CommandError::Invalid("no such key".into()))
+                .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, time::Duration};
+
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{
+            connect_test_client, send_command, setup_command_test, with_frame, CommandError,
+        },
+        messages::{ConnectionMessage, ServerMessage},
+        resp::types::Frame,
+        server::ServerError,
+        store::Value,
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn test_debug_sleep() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("debug"));
+        let (mut other_receiver, other) = connect_test_client(&mut server, 1);
+        server.config.enable_debug_command = true;
+        let start = tokio::time::Instant::now();
+
+        server
+            .run_requests(VecDeque::from([
+                with_frame(&request, cmd!("DEBUG", "SLEEP", "1.5")),
+                // Waits for the sleep to end
+                with_frame(&request, cmd!("ECHO", "awake")),
+                with_frame(&other, cmd!("PING")),
+            ]))
+            .await;
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(
+            ServerMessage::Data(Frame::simple("PONG")),
+            other_receiver.try_recv().unwrap()
+        );
+
+        let Some(ConnectionMessage::BlockTimeout { client_id, token }) =
+            server.receiver.recv().await
+        else {
+            panic!("expected the sleep to end");
+        };
+        assert_eq!(Duration::from_millis(1500), start.elapsed());
+        server.block_timeout(client_id, token).await;
+
+        assert_eq!(
+            ServerMessage::Data(Frame::ok()),
+            receiver.try_recv().unwrap()
+        );
+        assert_eq!(
+            ServerMessage::Data(Frame::Bulk(Bytes::from("awake"))),
+            receiver.try_recv().unwrap()
+        );
+        assert!(server.blocked.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_debug_sleep_in_transaction() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("debug"));
+        server.config.enable_debug_command = true;
+        let start = tokio::time::Instant::now();
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        send(cmd!("MULTI")).await;
+        send(cmd!("DEBUG", "SLEEP", "1.5")).await;
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Array(vec![Frame::ok()])),
+            send(cmd!("EXEC")).await
+        );
+        assert_eq!(Duration::from_millis(1500), start.elapsed());
+        assert!(server.blocked.is_empty());
+    }
+
+    #[rstest]
+    #[case(Value::String(Bytes::from("12345")), "int")]
+    #[case(Value::String(Bytes::from("value")), "embstr")]
+    #[case(Value::String(Bytes::from(vec![b'x'; 45])), "raw")]
+    #[case(Value::List([Bytes::from("a")].into()), "listpack")]
+    #[case(Value::Set([Bytes::from("a")].into()), "listpack")]
+    #[tokio::test]
+    async fn test_debug_object(#[case] value: Value, #[case] expected: &str) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("debug"));
+        server.config.enable_debug_command = true;
+        let usage = value.memory_usage();
+        server.db(&request).set(Bytes::from("key"), value);
+
+        let reply = send_command(
+            &mut server,
+            &mut receiver,
+            &request,
+            cmd!("DEBUG", "OBJECT", "key"),
+        )
+        .await;
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Simple(format!(
+                "Value refcount:1 encoding:{} memory_usage:{}",
+                expected, usage
+            ))),
+            reply
+        );
+        assert_eq!(
+            ServerMessage::Error(ServerError::Command(CommandError::Invalid(
+                "no such key".into()
+            ))),
+            send_command(
+                &mut server,
+                &mut receiver,
+                &request,
+                cmd!("DEBUG", "OBJECT", "missing")
+            )
+            .await
+        );
+    }
+
+    #[rstest]
+    #[case(cmd!("DEBUG", "SLEEP", "0"))]
+    #[case(cmd!("DEBUG", "OBJECT", "key"))]
+    #[tokio::test]
+    async fn test_debug_disabled(#[case] frame: Frame) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("debug"));
+
+        assert_eq!(
+            ServerMessage::Error(ServerError::DebugDisabled),
+            send_command(&mut server, &mut receiver, &request, frame).await
+        );
+    }
+}
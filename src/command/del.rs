@@ -0,0 +1,45 @@
+use bytes::Bytes;
+
+use crate::{messages::Request, resp::types::Frame, server::Server};
+
+// Remove the keys, replying with how many of them existed
+pub async fn command(server: &Server, request: &Request, keys: Vec<Bytes>) {
+    let removed = keys
+        .iter()
+        .filter(|key| server.db(request).remove(key))
+        .count();
+    request.data(Frame::Integer(removed as i64)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{del::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_del() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("del", "a", "b", "missing", "a"));
+        for key in ["a", "b", "c"] {
+            server
+                .db(&request)
+                .set(Bytes::from(key), Value::String(Bytes::from("value")));
+        }
+
+        let keys = ["a", "b", "missing", "a"].into_iter().map(Bytes::from);
+        command(&server, &request, keys.collect()).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(2))
+        );
+        assert_eq!(1, server.db(&request).len());
+    }
+}
@@ -0,0 +1,10 @@
+use crate::{messages::Request, resp::types::Frame, server::Server};
+
+// Drop the commands queued since MULTI, along with the watched keys
+pub async fn command(server: &mut Server, request: &Request) {
+    if let Some(client) = server.clients.get_mut(&request.client_id) {
+        client.state.transaction = None;
+        client.state.watched.clear();
+    }
+    request.data(Frame::ok()).await;
+}
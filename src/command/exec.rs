@@ -0,0 +1,232 @@
+use tokio::{select, sync::mpsc};
+
+use crate::{
+    messages::{Request, ServerMessage},
+    resp::types::Frame,
+    server::{Server, ServerError},
+};
+
+// Run the commands queued since MULTI, replying with the array of their
+// replies. Nothing else runs in between, as the server handles a single
+// request at a time.
+pub async fn command(server: &mut Server, request: &Request) {
+    let Some(client) = server.clients.get_mut(&request.client_id) else {
+        return;
+    };
+    let Some(transaction) = client.state.transaction.take() else {
+        return;
+    };
+    let watched = std::mem::take(&mut client.state.watched);
+    if transaction.failed {
+        request.error(ServerError::ExecAbort).await;
+        return;
+    }
+    // Some watched key changed since WATCH, so nothing runs
+    if watched
+        .iter()
+        .any(|(db, key, version)| server.dbs[*db].version(key) != *version)
+    {
+        request.data(Frame::Null).await;
+        return;
+    }
+
+    // Replies of the queued commands are collected instead of sent, as they
+    // come: a command may send more of them than the channel holds
+    let (sender, mut receiver) = mpsc::channel(32);
+    let mut replies = Vec::with_capacity(transaction.queued.len());
+    for (frame, command) in transaction.queued {
+        let queued = Request {
+            client_id: request.client_id,
+            frame,
+            connection: sender.clone(),
+        };
+        let execution = server.execute(&queued, command);
+        tokio::pin!(execution);
+        let result = loop {
+            select! {
+                biased;
+                Some(message) = receiver.recv() => collect(request, &mut replies, message).await,
+                result = &mut execution => break result,
+            }
+        };
+        while let Ok(message) = receiver.try_recv() {
+            collect(request, &mut replies, message).await;
+        }
+        if let Err(error) = result {
+            replies.push(error.to_frame());
+        }
+    }
+
+    request.data(Frame::Array(replies)).await;
+}
+
+async fn collect(request: &Request, replies: &mut Vec<Frame>, message: ServerMessage) {
+    match message {
+        ServerMessage::Data(frame) => replies.push(frame),
+        ServerMessage::Error(error) => replies.push(error.to_frame()),
+        ServerMessage::Protocol(version) => request.protocol(version).await,
+        // Limits and timeouts are sent to the client's own connection
+        // already, and QUIT is never queued
+        ServerMessage::Limits(_)
+        | ServerMessage::IdleTimeout(_)
+        | ServerMessage::ClientInitialized(_)
+        | ServerMessage::Close => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{send_command, setup_command_test, CommandError},
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::ServerError,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_exec() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("multi"));
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        assert_eq!(ServerMessage::Data(Frame::ok()), send(cmd!("MULTI")).await);
+        let queued = ServerMessage::Data(Frame::simple("QUEUED"));
+        assert_eq!(queued, send(cmd!("SET", "key", "1")).await);
+        assert_eq!(queued, send(cmd!("INCR", "key")).await);
+        assert_eq!(queued, send(cmd!("GET", "key")).await);
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Array(vec![
+                Frame::ok(),
+                Frame::Integer(2),
+                Frame::Bulk(Bytes::from("2")),
+            ])),
+            send(cmd!("EXEC")).await
+        );
+        assert!(server.clients[&0].state.transaction.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_exec_collects_errors() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("multi"));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("text")));
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        send(cmd!("MULTI")).await;
+        send(cmd!("INCR", "key")).await;
+        send(cmd!("ECHO", "after")).await;
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Array(vec![
+                Frame::Error("ERR value is not an integer or out of range".into()),
+                Frame::Bulk(Bytes::from("after")),
+            ])),
+            send(cmd!("EXEC")).await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_discard() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("multi"));
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        send(cmd!("MULTI")).await;
+        send(cmd!("SET", "key", "value")).await;
+        assert_eq!(
+            ServerMessage::Data(Frame::ok()),
+            send(cmd!("DISCARD")).await
+        );
+        assert_eq!(
+            ServerMessage::Error(ServerError::ExecWithoutMulti),
+            send(cmd!("EXEC")).await
+        );
+
+        assert_eq!(None, server.db(&request).get(b"key"));
+    }
+
+    #[tokio::test]
+    async fn test_exec_aborted_by_invalid_command() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("multi"));
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        send(cmd!("MULTI")).await;
+        send(cmd!("SET", "key", "value")).await;
+        assert!(matches!(
+            send(cmd!("SET", "key")).await,
+            ServerMessage::Error(ServerError::Command(CommandError::WrongArity(_)))
+        ));
+        assert_eq!(
+            ServerMessage::Error(ServerError::ExecAbort),
+            send(cmd!("EXEC")).await
+        );
+
+        assert_eq!(None, server.db(&request).get(b"key"));
+        assert!(server.clients[&0].state.transaction.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_nested_multi() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("multi"));
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        send(cmd!("MULTI")).await;
+        assert_eq!(
+            ServerMessage::Error(ServerError::NestedMulti),
+            send(cmd!("MULTI")).await
+        );
+        assert_eq!(
+            ServerMessage::Data(Frame::ok()),
+            send(cmd!("DISCARD")).await
+        );
+        assert_eq!(
+            ServerMessage::Error(ServerError::DiscardWithoutMulti),
+            send(cmd!("DISCARD")).await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exec_many_replies() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("multi"));
+        let channels: Vec<Frame> = (0..40)
+            .map(|i| Frame::Bulk(Bytes::from(format!("c{}", i))))
+            .collect();
+        let mut subscribe = vec![Frame::Bulk(Bytes::from("SUBSCRIBE"))];
+        subscribe.extend(channels.iter().cloned());
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        send(cmd!("MULTI")).await;
+        send(Frame::Array(subscribe)).await;
+        let reply = tokio::time::timeout(Duration::from_secs(1), send(cmd!("EXEC")))
+            .await
+            .expect("EXEC shouldn't block");
+
+        let ServerMessage::Data(Frame::Array(replies)) = reply else {
+            panic!("expected the replies, got {:?}", reply);
+        };
+        let expected: Vec<Frame> = channels
+            .into_iter()
+            .enumerate()
+            .map(|(i, channel)| {
+                Frame::Push(vec![
+                    Frame::from("subscribe"),
+                    channel,
+                    Frame::Integer(i as i64 + 1),
+                ])
+            })
+            .collect();
+        assert_eq!(expected, replies);
+    }
+}
@@ -0,0 +1,52 @@
+use bytes::Bytes;
+
+use crate::{messages::Request, resp::types::Frame, server::Server};
+
+// How many of the keys exist, a key given more than once counting each time
+pub async fn command(server: &Server, request: &Request, keys: Vec<Bytes>) {
+    let existing = keys
+        .iter()
+        .filter(|key| server.db(request).view(key, |value| value.is_some()))
+        .count();
+    request.data(Frame::Integer(existing as i64)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{exists::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn test_exists() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("exists", "a", "a", "expired", "missing"));
+        for key in ["a", "expired"] {
+            server
+                .db(&request)
+                .set(Bytes::from(key), Value::String(Bytes::from("value")));
+        }
+        server
+            .db(&request)
+            .expire(b"expired", Duration::from_secs(1));
+        tokio::time::advance(Duration::from_secs(1)).await;
+
+        let keys = ["a", "a", "expired", "missing"]
+            .into_iter()
+            .map(Bytes::from);
+        command(&server, &request, keys.collect()).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(2))
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+
+use crate::{command::CommandError, messages::Request, resp::types::Frame, server::Server};
+
+// EXPIRE: the key expires in `seconds`
+pub async fn command(server: &Server, request: &Request, key: Bytes, seconds: i64) {
+    let Some(millis) = seconds.checked_mul(1000) else {
+        request
+            .error(CommandError::Invalid(
+                "invalid expire time in 'expire' command".into(),
+            ))
+            .await;
+        return;
+    };
+    expire_in(server, request, key, millis).await;
+}
+
+// EXPIREAT and PEXPIREAT: the key expires at a unix time in milliseconds.
+// The deadline is turned into a delay from the wall clock, after which the
+// key expires like any other.
+pub async fn at(server: &Server, request: &Request, key: Bytes, unix_millis: i64) {
+    expire_in(server, request, key, millis_until(unix_millis)).await;
+}
+
+// Milliseconds from now to a unix time in milliseconds, negative if it's past
+pub(crate) fn millis_until(unix_millis: i64) -> i64 {
+    unix_millis.saturating_sub(unix_millis_now())
+}
+
+pub(crate) fn unix_millis_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as i64)
+}
+
+// Make the key expire in `millis`, replying whether it exists. A timeout in
+// the past deletes the key right away.
+async fn expire_in(server: &Server, request: &Request, key: Bytes, millis: i64) {
+    let updated = if millis <= 0 {
+        server.db(request).remove(&key)
+    } else {
+        server
+            .db(request)
+            .expire(&key, Duration::from_millis(millis as u64))
+    };
+
+    request.data(Frame::Integer(updated as i64)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{
+            expire::{at, command},
+            setup_command_test,
+        },
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn test_expire() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("expire", "key", "10"));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("value")));
+
+        command(&server, &request, Bytes::from("key"), 10).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(1))
+        );
+        assert_eq!(
+            Some(Some(Duration::from_secs(10))),
+            server.db(&request).ttl(b"key")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expire_missing() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("expire", "key", "10"));
+
+        command(&server, &request, Bytes::from("key"), 10).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(0))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expire_in_the_past_deletes() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("expire", "key", "-1"));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("value")));
+
+        command(&server, &request, Bytes::from("key"), -1).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(1))
+        );
+        assert_eq!(None, server.db(&request).get(b"key"));
+    }
+
+    #[tokio::test]
+    async fn test_expire_overflow() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("expire", "key", "9223372036854775807"));
+
+        command(&server, &request, Bytes::from("key"), i64::MAX).await;
+
+        assert!(matches!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Error(_)
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_expireat() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("pexpireat", "key", "0"));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("value")));
+        let in_a_minute = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64
+            + 60_000;
+
+        at(&server, &request, Bytes::from("key"), in_a_minute).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(1))
+        );
+        let ttl = server.db(&request).ttl(b"key").unwrap().unwrap();
+        assert!(ttl > Duration::from_secs(59) && ttl <= Duration::from_secs(60));
+
+        tokio::time::advance(Duration::from_secs(60)).await;
+        assert_eq!(None, server.db(&request).get(b"key"));
+    }
+
+    #[tokio::test]
+    async fn test_expireat_in_the_past_deletes() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("expireat", "key", "1"));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("value")));
+
+        at(&server, &request, Bytes::from("key"), 1000).await;
+        at(&server, &request, Bytes::from("missing"), 1000).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(1))
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(0))
+        );
+        assert_eq!(None, server.db(&request).get(b"key"));
+        assert_eq!(0, server.db(&request).len());
+    }
+}
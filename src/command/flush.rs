@@ -0,0 +1,64 @@
+use crate::{messages::Request, resp::types::Frame, server::Server};
+
+// FLUSHDB: delete every key of the selected database
+pub async fn db(server: &Server, request: &Request) {
+    server.db(request).clear();
+    request.data(Frame::ok()).await;
+}
+
+// FLUSHALL: delete every key of every database. Subscriptions aren't part
+// of the keyspace, so subscribers stay connected.
+pub async fn all(server: &Server, request: &Request) {
+    for db in server.dbs.iter() {
+        db.clear();
+    }
+    request.data(Frame::ok()).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{flush, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_flushdb_keeps_other_dbs() {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!("flushdb"));
+        server
+            .db(&request)
+            .set(Bytes::from("a"), Value::String(Bytes::from("1")));
+        server.dbs[1].set(Bytes::from("b"), Value::String(Bytes::from("2")));
+
+        flush::db(&server, &request).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::ok())
+        );
+        assert_eq!(0, server.db(&request).len());
+        assert_eq!(1, server.dbs[1].len());
+    }
+
+    #[tokio::test]
+    async fn test_flushall() {
+        let (mut server, mut connection_receiver, request) = setup_command_test(cmd!("flushall"));
+        server.dbs[0].set(Bytes::from("a"), Value::String(Bytes::from("1")));
+        server.dbs[1].set(Bytes::from("b"), Value::String(Bytes::from("2")));
+        server.pubsub.subscribe(0, Bytes::from("news"));
+
+        flush::all(&server, &request).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::ok())
+        );
+        assert!(server.dbs.iter().all(|db| db.is_empty()));
+        assert_eq!(vec![0], server.pubsub.subscribers(b"news"));
+    }
+}
@@ -0,0 +1,53 @@
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError, messages::Request, resp::types::Frame, server::Server, store::Value,
+};
+
+pub async fn command(server: &Server, request: &Request, key: Bytes) {
+    match server.db(request).get(&key) {
+        Some(Value::String(value)) => request.data(Frame::Bulk(value)).await,
+        Some(_) => request.error(CommandError::WrongType).await,
+        None => request.data(Frame::Null).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{get::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_get_existing() {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!("get", "key"));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("value")));
+
+        command(&server, &request, Bytes::from("key")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Bulk("value".into()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_missing() {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!("get", "key"));
+
+        command(&server, &request, Bytes::from("key")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Null)
+        );
+    }
+}
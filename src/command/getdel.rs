@@ -0,0 +1,70 @@
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError, messages::Request, resp::types::Frame, server::Server, store::Value,
+};
+
+// GETDEL: reply with the string stored at the key and delete it
+pub async fn command(server: &Server, request: &Request, key: Bytes) {
+    let result = server.db(request).update(key, |value| match value.take() {
+        Some(Value::String(string)) => Ok((Frame::Bulk(string), true)),
+        Some(other) => {
+            *value = Some(other);
+            Err(CommandError::WrongType)
+        }
+        None => Ok((Frame::Null, false)),
+    });
+
+    match result {
+        Ok(reply) => request.data(reply).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{send_command, setup_command_test, CommandError},
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::ServerError,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_getdel() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("getdel"));
+        let value = Bytes::from_static(b"\x00binary\r\n\xff");
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(value.clone()));
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Bulk(value)),
+            send(cmd!("GETDEL", "key")).await
+        );
+        assert_eq!(
+            ServerMessage::Data(Frame::Null),
+            send(cmd!("GETDEL", "key")).await
+        );
+        assert_eq!(None, server.db(&request).get(b"key"));
+    }
+
+    #[tokio::test]
+    async fn test_getdel_wrong_type() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("getdel"));
+        let list = Value::List([Bytes::from("a")].into());
+        server.db(&request).set(Bytes::from("key"), list.clone());
+
+        assert_eq!(
+            ServerMessage::Error(ServerError::Command(CommandError::WrongType)),
+            send_command(&mut server, &mut receiver, &request, cmd!("GETDEL", "key")).await
+        );
+        assert_eq!(Some(list), server.db(&request).get(b"key"));
+    }
+}
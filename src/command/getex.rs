@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+
+use crate::{
+    command::{expire::millis_until, CommandError},
+    messages::Request,
+    resp::types::Frame,
+    server::Server,
+    store::Value,
+};
+
+// What GETEX does to the expiration of the key
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GetExpire {
+    // No option: leave it alone
+    #[default]
+    Keep,
+    // EX and PX
+    In(Duration),
+    // EXAT and PXAT, a unix time in milliseconds
+    At(i64),
+    // PERSIST
+    Persist,
+}
+
+// GETEX: reply with the string stored at the key like GET, updating its
+// expiration
+pub async fn command(server: &Server, request: &Request, key: Bytes, expire: GetExpire) {
+    let db = server.db(request);
+    let value = match db.get(&key) {
+        Some(Value::String(value)) => value,
+        Some(_) => return request.error(CommandError::WrongType).await,
+        None => return request.data(Frame::Null).await,
+    };
+
+    match expire {
+        GetExpire::Keep => {}
+        GetExpire::In(after) => {
+            db.expire(&key, after);
+        }
+        // A deadline in the past deletes the key right away
+        GetExpire::At(unix_millis) => match millis_until(unix_millis) {
+            millis if millis > 0 => {
+                db.expire(&key, Duration::from_millis(millis as u64));
+            }
+            _ => {
+                db.remove(&key);
+            }
+        },
+        GetExpire::Persist => {
+            db.persist(&key);
+        }
+    }
+    request.data(Frame::Bulk(value)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{send_command, setup_command_test, CommandError},
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::ServerError,
+        store::Value,
+    };
+
+    fn unix_millis_in(after: Duration) -> String {
+        let at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap() + after;
+        at.as_millis().to_string()
+    }
+
+    #[rstest]
+    #[case(cmd!("GETEX", "key"), Some(Duration::from_secs(100)))]
+    #[case(cmd!("GETEX", "key", "EX", "10"), Some(Duration::from_secs(10)))]
+    #[case(cmd!("GETEX", "key", "px", "1500"), Some(Duration::from_millis(1500)))]
+    #[case(cmd!("GETEX", "key", "PERSIST"), None)]
+    #[tokio::test(start_paused = true)]
+    async fn test_getex(#[case] frame: Frame, #[case] ttl: Option<Duration>) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("getex"));
+        let value = Bytes::from_static(b"\x00binary\xff");
+        let db = server.db(&request).clone();
+        db.set(Bytes::from("key"), Value::String(value.clone()));
+        db.expire(b"key", Duration::from_secs(100));
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Bulk(value.clone())),
+            send_command(&mut server, &mut receiver, &request, frame).await
+        );
+        assert_eq!(Some(Value::String(value)), db.get(b"key"));
+        assert_eq!(Some(ttl), db.ttl(b"key"));
+    }
+
+    #[tokio::test]
+    async fn test_getex_at() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("getex"));
+        let db = server.db(&request).clone();
+        db.set(Bytes::from("key"), Value::String(Bytes::from("value")));
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        let at = unix_millis_in(Duration::from_secs(60));
+        send(cmd!("GETEX", "key", "PXAT", at)).await;
+        let ttl = db.ttl(b"key").unwrap().unwrap();
+        assert!(ttl > Duration::from_secs(58) && ttl <= Duration::from_secs(60));
+
+        // A deadline in the past deletes the key, after replying with it
+        assert_eq!(
+            ServerMessage::Data(Frame::Bulk(Bytes::from("value"))),
+            send(cmd!("GETEX", "key", "EXAT", "1")).await
+        );
+        assert_eq!(None, db.get(b"key"));
+    }
+
+    #[rstest]
+    #[case(cmd!("GETEX", "missing", "EX", "10"), ServerMessage::Data(Frame::Null))]
+    #[case(
+        cmd!("GETEX", "list", "PERSIST"),
+        ServerMessage::Error(ServerError::Command(CommandError::WrongType))
+    )]
+    #[tokio::test]
+    async fn test_getex_not_a_string(#[case] frame: Frame, #[case] expected: ServerMessage) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("getex"));
+        server
+            .db(&request)
+            .set(Bytes::from("list"), Value::List([Bytes::from("a")].into()));
+
+        assert_eq!(
+            expected,
+            send_command(&mut server, &mut receiver, &request, frame).await
+        );
+        assert_eq!(None, server.db(&request).get(b"missing"));
+    }
+}
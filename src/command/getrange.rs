@@ -0,0 +1,74 @@
+use bytes::Bytes;
+
+use crate::{
+    command::{index_range, CommandError},
+    messages::Request,
+    resp::types::Frame,
+    server::Server,
+    store::Value,
+};
+
+// Bytes of the string from `start` to `end` included, with the same index
+// rules as LRANGE
+pub async fn command(server: &Server, request: &Request, key: Bytes, start: i64, end: i64) {
+    let result = server.db(request).view(&key, |value| match value {
+        Some(Value::String(bytes)) => Ok(index_range(start, end, bytes.len())
+            .map(|range| bytes.slice(range))
+            .unwrap_or_default()),
+        Some(_) => Err(CommandError::WrongType),
+        None => Ok(Bytes::new()),
+    });
+
+    match result {
+        Ok(bytes) => request.data(Frame::Bulk(bytes)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{getrange::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[rstest]
+    #[case("key", 0, 3, "This")]
+    #[case("key", -3, -1, "ing")]
+    #[case("key", 0, -1, "This is a string")]
+    #[case("key", 10, 100, "string")]
+    #[case("key", -100, 3, "This")]
+    #[case("key", 5, 2, "")]
+    #[case("missing", 0, -1, "")]
+    #[tokio::test]
+    async fn test_getrange(
+        #[case] key: &str,
+        #[case] start: i64,
+        #[case] end: i64,
+        #[case] expected: &str,
+    ) {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!(
+            "getrange",
+            key.to_string(),
+            start.to_string(),
+            end.to_string()
+        ));
+        server.db(&request).set(
+            Bytes::from("key"),
+            Value::String(Bytes::from("This is a string")),
+        );
+
+        command(&server, &request, Bytes::from(key.to_string()), start, end).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::from(expected))
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError, messages::Request, resp::types::Frame, server::Server, store::Value,
+};
+
+// Remove the fields from the hash, deleting the key once the hash is empty,
+// and reply with how many fields were removed
+pub async fn command(server: &Server, request: &Request, key: Bytes, fields: Vec<Bytes>) {
+    let result = server.db(request).update(key, |value| {
+        let hash = match value {
+            Some(Value::Hash(hash)) => hash,
+            Some(_) => return Err(CommandError::WrongType),
+            None => return Ok((0, false)),
+        };
+        let removed = fields
+            .iter()
+            .filter(|field| hash.remove(*field).is_some())
+            .count();
+        if hash.is_empty() {
+            *value = None;
+        }
+        Ok((removed, removed > 0))
+    });
+
+    match result {
+        Ok(removed) => request.data(Frame::Integer(removed as i64)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{hdel::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_hdel() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("hdel", "key", "a", "missing"));
+        server.db(&request).set(
+            Bytes::from("key"),
+            Value::Hash(
+                [
+                    (Bytes::from("a"), Bytes::from("1")),
+                    (Bytes::from("b"), Bytes::from("2")),
+                ]
+                .into(),
+            ),
+        );
+
+        let fields = vec![Bytes::from("a"), Bytes::from("missing"), Bytes::from("a")];
+        command(&server, &request, Bytes::from("key"), fields).await;
+        command(
+            &server,
+            &request,
+            Bytes::from("key"),
+            vec![Bytes::from("b")],
+        )
+        .await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(1))
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(1))
+        );
+        assert_eq!(None, server.db(&request).get(b"key"));
+    }
+}
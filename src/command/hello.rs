@@ -0,0 +1,112 @@
+use crate::{
+    messages::Request,
+    resp::types::{Frame, RespVersion},
+    server::{Server, ServerError},
+};
+
+// Switch the connection to the requested protocol version (keeping the
+// current one without arguments) and describe the server
+pub async fn command(server: &mut Server, request: &Request, protover: Option<i64>) {
+    let Some(client) = server.clients.get_mut(&request.client_id) else {
+        return;
+    };
+    let protocol = match protover {
+        None => client.state.protocol,
+        Some(2) => RespVersion::V2,
+        Some(3) => RespVersion::V3,
+        Some(_) => {
+            request.error(ServerError::UnsupportedProtocol).await;
+            return;
+        }
+    };
+    client.state.protocol = protocol;
+
+    let proto = match protocol {
+        RespVersion::V2 => 2,
+        RespVersion::V3 => 3,
+    };
+    request.protocol(protocol).await;
+    request
+        .data(Frame::Map(vec![
+            (Frame::from("server"), Frame::from("yarrs")),
+            (
+                Frame::from("version"),
+                Frame::from(env!("CARGO_PKG_VERSION")),
+            ),
+            (Frame::from("proto"), Frame::Integer(proto)),
+            (Frame::from("id"), Frame::Integer(request.client_id as i64)),
+            (Frame::from("mode"), Frame::from("standalone")),
+            (Frame::from("role"), Frame::from("master")),
+            (Frame::from("modules"), Frame::Array(vec![])),
+        ]))
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cmd,
+        command::{hello::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::{Frame, RespVersion},
+        server::ServerError,
+    };
+
+    #[tokio::test]
+    async fn test_hello_3() {
+        let (mut server, mut connection_receiver, request) = setup_command_test(cmd!("hello", "3"));
+
+        command(&mut server, &request, Some(3)).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Protocol(RespVersion::V3)
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Map(vec![
+                (Frame::from("server"), Frame::from("yarrs")),
+                (
+                    Frame::from("version"),
+                    Frame::from(env!("CARGO_PKG_VERSION"))
+                ),
+                (Frame::from("proto"), Frame::Integer(3)),
+                (Frame::from("id"), Frame::Integer(0)),
+                (Frame::from("mode"), Frame::from("standalone")),
+                (Frame::from("role"), Frame::from("master")),
+                (Frame::from("modules"), Frame::Array(vec![])),
+            ]))
+        );
+        assert_eq!(RespVersion::V3, server.clients[&0].state.protocol);
+    }
+
+    #[tokio::test]
+    async fn test_hello_keeps_protocol() {
+        let (mut server, mut connection_receiver, request) = setup_command_test(cmd!("hello"));
+
+        command(&mut server, &request, None).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Protocol(RespVersion::V2)
+        );
+        assert!(matches!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Map(fields))
+                if fields[2] == (Frame::from("proto"), Frame::Integer(2))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_hello_unsupported_version() {
+        let (mut server, mut connection_receiver, request) = setup_command_test(cmd!("hello", "4"));
+
+        command(&mut server, &request, Some(4)).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Error(ServerError::UnsupportedProtocol)
+        );
+        assert_eq!(RespVersion::V2, server.clients[&0].state.protocol);
+    }
+}
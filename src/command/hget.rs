@@ -0,0 +1,60 @@
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError, messages::Request, resp::types::Frame, server::Server, store::Value,
+};
+
+pub async fn command(server: &Server, request: &Request, key: Bytes, field: Bytes) {
+    let result = server.db(request).view(&key, |value| match value {
+        Some(Value::Hash(hash)) => Ok(hash.get(&field).cloned()),
+        Some(_) => Err(CommandError::WrongType),
+        None => Ok(None),
+    });
+
+    match result {
+        Ok(Some(value)) => request.data(Frame::Bulk(value)).await,
+        Ok(None) => request.data(Frame::Null).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{hget::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[rstest]
+    #[case("key", "a", Frame::from("1"))]
+    #[case("key", "missing", Frame::Null)]
+    #[case("missing", "a", Frame::Null)]
+    #[tokio::test]
+    async fn test_hget(#[case] key: &str, #[case] field: &str, #[case] expected: Frame) {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("hget", key.to_string(), field.to_string()));
+        server.db(&request).set(
+            Bytes::from("key"),
+            Value::Hash([(Bytes::from("a"), Bytes::from("1"))].into()),
+        );
+
+        command(
+            &server,
+            &request,
+            Bytes::from(key.to_string()),
+            Bytes::from(field.to_string()),
+        )
+        .await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(expected)
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError, messages::Request, resp::types::Frame, server::Server, store::Value,
+};
+
+// Every field of the hash along with its value, in no particular order. The
+// map reply is flattened to an array of fields and values under RESP2.
+pub async fn command(server: &Server, request: &Request, key: Bytes) {
+    let result = server.db(request).view(&key, |value| match value {
+        Some(Value::Hash(hash)) => Ok(hash
+            .iter()
+            .map(|(field, value)| (Frame::Bulk(field.clone()), Frame::Bulk(value.clone())))
+            .collect()),
+        Some(_) => Err(CommandError::WrongType),
+        None => Ok(vec![]),
+    });
+
+    match result {
+        Ok(pairs) => request.data(Frame::Map(pairs)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{hgetall::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::{Frame, RespVersion},
+        store::Value,
+    };
+
+    #[rstest]
+    #[case(RespVersion::V2, "*2\r\n$1\r\na\r\n$1\r\n1\r\n")]
+    #[case(RespVersion::V3, "%1\r\n$1\r\na\r\n$1\r\n1\r\n")]
+    #[tokio::test]
+    async fn test_hgetall(#[case] version: RespVersion, #[case] encoded: &str) {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!("hgetall", "key"));
+        server.db(&request).set(
+            Bytes::from("key"),
+            Value::Hash([(Bytes::from("a"), Bytes::from("1"))].into()),
+        );
+
+        command(&server, &request, Bytes::from("key")).await;
+
+        let Ok(ServerMessage::Data(reply)) = connection_receiver.try_recv() else {
+            panic!("expected a reply");
+        };
+        assert_eq!(
+            Frame::Map(vec![(Frame::from("a"), Frame::from("1"))]),
+            reply
+        );
+        assert_eq!(encoded.as_bytes(), &reply.encode(version)[..]);
+    }
+
+    #[tokio::test]
+    async fn test_hgetall_missing() {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!("hgetall", "key"));
+
+        command(&server, &request, Bytes::from("key")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Map(vec![]))
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+
+use crate::{
+    command::{
+        incr::{add, add_float, format_float, parse_float, parse_integer},
+        CommandError,
+    },
+    messages::Request,
+    resp::types::Frame,
+    server::Server,
+    store::Value,
+};
+
+// Replace the field of the hash with what `increment` makes of its current
+// value (None if missing), creating the hash if needed
+fn update_field<T>(
+    server: &Server,
+    request: &Request,
+    key: Bytes,
+    field: Bytes,
+    increment: impl FnOnce(Option<&Bytes>) -> Result<(T, String), CommandError>,
+) -> Result<T, CommandError> {
+    server.db(request).update(key, |value| {
+        let hash = match value.get_or_insert_with(|| Value::Hash(HashMap::new())) {
+            Value::Hash(hash) => hash,
+            _ => return Err(CommandError::WrongType),
+        };
+        let (updated, stored) = match increment(hash.get(&field)) {
+            Ok(result) => result,
+            Err(error) => {
+                // Don't leave behind the hash created for nothing
+                if hash.is_empty() {
+                    *value = None;
+                }
+                return Err(error);
+            }
+        };
+        hash.insert(field, Bytes::from(stored));
+        Ok((updated, true))
+    })
+}
+
+// HINCRBY: add `delta` to the integer stored in the field, starting from 0
+// when the field is missing
+pub async fn command(server: &Server, request: &Request, key: Bytes, field: Bytes, delta: i64) {
+    let result = update_field(server, request, key, field, |current| {
+        let current = match current {
+            Some(bytes) => parse_integer(bytes)
+                .ok_or_else(|| CommandError::Invalid("hash value is not an integer".into()))?,
+            None => 0,
+        };
+        let updated = add(current, delta)?;
+        Ok((updated, updated.to_string()))
+    });
+
+    match result {
+        Ok(updated) => request.data(Frame::Integer(updated)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+// HINCRBYFLOAT: like HINCRBY with floats, replying with the new value as a
+// bulk string
+pub async fn float(server: &Server, request: &Request, key: Bytes, field: Bytes, delta: f64) {
+    let result = update_field(server, request, key, field, |current| {
+        let current = match current {
+            Some(bytes) => parse_float(bytes)
+                .ok_or_else(|| CommandError::Invalid("hash value is not a float".into()))?,
+            None => 0.0,
+        };
+        let updated = format_float(add_float(current, delta)?);
+        Ok((updated.clone(), updated))
+    });
+
+    match result {
+        Ok(updated) => request.data(Frame::Bulk(Bytes::from(updated))).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{send_command, setup_command_test, CommandError},
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::ServerError,
+        store::Value,
+    };
+
+    fn hash(fields: &[(&'static str, &'static str)]) -> Value {
+        Value::Hash(
+            fields
+                .iter()
+                .map(|(field, value)| (Bytes::from(*field), Bytes::from(*value)))
+                .collect(),
+        )
+    }
+
+    #[rstest]
+    #[case(cmd!("HINCRBY", "key", "new", "5"), Frame::Integer(5), "5")]
+    #[case(cmd!("HINCRBY", "key", "count", "-3"), Frame::Integer(7), "7")]
+    #[case(cmd!("HINCRBYFLOAT", "key", "new", "2.5"), Frame::Bulk(Bytes::from("2.5")), "2.5")]
+    #[case(cmd!("HINCRBYFLOAT", "key", "count", "0.5"), Frame::Bulk(Bytes::from("10.5")), "10.5")]
+    #[case(cmd!("HINCRBYFLOAT", "key", "price", "0.5"), Frame::Bulk(Bytes::from("2")), "2")]
+    #[case(cmd!("HINCRBYFLOAT", "key", "price", "-1e3"), Frame::Bulk(Bytes::from("-998.5")), "-998.5")]
+    #[tokio::test]
+    async fn test_hincrby(#[case] frame: Frame, #[case] expected: Frame, #[case] stored: &str) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("hincrby"));
+        server.db(&request).set(
+            Bytes::from("key"),
+            hash(&[("count", "10"), ("price", "1.5")]),
+        );
+        let field = frame.as_array().unwrap()[2].clone();
+
+        assert_eq!(
+            ServerMessage::Data(expected),
+            send_command(&mut server, &mut receiver, &request, frame).await
+        );
+        let Some(Value::Hash(hash)) = server.db(&request).get(b"key") else {
+            panic!("expected a hash");
+        };
+        let Frame::Bulk(field) = field else {
+            panic!("expected a bulk field");
+        };
+        assert_eq!(
+            Some(&Bytes::copy_from_slice(stored.as_bytes())),
+            hash.get(&field)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hincrby_missing_key() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("hincrby"));
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Integer(-2)),
+            send_command(
+                &mut server,
+                &mut receiver,
+                &request,
+                cmd!("HINCRBY", "key", "field", "-2")
+            )
+            .await
+        );
+        assert_eq!(
+            Some(hash(&[("field", "-2")])),
+            server.db(&request).get(b"key")
+        );
+
+        // No empty hash is left behind when the increment fails
+        send_command(
+            &mut server,
+            &mut receiver,
+            &request,
+            cmd!("HINCRBYFLOAT", "other", "field", "inf"),
+        )
+        .await;
+        assert_eq!(None, server.db(&request).get(b"other"));
+    }
+
+    #[rstest]
+    #[case(cmd!("HINCRBY", "key", "text", "1"), "hash value is not an integer")]
+    #[case(cmd!("HINCRBY", "key", "price", "1"), "hash value is not an integer")]
+    #[case(cmd!("HINCRBY", "key", "max", "1"), "increment or decrement would overflow")]
+    #[case(cmd!("HINCRBYFLOAT", "key", "text", "1"), "hash value is not a float")]
+    #[case(cmd!("HINCRBYFLOAT", "key", "price", "inf"), "increment would produce NaN or Infinity")]
+    #[tokio::test]
+    async fn test_hincrby_error(#[case] frame: Frame, #[case] message: &str) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("hincrby"));
+        let fields = hash(&[
+            ("text", "abc"),
+            ("price", "1.5"),
+            ("max", "9223372036854775807"),
+        ]);
+        server.db(&request).set(Bytes::from("key"), fields.clone());
+
+        assert_eq!(
+            ServerMessage::Error(ServerError::Command(CommandError::Invalid(message.into()))),
+            send_command(&mut server, &mut receiver, &request, frame).await
+        );
+        assert_eq!(Some(fields), server.db(&request).get(b"key"));
+    }
+
+    #[rstest]
+    #[case(cmd!("HINCRBY", "key", "field", "1"))]
+    #[case(cmd!("HINCRBYFLOAT", "key", "field", "1.5"))]
+    #[tokio::test]
+    async fn test_hincrby_wrong_type(#[case] frame: Frame) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("hincrby"));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("text")));
+
+        assert_eq!(
+            ServerMessage::Error(ServerError::Command(CommandError::WrongType)),
+            send_command(&mut server, &mut receiver, &request, frame).await
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError, messages::Request, resp::types::Frame, server::Server, store::Value,
+};
+
+// Set the fields of the hash, creating it if missing, and reply with how
+// many fields were added rather than updated
+pub async fn command(server: &Server, request: &Request, key: Bytes, fields: Vec<(Bytes, Bytes)>) {
+    let result = server.db(request).update(key, |value| {
+        let hash = match value.get_or_insert_with(|| Value::Hash(HashMap::new())) {
+            Value::Hash(hash) => hash,
+            _ => return Err(CommandError::WrongType),
+        };
+        let added = fields
+            .into_iter()
+            .filter(|(field, value)| hash.insert(field.clone(), value.clone()).is_none())
+            .count();
+        Ok((added, true))
+    });
+
+    match result {
+        Ok(added) => request.data(Frame::Integer(added as i64)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{hset::command, setup_command_test, CommandError},
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::ServerError,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_hset_multiple_fields() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("hset", "key", "a", "1", "b", "2"));
+        let fields = |pairs: &[(&'static str, &'static str)]| {
+            pairs
+                .iter()
+                .map(|(field, value)| (Bytes::from(*field), Bytes::from(*value)))
+                .collect::<Vec<_>>()
+        };
+
+        command(
+            &server,
+            &request,
+            Bytes::from("key"),
+            fields(&[("a", "1"), ("b", "2")]),
+        )
+        .await;
+        command(
+            &server,
+            &request,
+            Bytes::from("key"),
+            fields(&[("b", "3"), ("c", "4")]),
+        )
+        .await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(2))
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(1))
+        );
+        let expected: HashMap<_, _> = fields(&[("a", "1"), ("b", "3"), ("c", "4")])
+            .into_iter()
+            .collect();
+        assert_eq!(Some(Value::Hash(expected)), server.db(&request).get(b"key"));
+    }
+
+    #[tokio::test]
+    async fn test_hset_wrong_type() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("hset", "key", "a", "1"));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("text")));
+
+        let fields = vec![(Bytes::from("a"), Bytes::from("1"))];
+        command(&server, &request, Bytes::from("key"), fields).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Error(ServerError::Command(CommandError::WrongType))
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use std::str;
+
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError, messages::Request, resp::types::Frame, server::Server, store::Value,
+};
+
+// Add `delta` to the integer stored at the key, starting from 0 when the key
+// is missing. INCR, DECR and INCRBY only differ in the delta.
+pub async fn command(server: &Server, request: &Request, key: Bytes, delta: i64) {
+    let result = server.db(request).update(key, |value| {
+        let current = match value {
+            Some(Value::String(bytes)) => parse_integer(bytes).ok_or(CommandError::NotInteger)?,
+            Some(_) => return Err(CommandError::WrongType),
+            None => 0,
+        };
+        let updated = add(current, delta)?;
+        *value = Some(Value::String(Bytes::from(updated.to_string())));
+        Ok((updated, true))
+    });
+
+    match result {
+        Ok(updated) => request.data(Frame::Integer(updated)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+// The integer a stored value holds, if it's one
+pub(crate) fn parse_integer(bytes: &[u8]) -> Option<i64> {
+    str::from_utf8(bytes).ok()?.parse().ok()
+}
+
+pub(crate) fn add(current: i64, delta: i64) -> Result<i64, CommandError> {
+    current
+        .checked_add(delta)
+        .ok_or_else(|| CommandError::Invalid("increment or decrement would overflow".into()))
+}
+
+// The float a stored value holds, if it's a finite one
+pub(crate) fn parse_float(bytes: &[u8]) -> Option<f64> {
+    str::from_utf8(bytes)
+        .ok()?
+        .parse()
+        .ok()
+        .filter(|n: &f64| n.is_finite())
+}
+
+pub(crate) fn add_float(current: f64, delta: f64) -> Result<f64, CommandError> {
+    Some(current + delta)
+        .filter(|n| n.is_finite())
+        .ok_or_else(|| CommandError::Invalid("increment would produce NaN or Infinity".into()))
+}
+
+// Floats are stored in plain notation without trailing zeros, e.g. "10.5"
+// or "3", like Redis does
+pub(crate) fn format_float(n: f64) -> String {
+    n.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{
+            incr::{command, format_float},
+            setup_command_test, CommandError,
+        },
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::ServerError,
+        store::Value,
+    };
+
+    #[rstest]
+    #[case(None, 1, 1)]
+    #[case(None, -1, -1)]
+    #[case(Some("41"), 1, 42)]
+    #[case(Some("-3"), 10, 7)]
+    #[case(Some("10"), -20, -10)]
+    #[tokio::test]
+    async fn test_incr(#[case] current: Option<&str>, #[case] delta: i64, #[case] expected: i64) {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!("incr", "key"));
+        if let Some(current) = current {
+            server.db(&request).set(
+                Bytes::from("key"),
+                Value::String(Bytes::copy_from_slice(current.as_bytes())),
+            );
+        }
+
+        command(&server, &request, Bytes::from("key"), delta).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(expected))
+        );
+        assert_eq!(
+            Some(Value::String(Bytes::from(expected.to_string()))),
+            server.db(&request).get(b"key")
+        );
+    }
+
+    #[rstest]
+    #[case("abc", 1, CommandError::NotInteger)]
+    #[case("1.5", 1, CommandError::NotInteger)]
+    #[case(
+        "9223372036854775807",
+        1,
+        CommandError::Invalid("increment or decrement would overflow".into())
+    )]
+    #[case(
+        "-9223372036854775808",
+        -1,
+        CommandError::Invalid("increment or decrement would overflow".into())
+    )]
+    #[tokio::test]
+    async fn test_incr_error(
+        #[case] current: &str,
+        #[case] delta: i64,
+        #[case] error: CommandError,
+    ) {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!("incr", "key"));
+        let value = Value::String(Bytes::copy_from_slice(current.as_bytes()));
+        server.db(&request).set(Bytes::from("key"), value.clone());
+
+        command(&server, &request, Bytes::from("key"), delta).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Error(ServerError::Command(error))
+        );
+        assert_eq!(Some(value), server.db(&request).get(b"key"));
+    }
+
+    #[rstest]
+    #[case(3.0, "3")]
+    #[case(10.5, "10.5")]
+    #[case(-0.25, "-0.25")]
+    #[case(1e20, "100000000000000000000")]
+    fn test_format_float(#[case] n: f64, #[case] expected: &str) {
+        assert_eq!(expected, format_float(n));
+    }
+}
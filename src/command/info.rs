@@ -0,0 +1,165 @@
+use std::fmt::Write;
+
+use bytes::Bytes;
+
+use crate::{
+    messages::Request,
+    resp::types::{Frame, VerbatimEncoding},
+    server::Server,
+    store::Db,
+};
+
+// Sections INFO knows about, in the order they're listed
+const SECTIONS: &[&str] = &["server", "clients", "memory", "keyspace"];
+
+// Server state as `# Section` headers followed by `field:value` lines, for
+// every section or the requested one. Unknown sections are left out.
+pub async fn command(server: &Server, request: &Request, section: Option<Bytes>) {
+    let requested = section.map(|section| String::from_utf8_lossy(&section).to_lowercase());
+    let mut info = String::new();
+    for &name in SECTIONS {
+        let included = match requested.as_deref() {
+            None | Some("all" | "default" | "everything") => true,
+            Some(requested) => requested == name,
+        };
+        if !included {
+            continue;
+        }
+        if !info.is_empty() {
+            info.push_str("\r\n");
+        }
+        writeln!(info, "# {}\r", capitalize(name)).unwrap();
+        for (field, value) in fields(server, name) {
+            writeln!(info, "{}:{}\r", field, value).unwrap();
+        }
+    }
+
+    request
+        .data(Frame::Verbatim {
+            format: VerbatimEncoding::Text,
+            data: Bytes::from(info),
+        })
+        .await;
+}
+
+fn fields(server: &Server, section: &str) -> Vec<(String, String)> {
+    let fields = match section {
+        "server" => vec![
+            ("yarrs_version", env!("CARGO_PKG_VERSION").to_string()),
+            ("redis_mode", "standalone".to_string()),
+            ("process_id", std::process::id().to_string()),
+            ("tcp_port", server.info.port.to_string()),
+            (
+                "uptime_in_seconds",
+                server.info.started_at.elapsed().as_secs().to_string(),
+            ),
+        ],
+        "clients" => vec![("connected_clients", server.clients.len().to_string())],
+        "memory" => {
+            let used: usize = server.dbs.iter().map(Db::used_memory).sum();
+            vec![
+                ("used_memory", used.to_string()),
+                ("maxmemory", server.config.maxmemory.to_string()),
+                (
+                    "maxmemory_policy",
+                    server.config.maxmemory_policy.name().to_string(),
+                ),
+            ]
+        }
+        // One line for each database holding keys
+        "keyspace" => {
+            return server
+                .dbs
+                .iter()
+                .enumerate()
+                .filter(|(_, db)| !db.is_empty())
+                .map(|(index, db)| {
+                    (
+                        format!("db{}", index),
+                        format!("keys={},expires={},avg_ttl=0", db.len(), db.expires()),
+                    )
+                })
+                .collect()
+        }
+        _ => vec![],
+    };
+    fields
+        .into_iter()
+        .map(|(field, value)| (field.to_string(), value))
+        .collect()
+}
+
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{info::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    async fn info(section: Option<&str>) -> String {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!("info"));
+        server
+            .db(&request)
+            .set(Bytes::from("a"), Value::String(Bytes::from("1")));
+        server
+            .db(&request)
+            .set(Bytes::from("b"), Value::String(Bytes::from("2")));
+        server.db(&request).expire(b"b", Duration::from_secs(10));
+        server.dbs[3].set(Bytes::from("c"), Value::String(Bytes::from("3")));
+
+        command(
+            &server,
+            &request,
+            section.map(|s| Bytes::from(s.to_string())),
+        )
+        .await;
+
+        match connection_receiver.try_recv().unwrap() {
+            ServerMessage::Data(Frame::Verbatim { data, .. }) => {
+                String::from_utf8(data.to_vec()).unwrap()
+            }
+            reply => panic!("unexpected reply {:?}", reply),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_info_all_sections() {
+        let info = info(None).await;
+
+        for header in ["# Server", "# Clients", "# Memory", "# Keyspace"] {
+            assert!(info.contains(header), "missing {} in {:?}", header, info);
+        }
+        assert!(info.contains("connected_clients:1\r\n"));
+        assert!(info.contains("db0:keys=2,expires=1,avg_ttl=0\r\n"));
+        assert!(info.contains("used_memory:"));
+    }
+
+    #[rstest]
+    #[case("clients", "# Clients\r\nconnected_clients:1\r\n")]
+    #[case("CLIENTS", "# Clients\r\nconnected_clients:1\r\n")]
+    #[case(
+        "keyspace",
+        "# Keyspace\r\ndb0:keys=2,expires=1,avg_ttl=0\r\ndb3:keys=1,expires=0,avg_ttl=0\r\n"
+    )]
+    #[case("nope", "")]
+    #[tokio::test]
+    async fn test_info_section(#[case] section: &str, #[case] expected: &str) {
+        assert_eq!(expected, info(Some(section)).await);
+    }
+}
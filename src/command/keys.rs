@@ -0,0 +1,60 @@
+use bytes::Bytes;
+
+use crate::{messages::Request, resp::types::Frame, server::Server};
+
+// Keys matching the glob pattern. This is O(N) in the number of keys and
+// blocks the whole keyspace meanwhile, so SCAN is preferable on large
+// databases.
+pub async fn command(server: &Server, request: &Request, pattern: Bytes) {
+    let keys = server
+        .db(request)
+        .keys(&pattern)
+        .into_iter()
+        .map(Frame::Bulk);
+    request.data(Frame::Array(keys.collect())).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{keys::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[rstest]
+    #[case("*", vec!["hello", "hallo", "hxllo", "user:1", "user:2"])]
+    #[case("user:*", vec!["user:1", "user:2"])]
+    #[case("h[ae]llo", vec!["hello", "hallo"])]
+    #[case("h?llo", vec!["hello", "hallo", "hxllo"])]
+    #[case("missing*", vec![])]
+    #[tokio::test]
+    async fn test_keys(#[case] pattern: &str, #[case] expected: Vec<&str>) {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("keys", pattern.to_string()));
+        for key in ["hello", "hallo", "hxllo", "user:1", "user:2"] {
+            server
+                .db(&request)
+                .set(Bytes::from(key), Value::String(Bytes::from("value")));
+        }
+
+        command(&server, &request, Bytes::from(pattern.to_string())).await;
+
+        let Ok(ServerMessage::Data(Frame::Array(keys))) = connection_receiver.try_recv() else {
+            panic!("expected an array of keys");
+        };
+        let mut keys: Vec<_> = keys
+            .into_iter()
+            .map(|key| String::try_from(key).unwrap())
+            .collect();
+        keys.sort();
+        let mut expected: Vec<_> = expected.into_iter().map(String::from).collect();
+        expected.sort();
+        assert_eq!(expected, keys);
+    }
+}
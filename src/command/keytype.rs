@@ -0,0 +1,47 @@
+use bytes::Bytes;
+
+use crate::{messages::Request, resp::types::Frame, server::Server};
+
+// TYPE: name of the type of the value stored at the key, "none" if missing
+pub async fn command(server: &Server, request: &Request, key: Bytes) {
+    let name = server.db(request).view(&key, |value| {
+        value.map_or("none", |value| value.type_name())
+    });
+    request.data(Frame::simple(name)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{keytype::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::{sorted_set::SortedSet, Value},
+    };
+
+    #[rstest]
+    #[case(Some(Value::String(Bytes::from("value"))), "string")]
+    #[case(Some(Value::List([Bytes::from("a")].into())), "list")]
+    #[case(Some(Value::Hash([(Bytes::from("a"), Bytes::from("1"))].into())), "hash")]
+    #[case(Some(Value::Set([Bytes::from("a")].into())), "set")]
+    #[case(Some(Value::SortedSet(SortedSet::new())), "zset")]
+    #[case(None, "none")]
+    #[tokio::test]
+    async fn test_type(#[case] value: Option<Value>, #[case] expected: &str) {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!("type", "key"));
+        if let Some(value) = value {
+            server.db(&request).set(Bytes::from("key"), value);
+        }
+
+        command(&server, &request, Bytes::from("key")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::simple(expected))
+        );
+    }
+}
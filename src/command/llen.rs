@@ -0,0 +1,52 @@
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError, messages::Request, resp::types::Frame, server::Server, store::Value,
+};
+
+pub async fn command(server: &Server, request: &Request, key: Bytes) {
+    let result = server.db(request).view(&key, |value| match value {
+        Some(Value::List(list)) => Ok(list.len()),
+        Some(_) => Err(CommandError::WrongType),
+        None => Ok(0),
+    });
+
+    match result {
+        Ok(len) => request.data(Frame::Integer(len as i64)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{llen::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_llen() {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!("llen", "key"));
+        server.db(&request).set(
+            Bytes::from("key"),
+            Value::List(["a", "b"].into_iter().map(Bytes::from).collect()),
+        );
+
+        command(&server, &request, Bytes::from("key")).await;
+        command(&server, &request, Bytes::from("missing")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(2))
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(0))
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use bytes::Bytes;
+
+use crate::{
+    command::{index_range, CommandError},
+    messages::Request,
+    resp::types::Frame,
+    server::Server,
+    store::Value,
+};
+
+// Elements from `start` to `stop` included. Negative indexes count from the
+// end of the list, and out of range ones are clamped to it.
+pub async fn command(server: &Server, request: &Request, key: Bytes, start: i64, stop: i64) {
+    let result = server.db(request).view(&key, |value| match value {
+        Some(Value::List(list)) => Ok(index_range(start, stop, list.len())
+            .map(|range| list.range(range).cloned().map(Frame::Bulk).collect())
+            .unwrap_or_default()),
+        Some(_) => Err(CommandError::WrongType),
+        None => Ok(vec![]),
+    });
+
+    match result {
+        Ok(items) => request.data(Frame::Array(items)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{lrange::command, setup_command_test, CommandError},
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::ServerError,
+        store::Value,
+    };
+
+    #[rstest]
+    #[case(0, -1, vec!["a", "b", "c", "d"])]
+    #[case(0, 1, vec!["a", "b"])]
+    #[case(-2, -1, vec!["c", "d"])]
+    #[case(1, -2, vec!["b", "c"])]
+    #[case(-100, 100, vec!["a", "b", "c", "d"])]
+    #[case(2, 1, vec![])]
+    #[case(4, 10, vec![])]
+    #[case(0, -5, vec![])]
+    #[tokio::test]
+    async fn test_lrange(#[case] start: i64, #[case] stop: i64, #[case] expected: Vec<&str>) {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("lrange", "key", start.to_string(), stop.to_string()));
+        server.db(&request).set(
+            Bytes::from("key"),
+            Value::List(["a", "b", "c", "d"].into_iter().map(Bytes::from).collect()),
+        );
+
+        command(&server, &request, Bytes::from("key"), start, stop).await;
+
+        let expected = expected.into_iter().map(Frame::from).collect();
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Array(expected))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lrange_missing() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("lrange", "key", "0", "-1"));
+
+        command(&server, &request, Bytes::from("key"), 0, -1).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Array(vec![]))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lrange_wrong_type() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("lrange", "key", "0", "-1"));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("text")));
+
+        command(&server, &request, Bytes::from("key"), 0, -1).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Error(ServerError::Command(CommandError::WrongType))
+        );
+    }
+}
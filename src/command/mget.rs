@@ -0,0 +1,57 @@
+use bytes::Bytes;
+
+use crate::{messages::Request, resp::types::Frame, server::Server, store::Value};
+
+// Value of each of the keys, Null for the missing ones and those which don't
+// hold a string
+pub async fn command(server: &Server, request: &Request, keys: Vec<Bytes>) {
+    let values = keys
+        .iter()
+        .map(|key| match server.db(request).get(key) {
+            Some(Value::String(value)) => Frame::Bulk(value),
+            _ => Frame::Null,
+        })
+        .collect();
+    request.data(Frame::Array(values)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{mget::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_mget() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("mget", "a", "missing", "list", "b"));
+        server
+            .db(&request)
+            .set(Bytes::from("a"), Value::String(Bytes::from("1")));
+        server
+            .db(&request)
+            .set(Bytes::from("b"), Value::String(Bytes::from("2")));
+        server
+            .db(&request)
+            .set(Bytes::from("list"), Value::List([Bytes::from("x")].into()));
+
+        let keys = ["a", "missing", "list", "b"].into_iter().map(Bytes::from);
+        command(&server, &request, keys.collect()).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Array(vec![
+                Frame::from("1"),
+                Frame::Null,
+                Frame::Null,
+                Frame::from("2"),
+            ]))
+        );
+    }
+}
@@ -1,2 +1,1761 @@
+use std::{ops::RangeInclusive, str, time::Duration};
+
+use bytes::Bytes;
+use thiserror::Error;
+
+use crate::resp::types::Frame;
+use expire::millis_until;
+use getex::GetExpire;
+use set::{Condition, SetOptions};
+
+pub mod append;
+pub mod auth;
+pub mod bitmap;
+pub mod commands;
+pub mod config;
+pub mod copy;
+pub mod dbsize;
+pub mod debug;
+pub mod del;
+pub mod discard;
 pub mod echo;
+pub mod exec;
+pub mod exists;
+pub mod expire;
+pub mod flush;
+pub mod get;
+pub mod getdel;
+pub mod getex;
+pub mod getrange;
+pub mod hdel;
+pub mod hello;
+pub mod hget;
+pub mod hgetall;
+pub mod hincrby;
+pub mod hset;
+pub mod incr;
+pub mod info;
+pub mod keys;
+pub mod keytype;
+pub mod llen;
+pub mod lrange;
+pub mod mget;
+pub mod mset;
+pub mod multi;
+pub mod object;
+pub mod persist;
 pub mod ping;
+pub mod pop;
+pub mod psubscribe;
+pub mod pttl;
+pub mod publish;
+pub mod push;
+pub mod quit;
+pub mod rename;
+pub mod reset;
+pub mod sadd;
+pub mod save;
+pub mod scan;
+pub mod select;
+pub mod set;
+pub mod setnx;
+pub mod setop;
+pub mod setrange;
+pub mod sismember;
+pub mod slowlog;
+pub mod smembers;
+pub mod srem;
+pub mod strlen;
+pub mod subscribe;
+pub mod ttl;
+pub mod unsubscribe;
+pub mod wait;
+pub mod watch;
+pub mod zadd;
+pub mod zrange;
+pub mod zscore;
+
+// A client request, validated and with its arguments extracted
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Append {
+        key: Bytes,
+        value: Bytes,
+    },
+    Auth {
+        username: Option<Bytes>,
+        password: Bytes,
+    },
+    BgSave,
+    BitCount {
+        key: Bytes,
+        // Byte range, the whole string if missing
+        range: Option<(i64, i64)>,
+    },
+    // Without a timeout, BLPOP waits for as long as it takes
+    BLPop {
+        keys: Vec<Bytes>,
+        timeout: Option<Duration>,
+    },
+    CommandCount,
+    CommandList,
+    ConfigGet(Bytes),
+    ConfigSet {
+        parameter: Bytes,
+        value: Bytes,
+    },
+    Copy {
+        from: Bytes,
+        to: Bytes,
+        db: Option<i64>,
+        replace: bool,
+    },
+    DbSize,
+    DebugObject(Bytes),
+    DebugSleep(Duration),
+    Decr(Bytes),
+    Del(Vec<Bytes>),
+    Discard,
+    Echo(Bytes),
+    Exec,
+    Exists(Vec<Bytes>),
+    Expire {
+        key: Bytes,
+        seconds: i64,
+    },
+    // EXPIREAT and PEXPIREAT, with the deadline in unix milliseconds
+    ExpireAt {
+        key: Bytes,
+        unix_millis: i64,
+    },
+    FlushAll,
+    FlushDb,
+    Get(Bytes),
+    GetBit {
+        key: Bytes,
+        offset: u64,
+    },
+    GetDel(Bytes),
+    GetEx {
+        key: Bytes,
+        expire: GetExpire,
+    },
+    GetRange {
+        key: Bytes,
+        start: i64,
+        end: i64,
+    },
+    HDel {
+        key: Bytes,
+        fields: Vec<Bytes>,
+    },
+    Hello(Option<i64>),
+    HGet {
+        key: Bytes,
+        field: Bytes,
+    },
+    HGetAll(Bytes),
+    HIncrBy {
+        key: Bytes,
+        field: Bytes,
+        delta: i64,
+    },
+    HIncrByFloat {
+        key: Bytes,
+        field: Bytes,
+        delta: f64,
+    },
+    HSet {
+        key: Bytes,
+        fields: Vec<(Bytes, Bytes)>,
+    },
+    Incr(Bytes),
+    IncrBy {
+        key: Bytes,
+        delta: i64,
+    },
+    Info(Option<Bytes>),
+    Keys(Bytes),
+    LLen(Bytes),
+    LPop {
+        key: Bytes,
+        count: Option<usize>,
+    },
+    LPush {
+        key: Bytes,
+        values: Vec<Bytes>,
+    },
+    LRange {
+        key: Bytes,
+        start: i64,
+        stop: i64,
+    },
+    MGet(Vec<Bytes>),
+    MSet(Vec<(Bytes, Bytes)>),
+    Multi,
+    ObjectEncoding(Bytes),
+    Persist(Bytes),
+    Ping(Option<Bytes>),
+    PSubscribe(Vec<Bytes>),
+    Pttl(Bytes),
+    RPop {
+        key: Bytes,
+        count: Option<usize>,
+    },
+    RPush {
+        key: Bytes,
+        values: Vec<Bytes>,
+    },
+    Publish {
+        channel: Bytes,
+        message: Bytes,
+    },
+    Quit,
+    Reset,
+    Rename {
+        from: Bytes,
+        to: Bytes,
+    },
+    RenameNx {
+        from: Bytes,
+        to: Bytes,
+    },
+    SAdd {
+        key: Bytes,
+        members: Vec<Bytes>,
+    },
+    Save,
+    Scan {
+        cursor: u64,
+        pattern: Option<Bytes>,
+        count: usize,
+    },
+    SDiff(Vec<Bytes>),
+    SDiffStore {
+        destination: Bytes,
+        keys: Vec<Bytes>,
+    },
+    Select(i64),
+    Set {
+        key: Bytes,
+        value: Bytes,
+        options: SetOptions,
+    },
+    SetBit {
+        key: Bytes,
+        offset: u64,
+        value: bool,
+    },
+    SetNx {
+        key: Bytes,
+        value: Bytes,
+    },
+    SetRange {
+        key: Bytes,
+        offset: i64,
+        value: Bytes,
+    },
+    SInter(Vec<Bytes>),
+    SInterStore {
+        destination: Bytes,
+        keys: Vec<Bytes>,
+    },
+    SIsMember {
+        key: Bytes,
+        member: Bytes,
+    },
+    // Up to this many entries
+    SlowLogGet(usize),
+    SlowLogLen,
+    SlowLogReset,
+    SMembers(Bytes),
+    SRem {
+        key: Bytes,
+        members: Vec<Bytes>,
+    },
+    StrLen(Bytes),
+    Subscribe(Vec<Bytes>),
+    SUnion(Vec<Bytes>),
+    SUnionStore {
+        destination: Bytes,
+        keys: Vec<Bytes>,
+    },
+    Ttl(Bytes),
+    Type(Bytes),
+    Unsubscribe(Vec<Bytes>),
+    Unwatch,
+    Wait,
+    Watch(Vec<Bytes>),
+    ZAdd {
+        key: Bytes,
+        members: Vec<(f64, Bytes)>,
+    },
+    ZRange {
+        key: Bytes,
+        start: i64,
+        stop: i64,
+        with_scores: bool,
+    },
+    ZScore {
+        key: Bytes,
+        member: Bytes,
+    },
+}
+
+// What COMMAND reports about a supported command
+#[derive(Debug, PartialEq)]
+pub struct CommandInfo {
+    pub name: &'static str,
+    // Number of arguments, the name included. Negative when it's a minimum.
+    pub arity: i64,
+    pub flags: &'static [&'static str],
+    // Position of the first and last key arguments (negative counting from
+    // the end), and the step between keys. All 0 without keys.
+    pub keys: (i64, i64, i64),
+}
+
+impl CommandInfo {
+    // Whether `len` arguments, the name included, fit the arity
+    pub fn accepts(&self, len: usize) -> bool {
+        let len = len as i64;
+        if self.arity < 0 {
+            len >= -self.arity
+        } else {
+            len == self.arity
+        }
+    }
+}
+
+const fn entry(
+    name: &'static str,
+    arity: i64,
+    flags: &'static [&'static str],
+    keys: (i64, i64, i64),
+) -> CommandInfo {
+    CommandInfo {
+        name,
+        arity,
+        flags,
+        keys,
+    }
+}
+
+// Supported commands, by name
+pub const COMMANDS: &[CommandInfo] = &[
+    entry("APPEND", 3, &["write", "denyoom", "fast"], (1, 1, 1)),
+    entry(
+        "AUTH",
+        -2,
+        &["noscript", "loading", "stale", "fast", "no_auth"],
+        (0, 0, 0),
+    ),
+    entry("BGSAVE", 1, &["admin", "noscript"], (0, 0, 0)),
+    entry("BITCOUNT", -2, &["readonly"], (1, 1, 1)),
+    entry("BLPOP", -3, &["write", "noscript"], (1, -2, 1)),
+    entry("COMMAND", -1, &["loading", "stale"], (0, 0, 0)),
+    entry(
+        "CONFIG",
+        -2,
+        &["admin", "noscript", "loading", "stale"],
+        (0, 0, 0),
+    ),
+    entry("COPY", -3, &["write", "denyoom"], (1, 2, 1)),
+    entry("DBSIZE", 1, &["readonly", "fast"], (0, 0, 0)),
+    entry(
+        "DEBUG",
+        -2,
+        &["admin", "noscript", "loading", "stale"],
+        (0, 0, 0),
+    ),
+    entry("DECR", 2, &["write", "denyoom", "fast"], (1, 1, 1)),
+    entry("DEL", -2, &["write"], (1, -1, 1)),
+    entry(
+        "DISCARD",
+        1,
+        &["noscript", "loading", "stale", "fast"],
+        (0, 0, 0),
+    ),
+    entry("ECHO", 2, &["fast"], (0, 0, 0)),
+    entry("EXEC", 1, &["noscript", "loading", "stale"], (0, 0, 0)),
+    entry("EXISTS", -2, &["readonly", "fast"], (1, -1, 1)),
+    entry("EXPIRE", 3, &["write", "fast"], (1, 1, 1)),
+    entry("EXPIREAT", 3, &["write", "fast"], (1, 1, 1)),
+    entry("FLUSHALL", -1, &["write"], (0, 0, 0)),
+    entry("FLUSHDB", -1, &["write"], (0, 0, 0)),
+    entry("GET", 2, &["readonly", "fast"], (1, 1, 1)),
+    entry("GETBIT", 3, &["readonly", "fast"], (1, 1, 1)),
+    entry("GETDEL", 2, &["write", "fast"], (1, 1, 1)),
+    entry("GETEX", -2, &["write", "fast"], (1, 1, 1)),
+    entry("GETRANGE", 4, &["readonly"], (1, 1, 1)),
+    entry("HDEL", -3, &["write", "fast"], (1, 1, 1)),
+    entry(
+        "HELLO",
+        -1,
+        &["noscript", "loading", "stale", "fast", "no_auth"],
+        (0, 0, 0),
+    ),
+    entry("HGET", 3, &["readonly", "fast"], (1, 1, 1)),
+    entry("HGETALL", 2, &["readonly"], (1, 1, 1)),
+    entry("HINCRBY", 4, &["write", "denyoom", "fast"], (1, 1, 1)),
+    entry("HINCRBYFLOAT", 4, &["write", "denyoom", "fast"], (1, 1, 1)),
+    entry("HSET", -4, &["write", "denyoom", "fast"], (1, 1, 1)),
+    entry("INCR", 2, &["write", "denyoom", "fast"], (1, 1, 1)),
+    entry("INCRBY", 3, &["write", "denyoom", "fast"], (1, 1, 1)),
+    entry("INFO", -1, &["loading", "stale"], (0, 0, 0)),
+    entry("KEYS", 2, &["readonly"], (0, 0, 0)),
+    entry("LLEN", 2, &["readonly", "fast"], (1, 1, 1)),
+    entry("LPOP", -2, &["write", "fast"], (1, 1, 1)),
+    entry("LPUSH", -3, &["write", "denyoom", "fast"], (1, 1, 1)),
+    entry("LRANGE", 4, &["readonly"], (1, 1, 1)),
+    entry("MGET", -2, &["readonly", "fast"], (1, -1, 1)),
+    entry("MSET", -3, &["write", "denyoom"], (1, -1, 2)),
+    entry(
+        "MULTI",
+        1,
+        &["noscript", "loading", "stale", "fast"],
+        (0, 0, 0),
+    ),
+    entry("OBJECT", -2, &["readonly"], (2, 2, 1)),
+    entry("PERSIST", 2, &["write", "fast"], (1, 1, 1)),
+    entry("PEXPIREAT", 3, &["write", "fast"], (1, 1, 1)),
+    entry("PING", -1, &["fast"], (0, 0, 0)),
+    entry("PSETEX", 4, &["write", "denyoom"], (1, 1, 1)),
+    entry(
+        "PSUBSCRIBE",
+        -2,
+        &["pubsub", "noscript", "loading", "stale"],
+        (0, 0, 0),
+    ),
+    entry("PTTL", 2, &["readonly", "fast"], (1, 1, 1)),
+    entry(
+        "PUBLISH",
+        3,
+        &["pubsub", "loading", "stale", "fast"],
+        (0, 0, 0),
+    ),
+    entry(
+        "QUIT",
+        1,
+        &["noscript", "loading", "stale", "fast", "no_auth"],
+        (0, 0, 0),
+    ),
+    entry("RENAME", 3, &["write"], (1, 2, 1)),
+    entry("RENAMENX", 3, &["write", "fast"], (1, 2, 1)),
+    entry(
+        "RESET",
+        1,
+        &["noscript", "loading", "stale", "fast", "no_auth"],
+        (0, 0, 0),
+    ),
+    entry("RPOP", -2, &["write", "fast"], (1, 1, 1)),
+    entry("RPUSH", -3, &["write", "denyoom", "fast"], (1, 1, 1)),
+    entry("SADD", -3, &["write", "denyoom", "fast"], (1, 1, 1)),
+    entry("SAVE", 1, &["admin", "noscript"], (0, 0, 0)),
+    entry("SCAN", -2, &["readonly"], (0, 0, 0)),
+    entry("SDIFF", -2, &["readonly"], (1, -1, 1)),
+    entry("SDIFFSTORE", -3, &["write", "denyoom"], (1, -1, 1)),
+    entry("SELECT", 2, &["loading", "stale", "fast"], (0, 0, 0)),
+    entry("SET", -3, &["write", "denyoom"], (1, 1, 1)),
+    entry("SETBIT", 4, &["write", "denyoom"], (1, 1, 1)),
+    entry("SETEX", 4, &["write", "denyoom"], (1, 1, 1)),
+    entry("SETNX", 3, &["write", "denyoom", "fast"], (1, 1, 1)),
+    entry("SETRANGE", 4, &["write", "denyoom"], (1, 1, 1)),
+    entry("SINTER", -2, &["readonly"], (1, -1, 1)),
+    entry("SINTERSTORE", -3, &["write", "denyoom"], (1, -1, 1)),
+    entry("SISMEMBER", 3, &["readonly", "fast"], (1, 1, 1)),
+    entry(
+        "SLOWLOG",
+        -2,
+        &["admin", "random", "loading", "stale"],
+        (0, 0, 0),
+    ),
+    entry("SMEMBERS", 2, &["readonly"], (1, 1, 1)),
+    entry("SREM", -3, &["write", "fast"], (1, 1, 1)),
+    entry("STRLEN", 2, &["readonly", "fast"], (1, 1, 1)),
+    entry(
+        "SUBSCRIBE",
+        -2,
+        &["pubsub", "noscript", "loading", "stale"],
+        (0, 0, 0),
+    ),
+    entry("SUNION", -2, &["readonly"], (1, -1, 1)),
+    entry("SUNIONSTORE", -3, &["write", "denyoom"], (1, -1, 1)),
+    entry("TTL", 2, &["readonly", "fast"], (1, 1, 1)),
+    entry("TYPE", 2, &["readonly", "fast"], (1, 1, 1)),
+    entry(
+        "UNSUBSCRIBE",
+        -1,
+        &["pubsub", "noscript", "loading", "stale"],
+        (0, 0, 0),
+    ),
+    entry(
+        "UNWATCH",
+        1,
+        &["noscript", "loading", "stale", "fast"],
+        (0, 0, 0),
+    ),
+    entry("WAIT", 3, &["noscript"], (0, 0, 0)),
+    entry(
+        "WATCH",
+        -2,
+        &["noscript", "loading", "stale", "fast"],
+        (1, -1, 1),
+    ),
+    entry("ZADD", -4, &["write", "denyoom", "fast"], (1, 1, 1)),
+    entry("ZRANGE", -4, &["readonly"], (1, 1, 1)),
+    entry("ZSCORE", 3, &["readonly", "fast"], (1, 1, 1)),
+];
+
+// The supported command with this (uppercase) name
+pub fn command_info(name: &str) -> Option<&'static CommandInfo> {
+    COMMANDS.iter().find(|info| info.name == name)
+}
+
+// Errors of the command handlers, worded as Redis does
+#[derive(Error, Debug, PartialEq)]
+pub enum CommandError {
+    #[error("unknown command '{0}'")]
+    Unknown(String),
+    #[error("wrong number of arguments for '{}' command", .0.to_lowercase())]
+    WrongArity(String),
+    #[error("WRONGTYPE Operation against a key holding the wrong kind of value")]
+    WrongType,
+    #[error("syntax error")]
+    Syntax,
+    #[error("value is not an integer or out of range")]
+    NotInteger,
+    // What is out of range, e.g. "offset"
+    #[error("{0} is out of range")]
+    OutOfRange(String),
+    #[error("{0}")]
+    Invalid(String),
+}
+
+impl CommandError {
+    // Error reply sent to the client, starting with the error code
+    pub fn to_frame(&self) -> Frame {
+        match self {
+            CommandError::WrongType => Frame::Error(self.to_string()),
+            error => Frame::Error(format!("ERR {}", error)),
+        }
+    }
+}
+
+impl Command {
+    // Build a command out of an array of bulk strings, the first one being
+    // the (case insensitive) command name
+    pub fn from_frame(frame: Frame) -> Result<Command, CommandError> {
+        let Frame::Array(frames) = frame else {
+            return Err(CommandError::Invalid(
+                "request must be an array of bulk strings".into(),
+            ));
+        };
+
+        let mut args = Vec::with_capacity(frames.len());
+        for frame in frames {
+            match frame {
+                Frame::Bulk(bytes) => args.push(bytes),
+                _ => {
+                    return Err(CommandError::Invalid(
+                        "request must be an array of bulk strings".into(),
+                    ))
+                }
+            }
+        }
+
+        let mut args = args.into_iter();
+        let name = match args.next() {
+            Some(name) => String::from_utf8_lossy(&name).to_uppercase(),
+            None => return Err(CommandError::Invalid("missing command name".into())),
+        };
+        match command_info(&name) {
+            Some(info) if info.accepts(args.len() + 1) => {}
+            Some(_) => return Err(CommandError::WrongArity(name)),
+            None => return Err(CommandError::Unknown(name)),
+        }
+        let args: Vec<Bytes> = args.collect();
+
+        match (name.as_str(), args.as_slice()) {
+            ("APPEND", [key, value]) => Ok(Command::Append {
+                key: key.clone(),
+                value: value.clone(),
+            }),
+            ("AUTH", [password]) => Ok(Command::Auth {
+                username: None,
+                password: password.clone(),
+            }),
+            ("AUTH", [username, password]) => Ok(Command::Auth {
+                username: Some(username.clone()),
+                password: password.clone(),
+            }),
+            ("BGSAVE", []) => Ok(Command::BgSave),
+            ("BLPOP", [keys @ .., timeout]) => Ok(Command::BLPop {
+                keys: keys.to_vec(),
+                timeout: parse_timeout(timeout)?,
+            }),
+            ("BITCOUNT", [key, range @ ..]) => Ok(Command::BitCount {
+                key: key.clone(),
+                range: match range {
+                    [] => None,
+                    [start, end] => Some((parse_integer(start)?, parse_integer(end)?)),
+                    _ => return Err(CommandError::Syntax),
+                },
+            }),
+            ("COMMAND", []) => Ok(Command::CommandList),
+            ("COMMAND", [subcommand, rest @ ..]) => {
+                match (subcommand.to_ascii_uppercase().as_slice(), rest) {
+                    (b"COUNT", []) => Ok(Command::CommandCount),
+                    _ => Err(CommandError::Invalid(format!(
+                        "unknown subcommand or wrong number of arguments for '{}'",
+                        String::from_utf8_lossy(subcommand)
+                    ))),
+                }
+            }
+            ("CONFIG", [subcommand, rest @ ..]) => {
+                match (subcommand.to_ascii_uppercase().as_slice(), rest) {
+                    (b"GET", [pattern]) => Ok(Command::ConfigGet(pattern.clone())),
+                    (b"SET", [parameter, value]) => Ok(Command::ConfigSet {
+                        parameter: parameter.clone(),
+                        value: value.clone(),
+                    }),
+                    _ => Err(CommandError::Invalid(format!(
+                        "unknown subcommand or wrong number of arguments for '{}'",
+                        String::from_utf8_lossy(subcommand)
+                    ))),
+                }
+            }
+            ("COPY", [from, to, options @ ..]) => {
+                let (db, replace) = parse_copy_options(options)?;
+                Ok(Command::Copy {
+                    from: from.clone(),
+                    to: to.clone(),
+                    db,
+                    replace,
+                })
+            }
+            ("DBSIZE", []) => Ok(Command::DbSize),
+            ("DEBUG", [subcommand, rest @ ..]) => {
+                match (subcommand.to_ascii_uppercase().as_slice(), rest) {
+                    (b"OBJECT", [key]) => Ok(Command::DebugObject(key.clone())),
+                    (b"SLEEP", [seconds]) => parse_float(seconds)
+                        .ok()
+                        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                        .map(Command::DebugSleep)
+                        .ok_or_else(|| CommandError::Invalid("invalid sleep time".into())),
+                    _ => Err(CommandError::Invalid(format!(
+                        "unknown subcommand or wrong number of arguments for '{}'",
+                        String::from_utf8_lossy(subcommand)
+                    ))),
+                }
+            }
+            ("DECR", [key]) => Ok(Command::Decr(key.clone())),
+            ("DEL", [_, ..]) => Ok(Command::Del(args)),
+            ("DISCARD", []) => Ok(Command::Discard),
+            ("ECHO", [message]) => Ok(Command::Echo(message.clone())),
+            ("EXEC", []) => Ok(Command::Exec),
+            ("EXISTS", [_, ..]) => Ok(Command::Exists(args)),
+            ("EXPIRE", [key, seconds]) => Ok(Command::Expire {
+                key: key.clone(),
+                seconds: parse_integer(seconds)?,
+            }),
+            ("EXPIREAT", [key, seconds]) => Ok(Command::ExpireAt {
+                key: key.clone(),
+                unix_millis: parse_integer(seconds)?.checked_mul(1000).ok_or_else(|| {
+                    CommandError::Invalid("invalid expire time in 'expireat' command".into())
+                })?,
+            }),
+            ("FLUSHALL", mode) => parse_flush_mode(mode).map(|_| Command::FlushAll),
+            ("FLUSHDB", mode) => parse_flush_mode(mode).map(|_| Command::FlushDb),
+            ("GET", [key]) => Ok(Command::Get(key.clone())),
+            ("GETBIT", [key, offset]) => Ok(Command::GetBit {
+                key: key.clone(),
+                offset: parse_bit_offset(offset)?,
+            }),
+            ("GETDEL", [key]) => Ok(Command::GetDel(key.clone())),
+            ("GETEX", [key, options @ ..]) => Ok(Command::GetEx {
+                key: key.clone(),
+                expire: parse_getex_options(options)?,
+            }),
+            ("GETRANGE", [key, start, end]) => Ok(Command::GetRange {
+                key: key.clone(),
+                start: parse_integer(start)?,
+                end: parse_integer(end)?,
+            }),
+            ("HDEL", [key, fields @ ..]) if !fields.is_empty() => Ok(Command::HDel {
+                key: key.clone(),
+                fields: fields.to_vec(),
+            }),
+            ("HELLO", []) => Ok(Command::Hello(None)),
+            ("HELLO", [protover]) => Ok(Command::Hello(Some(parse_integer(protover)?))),
+            ("HGET", [key, field]) => Ok(Command::HGet {
+                key: key.clone(),
+                field: field.clone(),
+            }),
+            ("HGETALL", [key]) => Ok(Command::HGetAll(key.clone())),
+            ("HINCRBY", [key, field, delta]) => Ok(Command::HIncrBy {
+                key: key.clone(),
+                field: field.clone(),
+                delta: parse_integer(delta)?,
+            }),
+            ("HINCRBYFLOAT", [key, field, delta]) => Ok(Command::HIncrByFloat {
+                key: key.clone(),
+                field: field.clone(),
+                delta: parse_float(delta)?,
+            }),
+            ("HSET", [key, fields @ ..])
+                if !fields.is_empty() && fields.len().is_multiple_of(2) =>
+            {
+                Ok(Command::HSet {
+                    key: key.clone(),
+                    fields: fields
+                        .chunks(2)
+                        .map(|pair| (pair[0].clone(), pair[1].clone()))
+                        .collect(),
+                })
+            }
+            ("INCR", [key]) => Ok(Command::Incr(key.clone())),
+            ("INCRBY", [key, delta]) => Ok(Command::IncrBy {
+                key: key.clone(),
+                delta: parse_integer(delta)?,
+            }),
+            ("INFO", []) => Ok(Command::Info(None)),
+            ("INFO", [section]) => Ok(Command::Info(Some(section.clone()))),
+            ("KEYS", [pattern]) => Ok(Command::Keys(pattern.clone())),
+            ("LLEN", [key]) => Ok(Command::LLen(key.clone())),
+            ("LPOP", [key, count @ ..]) => Ok(Command::LPop {
+                key: key.clone(),
+                count: parse_pop_count(count)?,
+            }),
+            ("LPUSH", [key, values @ ..]) if !values.is_empty() => Ok(Command::LPush {
+                key: key.clone(),
+                values: values.to_vec(),
+            }),
+            ("LRANGE", [key, start, stop]) => Ok(Command::LRange {
+                key: key.clone(),
+                start: parse_integer(start)?,
+                stop: parse_integer(stop)?,
+            }),
+            ("MGET", [_, ..]) => Ok(Command::MGet(args)),
+            ("MSET", [_, _, ..]) if args.len().is_multiple_of(2) => Ok(Command::MSet(
+                args.chunks(2)
+                    .map(|pair| (pair[0].clone(), pair[1].clone()))
+                    .collect(),
+            )),
+            ("MULTI", []) => Ok(Command::Multi),
+            ("PING", []) => Ok(Command::Ping(None)),
+            ("PING", [message]) => Ok(Command::Ping(Some(message.clone()))),
+            ("PSUBSCRIBE", [_, ..]) => Ok(Command::PSubscribe(args)),
+            ("PTTL", [key]) => Ok(Command::Pttl(key.clone())),
+            ("OBJECT", [subcommand, rest @ ..]) => {
+                match (subcommand.to_ascii_uppercase().as_slice(), rest) {
+                    (b"ENCODING", [key]) => Ok(Command::ObjectEncoding(key.clone())),
+                    _ => Err(CommandError::Invalid(format!(
+                        "unknown subcommand or wrong number of arguments for '{}'",
+                        String::from_utf8_lossy(subcommand)
+                    ))),
+                }
+            }
+            ("PERSIST", [key]) => Ok(Command::Persist(key.clone())),
+            ("PEXPIREAT", [key, millis]) => Ok(Command::ExpireAt {
+                key: key.clone(),
+                unix_millis: parse_integer(millis)?,
+            }),
+            ("PSETEX", [key, millis, value]) => Ok(Command::Set {
+                key: key.clone(),
+                value: value.clone(),
+                options: SetOptions {
+                    expire: Some(parse_expire(millis, 1, "psetex")?),
+                    ..SetOptions::default()
+                },
+            }),
+            ("PUBLISH", [channel, message]) => Ok(Command::Publish {
+                channel: channel.clone(),
+                message: message.clone(),
+            }),
+            ("QUIT", []) => Ok(Command::Quit),
+            ("RESET", []) => Ok(Command::Reset),
+            ("RENAME", [from, to]) => Ok(Command::Rename {
+                from: from.clone(),
+                to: to.clone(),
+            }),
+            ("RENAMENX", [from, to]) => Ok(Command::RenameNx {
+                from: from.clone(),
+                to: to.clone(),
+            }),
+            ("RPOP", [key, count @ ..]) => Ok(Command::RPop {
+                key: key.clone(),
+                count: parse_pop_count(count)?,
+            }),
+            ("RPUSH", [key, values @ ..]) if !values.is_empty() => Ok(Command::RPush {
+                key: key.clone(),
+                values: values.to_vec(),
+            }),
+            ("SADD", [key, members @ ..]) if !members.is_empty() => Ok(Command::SAdd {
+                key: key.clone(),
+                members: members.to_vec(),
+            }),
+            ("SAVE", []) => Ok(Command::Save),
+            ("SCAN", [cursor, options @ ..]) => parse_scan(cursor, options),
+            ("SDIFF", [_, ..]) => Ok(Command::SDiff(args)),
+            ("SDIFFSTORE", [destination, keys @ ..]) if !keys.is_empty() => {
+                Ok(Command::SDiffStore {
+                    destination: destination.clone(),
+                    keys: keys.to_vec(),
+                })
+            }
+            ("SELECT", [index]) => Ok(Command::Select(parse_integer(index)?)),
+            ("SET", [key, value, options @ ..]) => Ok(Command::Set {
+                key: key.clone(),
+                value: value.clone(),
+                options: parse_set_options(options)?,
+            }),
+            ("SETEX", [key, seconds, value]) => Ok(Command::Set {
+                key: key.clone(),
+                value: value.clone(),
+                options: SetOptions {
+                    expire: Some(parse_expire(seconds, 1000, "setex")?),
+                    ..SetOptions::default()
+                },
+            }),
+            ("SETNX", [key, value]) => Ok(Command::SetNx {
+                key: key.clone(),
+                value: value.clone(),
+            }),
+            ("SETBIT", [key, offset, value]) => Ok(Command::SetBit {
+                key: key.clone(),
+                offset: parse_bit_offset(offset)?,
+                value: match value.as_ref() {
+                    b"0" => false,
+                    b"1" => true,
+                    _ => {
+                        return Err(CommandError::Invalid(
+                            "bit is not an integer or out of range".into(),
+                        ))
+                    }
+                },
+            }),
+            ("SETRANGE", [key, offset, value]) => Ok(Command::SetRange {
+                key: key.clone(),
+                offset: parse_integer(offset)?,
+                value: value.clone(),
+            }),
+            ("SINTER", [_, ..]) => Ok(Command::SInter(args)),
+            ("SINTERSTORE", [destination, keys @ ..]) if !keys.is_empty() => {
+                Ok(Command::SInterStore {
+                    destination: destination.clone(),
+                    keys: keys.to_vec(),
+                })
+            }
+            ("SISMEMBER", [key, member]) => Ok(Command::SIsMember {
+                key: key.clone(),
+                member: member.clone(),
+            }),
+            ("SLOWLOG", [subcommand, rest @ ..]) => {
+                match (subcommand.to_ascii_uppercase().as_slice(), rest) {
+                    (b"GET", []) => Ok(Command::SlowLogGet(10)),
+                    // -1 gets every entry
+                    (b"GET", [count]) => match parse_integer(count)? {
+                        -1 => Ok(Command::SlowLogGet(usize::MAX)),
+                        count => usize::try_from(count)
+                            .map(Command::SlowLogGet)
+                            .map_err(|_| {
+                                CommandError::Invalid(
+                                    "count should be greater than or equal to -1".into(),
+                                )
+                            }),
+                    },
+                    (b"LEN", []) => Ok(Command::SlowLogLen),
+                    (b"RESET", []) => Ok(Command::SlowLogReset),
+                    _ => Err(CommandError::Invalid(format!(
+                        "unknown subcommand or wrong number of arguments for '{}'",
+                        String::from_utf8_lossy(subcommand)
+                    ))),
+                }
+            }
+            ("SMEMBERS", [key]) => Ok(Command::SMembers(key.clone())),
+            ("SREM", [key, members @ ..]) if !members.is_empty() => Ok(Command::SRem {
+                key: key.clone(),
+                members: members.to_vec(),
+            }),
+            ("STRLEN", [key]) => Ok(Command::StrLen(key.clone())),
+            ("SUBSCRIBE", [_, ..]) => Ok(Command::Subscribe(args)),
+            ("SUNION", [_, ..]) => Ok(Command::SUnion(args)),
+            ("SUNIONSTORE", [destination, keys @ ..]) if !keys.is_empty() => {
+                Ok(Command::SUnionStore {
+                    destination: destination.clone(),
+                    keys: keys.to_vec(),
+                })
+            }
+            ("TTL", [key]) => Ok(Command::Ttl(key.clone())),
+            ("TYPE", [key]) => Ok(Command::Type(key.clone())),
+            ("UNSUBSCRIBE", _) => Ok(Command::Unsubscribe(args)),
+            ("UNWATCH", []) => Ok(Command::Unwatch),
+            ("WAIT", [replicas, timeout]) => {
+                parse_integer(replicas)?;
+                if parse_integer(timeout)? < 0 {
+                    return Err(CommandError::Invalid("timeout is negative".into()));
+                }
+                Ok(Command::Wait)
+            }
+            ("WATCH", [_, ..]) => Ok(Command::Watch(args)),
+            ("ZADD", [key, members @ ..])
+                if !members.is_empty() && members.len().is_multiple_of(2) =>
+            {
+                Ok(Command::ZAdd {
+                    key: key.clone(),
+                    members: members
+                        .chunks(2)
+                        .map(|pair| Ok((parse_float(&pair[0])?, pair[1].clone())))
+                        .collect::<Result<_, _>>()?,
+                })
+            }
+            ("ZRANGE", [key, start, stop, options @ ..]) => Ok(Command::ZRange {
+                key: key.clone(),
+                start: parse_integer(start)?,
+                stop: parse_integer(stop)?,
+                with_scores: match options {
+                    [] => false,
+                    [option] if option.eq_ignore_ascii_case(b"WITHSCORES") => true,
+                    _ => return Err(CommandError::Syntax),
+                },
+            }),
+            ("ZSCORE", [key, member]) => Ok(Command::ZScore {
+                key: key.clone(),
+                member: member.clone(),
+            }),
+            _ => Err(CommandError::WrongArity(name)),
+        }
+    }
+
+    // Name of the command in the COMMANDS table
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Append { .. } => "APPEND",
+            Command::Auth { .. } => "AUTH",
+            Command::BgSave => "BGSAVE",
+            Command::BitCount { .. } => "BITCOUNT",
+            Command::BLPop { .. } => "BLPOP",
+            Command::CommandCount | Command::CommandList => "COMMAND",
+            Command::ConfigGet(_) | Command::ConfigSet { .. } => "CONFIG",
+            Command::Copy { .. } => "COPY",
+            Command::DbSize => "DBSIZE",
+            Command::DebugObject(_) | Command::DebugSleep(_) => "DEBUG",
+            Command::Decr(_) => "DECR",
+            Command::Del(_) => "DEL",
+            Command::Discard => "DISCARD",
+            Command::Echo(_) => "ECHO",
+            Command::Exec => "EXEC",
+            Command::Exists(_) => "EXISTS",
+            Command::Expire { .. } => "EXPIRE",
+            Command::ExpireAt { .. } => "EXPIREAT",
+            Command::FlushAll => "FLUSHALL",
+            Command::FlushDb => "FLUSHDB",
+            Command::Get(_) => "GET",
+            Command::GetBit { .. } => "GETBIT",
+            Command::GetDel(_) => "GETDEL",
+            Command::GetEx { .. } => "GETEX",
+            Command::GetRange { .. } => "GETRANGE",
+            Command::HDel { .. } => "HDEL",
+            Command::Hello(_) => "HELLO",
+            Command::HGet { .. } => "HGET",
+            Command::HGetAll(_) => "HGETALL",
+            Command::HIncrBy { .. } => "HINCRBY",
+            Command::HIncrByFloat { .. } => "HINCRBYFLOAT",
+            Command::HSet { .. } => "HSET",
+            Command::Incr(_) => "INCR",
+            Command::IncrBy { .. } => "INCRBY",
+            Command::Info(_) => "INFO",
+            Command::Keys(_) => "KEYS",
+            Command::LLen(_) => "LLEN",
+            Command::LPop { .. } => "LPOP",
+            Command::LPush { .. } => "LPUSH",
+            Command::LRange { .. } => "LRANGE",
+            Command::MGet(_) => "MGET",
+            Command::MSet(_) => "MSET",
+            Command::Multi => "MULTI",
+            Command::ObjectEncoding(_) => "OBJECT",
+            Command::Persist(_) => "PERSIST",
+            Command::Ping(_) => "PING",
+            Command::PSubscribe(_) => "PSUBSCRIBE",
+            Command::Pttl(_) => "PTTL",
+            Command::RPop { .. } => "RPOP",
+            Command::RPush { .. } => "RPUSH",
+            Command::Publish { .. } => "PUBLISH",
+            Command::Quit => "QUIT",
+            Command::Reset => "RESET",
+            Command::Rename { .. } => "RENAME",
+            Command::RenameNx { .. } => "RENAMENX",
+            Command::SAdd { .. } => "SADD",
+            Command::Save => "SAVE",
+            Command::Scan { .. } => "SCAN",
+            Command::SDiff(_) => "SDIFF",
+            Command::SDiffStore { .. } => "SDIFFSTORE",
+            Command::Select(_) => "SELECT",
+            Command::Set { .. } => "SET",
+            Command::SetBit { .. } => "SETBIT",
+            Command::SetNx { .. } => "SETNX",
+            Command::SetRange { .. } => "SETRANGE",
+            Command::SInter(_) => "SINTER",
+            Command::SInterStore { .. } => "SINTERSTORE",
+            Command::SIsMember { .. } => "SISMEMBER",
+            Command::SlowLogGet(_) | Command::SlowLogLen | Command::SlowLogReset => "SLOWLOG",
+            Command::SMembers(_) => "SMEMBERS",
+            Command::SRem { .. } => "SREM",
+            Command::StrLen(_) => "STRLEN",
+            Command::Subscribe(_) => "SUBSCRIBE",
+            Command::SUnion(_) => "SUNION",
+            Command::SUnionStore { .. } => "SUNIONSTORE",
+            Command::Ttl(_) => "TTL",
+            Command::Type(_) => "TYPE",
+            Command::Unsubscribe(_) => "UNSUBSCRIBE",
+            Command::Unwatch => "UNWATCH",
+            Command::Wait => "WAIT",
+            Command::Watch(_) => "WATCH",
+            Command::ZAdd { .. } => "ZADD",
+            Command::ZRange { .. } => "ZRANGE",
+            Command::ZScore { .. } => "ZSCORE",
+        }
+    }
+
+    fn has_flag(&self, flag: &str) -> bool {
+        command_info(self.name()).is_some_and(|info| info.flags.contains(&flag))
+    }
+
+    // Whether the command may take more memory, so it's refused when the
+    // keyspace is full and nothing can be evicted
+    pub fn denied_when_oom(&self) -> bool {
+        self.has_flag("denyoom")
+    }
+
+    // Whether the command may change the keyspace
+    pub fn is_write(&self) -> bool {
+        self.has_flag("write")
+    }
+}
+
+fn parse_integer(arg: &[u8]) -> Result<i64, CommandError> {
+    str::from_utf8(arg)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or(CommandError::NotInteger)
+}
+
+// [EX seconds | PX milliseconds | EXAT unix-time-seconds |
+// PXAT unix-time-milliseconds] [NX | XX] [GET]
+fn parse_set_options(options: &[Bytes]) -> Result<SetOptions, CommandError> {
+    let mut parsed = SetOptions::default();
+    let mut options = options.iter();
+
+    while let Some(option) = options.next() {
+        let option = option.to_ascii_uppercase();
+        match option.as_slice() {
+            b"EX" | b"PX" if parsed.expire.is_none() => {
+                let amount = options.next().ok_or(CommandError::Syntax)?;
+                let unit = if option == b"EX" { 1000 } else { 1 };
+                parsed.expire = Some(parse_expire(amount, unit, "set")?);
+            }
+            // A deadline already past stores a key which is expired right away
+            b"EXAT" | b"PXAT" if parsed.expire.is_none() => {
+                let amount = options.next().ok_or(CommandError::Syntax)?;
+                let unit = if option == b"EXAT" { 1000 } else { 1 };
+                let unix_millis = parse_expire(amount, unit, "set")?.as_millis() as i64;
+                parsed.expire = Some(Duration::from_millis(
+                    millis_until(unix_millis).max(0) as u64
+                ));
+            }
+            b"NX" if parsed.condition == Condition::Always => {
+                parsed.condition = Condition::IfMissing
+            }
+            b"XX" if parsed.condition == Condition::Always => {
+                parsed.condition = Condition::IfExists
+            }
+            b"GET" => parsed.get = true,
+            _ => return Err(CommandError::Syntax),
+        }
+    }
+    Ok(parsed)
+}
+
+// Bit offsets are limited to strings of 512MB, like in Redis
+fn parse_bit_offset(arg: &[u8]) -> Result<u64, CommandError> {
+    parse_integer(arg)
+        .ok()
+        .and_then(|offset| u64::try_from(offset).ok())
+        .filter(|offset| *offset < 1 << 32)
+        .ok_or_else(|| CommandError::Invalid("bit offset is not an integer or out of range".into()))
+}
+
+// A positive expiration, in `unit` milliseconds
+fn parse_expire(arg: &[u8], unit: i64, command: &str) -> Result<Duration, CommandError> {
+    match parse_integer(arg)?.checked_mul(unit) {
+        Some(millis) if millis > 0 => Ok(Duration::from_millis(millis as u64)),
+        _ => Err(CommandError::Invalid(format!(
+            "invalid expire time in '{}' command",
+            command
+        ))),
+    }
+}
+
+// [EX seconds | PX milliseconds | EXAT unix-time-seconds |
+// PXAT unix-time-milliseconds | PERSIST]
+fn parse_getex_options(options: &[Bytes]) -> Result<GetExpire, CommandError> {
+    let invalid = || CommandError::Invalid("invalid expire time in 'getex' command".into());
+    let option = options.first().map(|option| option.to_ascii_uppercase());
+    match (option.as_deref(), options) {
+        (None, []) => Ok(GetExpire::Keep),
+        (Some(b"EX"), [_, seconds]) => Ok(GetExpire::In(parse_expire(seconds, 1000, "getex")?)),
+        (Some(b"PX"), [_, millis]) => Ok(GetExpire::In(parse_expire(millis, 1, "getex")?)),
+        (Some(b"EXAT"), [_, seconds]) => match parse_integer(seconds)? {
+            seconds if seconds > 0 => seconds
+                .checked_mul(1000)
+                .map(GetExpire::At)
+                .ok_or_else(invalid),
+            _ => Err(invalid()),
+        },
+        (Some(b"PXAT"), [_, millis]) => match parse_integer(millis)? {
+            millis if millis > 0 => Ok(GetExpire::At(millis)),
+            _ => Err(invalid()),
+        },
+        (Some(b"PERSIST"), [_]) => Ok(GetExpire::Persist),
+        _ => Err(CommandError::Syntax),
+    }
+}
+
+// [DB destination-db] [REPLACE]
+fn parse_copy_options(options: &[Bytes]) -> Result<(Option<i64>, bool), CommandError> {
+    let (mut db, mut replace) = (None, false);
+    let mut options = options.iter();
+
+    while let Some(option) = options.next() {
+        match option.to_ascii_uppercase().as_slice() {
+            b"DB" if db.is_none() => {
+                let index = options.next().ok_or(CommandError::Syntax)?;
+                db = Some(parse_integer(index)?);
+            }
+            b"REPLACE" if !replace => replace = true,
+            _ => return Err(CommandError::Syntax),
+        }
+    }
+    Ok((db, replace))
+}
+
+// Optional count of LPOP and RPOP
+fn parse_pop_count(args: &[Bytes]) -> Result<Option<usize>, CommandError> {
+    match args {
+        [] => Ok(None),
+        [count] => usize::try_from(parse_integer(count)?)
+            .map(Some)
+            .map_err(|_| CommandError::Invalid("value is out of range, must be positive".into())),
+        _ => Err(CommandError::Syntax),
+    }
+}
+
+// Timeout of a blocking command, in seconds. 0 waits forever.
+fn parse_timeout(arg: &[u8]) -> Result<Option<Duration>, CommandError> {
+    let seconds = parse_float(arg)
+        .ok()
+        .filter(|seconds| seconds.is_finite())
+        .ok_or_else(|| CommandError::Invalid("timeout is not a float or out of range".into()))?;
+    if seconds < 0.0 {
+        return Err(CommandError::Invalid("timeout is negative".into()));
+    }
+    if seconds == 0.0 {
+        return Ok(None);
+    }
+    Duration::try_from_secs_f64(seconds)
+        .map(Some)
+        .map_err(|_| CommandError::Invalid("timeout is out of range".into()))
+}
+
+// Scores can be any float, infinities included, but NaN
+fn parse_float(arg: &[u8]) -> Result<f64, CommandError> {
+    str::from_utf8(arg)
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|n| !n.is_nan())
+        .ok_or_else(|| CommandError::Invalid("value is not a valid float".into()))
+}
+
+// Indexes from `start` to `stop` included, as taken by LRANGE and the like:
+// negative ones count from the end, and out of range ones are clamped. None
+// if the range is empty.
+pub(crate) fn index_range(start: i64, stop: i64, len: usize) -> Option<RangeInclusive<usize>> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (len + start).max(0)
+    } else {
+        start
+    };
+    let stop = if stop < 0 {
+        len + stop
+    } else {
+        stop.min(len - 1)
+    };
+    (start <= stop).then_some(start as usize..=stop as usize)
+}
+
+// [ASYNC | SYNC]. Flushing always happens right away, so both are accepted
+// and behave the same.
+fn parse_flush_mode(mode: &[Bytes]) -> Result<(), CommandError> {
+    match mode {
+        [] => Ok(()),
+        [mode] if mode.eq_ignore_ascii_case(b"ASYNC") || mode.eq_ignore_ascii_case(b"SYNC") => {
+            Ok(())
+        }
+        _ => Err(CommandError::Syntax),
+    }
+}
+
+// SCAN cursor [MATCH pattern] [COUNT count]
+fn parse_scan(cursor: &[u8], options: &[Bytes]) -> Result<Command, CommandError> {
+    let cursor = str::from_utf8(cursor)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| CommandError::Invalid("invalid cursor".into()))?;
+    let mut pattern = None;
+    let mut count = 10;
+
+    for option in options.chunks(2) {
+        match option {
+            [name, value] if name.eq_ignore_ascii_case(b"MATCH") => pattern = Some(value.clone()),
+            [name, value] if name.eq_ignore_ascii_case(b"COUNT") => {
+                count = match parse_integer(value)? {
+                    count if count >= 1 => count as usize,
+                    _ => return Err(CommandError::Syntax),
+                }
+            }
+            _ => return Err(CommandError::Syntax),
+        }
+    }
+
+    Ok(Command::Scan {
+        cursor,
+        pattern,
+        count,
+    })
+}
+
+// An empty directory for the test to write files into, named after it
+#[cfg(test)]
+pub(crate) fn temporary_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("yarrs-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Server, request and the receiving end of its connection, to run a command
+// handler against and inspect what it replied
+#[cfg(test)]
+pub(crate) fn setup_command_test(
+    frame: Frame,
+) -> (
+    crate::server::Server,
+    tokio::sync::mpsc::Receiver<crate::messages::ServerMessage>,
+    crate::messages::Request,
+) {
+    let mut server = crate::server::Server::new("0.0.0.0".into(), 0);
+    let (connection_sender, connection_receiver) = tokio::sync::mpsc::channel(32);
+    server.clients.insert(
+        0,
+        crate::server::Client {
+            id: 0,
+            sender: connection_sender.clone(),
+            state: crate::server::ConnectionState::default(),
+        },
+    );
+    let request = crate::messages::Request {
+        client_id: 0,
+        frame,
+        connection: connection_sender,
+    };
+
+    (server, connection_receiver, request)
+}
+
+// A second client, with its own connection, on the same server
+#[cfg(test)]
+pub(crate) fn connect_test_client(
+    server: &mut crate::server::Server,
+    id: u64,
+) -> (
+    tokio::sync::mpsc::Receiver<crate::messages::ServerMessage>,
+    crate::messages::Request,
+) {
+    let (sender, receiver) = tokio::sync::mpsc::channel(32);
+    server.clients.insert(
+        id,
+        crate::server::Client {
+            id,
+            sender: sender.clone(),
+            state: crate::server::ConnectionState::default(),
+        },
+    );
+    let request = crate::messages::Request {
+        client_id: id,
+        frame: crate::cmd!(),
+        connection: sender,
+    };
+    (receiver, request)
+}
+
+// The same client sending another command
+#[cfg(test)]
+pub(crate) fn with_frame(
+    request: &crate::messages::Request,
+    frame: Frame,
+) -> crate::messages::Request {
+    crate::messages::Request {
+        frame,
+        connection: request.connection.clone(),
+        ..*request
+    }
+}
+
+// Send the command as the test client, going through transactions like the
+// server does, and return the reply
+#[cfg(test)]
+pub(crate) async fn send_command(
+    server: &mut crate::server::Server,
+    receiver: &mut tokio::sync::mpsc::Receiver<crate::messages::ServerMessage>,
+    request: &crate::messages::Request,
+    frame: Frame,
+) -> crate::messages::ServerMessage {
+    let request = crate::messages::Request {
+        frame,
+        connection: request.connection.clone(),
+        ..*request
+    };
+    if let Err(error) = server.handle_message(&request).await {
+        request.error(error).await;
+    }
+    receiver.try_recv().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ops::RangeInclusive, time::Duration};
+
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use super::{
+        command_info,
+        getex::GetExpire,
+        index_range,
+        set::{Condition, SetOptions},
+        Command, CommandError, COMMANDS,
+    };
+    use crate::{
+        cmd,
+        resp::types::{Frame, RespVersion},
+    };
+
+    #[rstest]
+    #[case(cmd!("GET", "key"), Command::Get(Bytes::from("key")))]
+    #[case(cmd!("GETEX", "key"), Command::GetEx { key: Bytes::from("key"), expire: GetExpire::Keep })]
+    #[case(
+        cmd!("getex", "key", "ex", "2"),
+        Command::GetEx { key: Bytes::from("key"), expire: GetExpire::In(Duration::from_secs(2)) }
+    )]
+    #[case(cmd!("GETEX", "key", "EXAT", "5"), Command::GetEx { key: Bytes::from("key"), expire: GetExpire::At(5000) })]
+    #[case(cmd!("get", "key"), Command::Get(Bytes::from("key")))]
+    #[case(cmd!("PiNg"), Command::Ping(None))]
+    #[case(cmd!("quit"), Command::Quit)]
+    #[case(cmd!("reset"), Command::Reset)]
+    #[case(cmd!("slowlog", "get"), Command::SlowLogGet(10))]
+    #[case(cmd!("SLOWLOG", "GET", "-1"), Command::SlowLogGet(usize::MAX))]
+    #[case(cmd!("slowlog", "len"), Command::SlowLogLen)]
+    #[case(cmd!("slowlog", "reset"), Command::SlowLogReset)]
+    #[case(cmd!("dbsize"), Command::DbSize)]
+    #[case(cmd!("WAIT", "1", "100"), Command::Wait)]
+    #[case(cmd!("lpop", "key"), Command::LPop { key: Bytes::from("key"), count: None })]
+    #[case(cmd!("RPOP", "key", "3"), Command::RPop { key: Bytes::from("key"), count: Some(3) })]
+    #[case(
+        cmd!("BLPOP", "a", "b", "1.5"),
+        Command::BLPop {
+            keys: vec![Bytes::from("a"), Bytes::from("b")],
+            timeout: Some(Duration::from_millis(1500)),
+        }
+    )]
+    #[case(cmd!("blpop", "a", "0"), Command::BLPop { keys: vec![Bytes::from("a")], timeout: None })]
+    #[case(cmd!("command"), Command::CommandList)]
+    #[case(cmd!("COMMAND", "count"), Command::CommandCount)]
+    #[case(
+        cmd!("copy", "a", "b", "db", "2", "replace"),
+        Command::Copy { from: Bytes::from("a"), to: Bytes::from("b"), db: Some(2), replace: true }
+    )]
+    #[case(
+        cmd!("rename", "a", "b"),
+        Command::Rename { from: Bytes::from("a"), to: Bytes::from("b") }
+    )]
+    #[case(
+        cmd!("RENAMENX", "a", "b"),
+        Command::RenameNx { from: Bytes::from("a"), to: Bytes::from("b") }
+    )]
+    #[case(cmd!("PING", "hi"), Command::Ping(Some(Bytes::from("hi"))))]
+    #[case(cmd!("ECHO", "hi"), Command::Echo(Bytes::from("hi")))]
+    #[case(
+        cmd!("SET", "key", "value"),
+        Command::Set { key: Bytes::from("key"), value: Bytes::from("value"), options: SetOptions::default() }
+    )]
+    #[case(
+        cmd!("SET", "key", "value", "ex", "10", "NX", "get"),
+        Command::Set {
+            key: Bytes::from("key"),
+            value: Bytes::from("value"),
+            options: SetOptions {
+                expire: Some(Duration::from_secs(10)),
+                condition: Condition::IfMissing,
+                get: true,
+            },
+        }
+    )]
+    #[case(
+        cmd!("SET", "key", "value", "PX", "1500", "XX"),
+        Command::Set {
+            key: Bytes::from("key"),
+            value: Bytes::from("value"),
+            options: SetOptions {
+                expire: Some(Duration::from_millis(1500)),
+                condition: Condition::IfExists,
+                get: false,
+            },
+        }
+    )]
+    #[case(
+        cmd!("SET", "key", "value", "PXAT", "1"),
+        Command::Set {
+            key: Bytes::from("key"),
+            value: Bytes::from("value"),
+            options: SetOptions { expire: Some(Duration::ZERO), ..SetOptions::default() },
+        }
+    )]
+    #[case(
+        cmd!("SETEX", "key", "10", "value"),
+        Command::Set {
+            key: Bytes::from("key"),
+            value: Bytes::from("value"),
+            options: SetOptions { expire: Some(Duration::from_secs(10)), ..SetOptions::default() },
+        }
+    )]
+    #[case(
+        cmd!("EXPIREAT", "key", "1700000000"),
+        Command::ExpireAt { key: Bytes::from("key"), unix_millis: 1_700_000_000_000 }
+    )]
+    #[case(
+        cmd!("pexpireat", "key", "1700000000123"),
+        Command::ExpireAt { key: Bytes::from("key"), unix_millis: 1_700_000_000_123 }
+    )]
+    #[case(cmd!("persist", "key"), Command::Persist(Bytes::from("key")))]
+    #[case(cmd!("object", "encoding", "key"), Command::ObjectEncoding(Bytes::from("key")))]
+    #[case(
+        cmd!("PSETEX", "key", "10", "value"),
+        Command::Set {
+            key: Bytes::from("key"),
+            value: Bytes::from("value"),
+            options: SetOptions { expire: Some(Duration::from_millis(10)), ..SetOptions::default() },
+        }
+    )]
+    #[case(
+        cmd!("SETNX", "key", "value"),
+        Command::SetNx { key: Bytes::from("key"), value: Bytes::from("value") }
+    )]
+    #[case(
+        cmd!("EXPIRE", "key", "-10"),
+        Command::Expire { key: Bytes::from("key"), seconds: -10 }
+    )]
+    #[case(cmd!("ttl", "key"), Command::Ttl(Bytes::from("key")))]
+    #[case(cmd!("incr", "key"), Command::Incr(Bytes::from("key")))]
+    #[case(cmd!("hello"), Command::Hello(None))]
+    #[case(cmd!("keys", "*"), Command::Keys(Bytes::from("*")))]
+    #[case(
+        cmd!("scan", "0"),
+        Command::Scan { cursor: 0, pattern: None, count: 10 }
+    )]
+    #[case(
+        cmd!("scan", "42", "match", "user:*", "COUNT", "100"),
+        Command::Scan { cursor: 42, pattern: Some(Bytes::from("user:*")), count: 100 }
+    )]
+    #[case(
+        cmd!("subscribe", "a", "b"),
+        Command::Subscribe(vec![Bytes::from("a"), Bytes::from("b")])
+    )]
+    #[case(cmd!("unsubscribe"), Command::Unsubscribe(vec![]))]
+    #[case(
+        cmd!("watch", "a", "b"),
+        Command::Watch(vec![Bytes::from("a"), Bytes::from("b")])
+    )]
+    #[case(cmd!("unwatch"), Command::Unwatch)]
+    #[case(
+        cmd!("psubscribe", "news.*"),
+        Command::PSubscribe(vec![Bytes::from("news.*")])
+    )]
+    #[case(cmd!("hello", "3"), Command::Hello(Some(3)))]
+    #[case(cmd!("decr", "key"), Command::Decr(Bytes::from("key")))]
+    #[case(
+        cmd!("incrby", "key", "-5"),
+        Command::IncrBy { key: Bytes::from("key"), delta: -5 }
+    )]
+    #[case(cmd!("pttl", "key"), Command::Pttl(Bytes::from("key")))]
+    #[case(cmd!("select", "1"), Command::Select(1))]
+    #[case(cmd!("flushdb"), Command::FlushDb)]
+    #[case(cmd!("save"), Command::Save)]
+    #[case(cmd!("BGSAVE"), Command::BgSave)]
+    #[case(
+        cmd!("auth", "secret"),
+        Command::Auth { username: None, password: Bytes::from("secret") }
+    )]
+    #[case(
+        cmd!("AUTH", "default", "secret"),
+        Command::Auth { username: Some(Bytes::from("default")), password: Bytes::from("secret") }
+    )]
+    #[case(cmd!("flushdb", "async"), Command::FlushDb)]
+    #[case(cmd!("FLUSHALL", "SYNC"), Command::FlushAll)]
+    #[case(
+        cmd!("rpush", "key", "a", "b"),
+        Command::RPush { key: Bytes::from("key"), values: vec![Bytes::from("a"), Bytes::from("b")] }
+    )]
+    #[case(
+        cmd!("lrange", "key", "0", "-1"),
+        Command::LRange { key: Bytes::from("key"), start: 0, stop: -1 }
+    )]
+    #[case(cmd!("llen", "key"), Command::LLen(Bytes::from("key")))]
+    #[case(cmd!("del", "a", "b"), Command::Del(vec![Bytes::from("a"), Bytes::from("b")]))]
+    #[case(cmd!("exists", "a"), Command::Exists(vec![Bytes::from("a")]))]
+    #[case(cmd!("type", "key"), Command::Type(Bytes::from("key")))]
+    #[case(cmd!("info"), Command::Info(None))]
+    #[case(cmd!("info", "memory"), Command::Info(Some(Bytes::from("memory"))))]
+    #[case(cmd!("config", "get", "max*"), Command::ConfigGet(Bytes::from("max*")))]
+    #[case(cmd!("debug", "object", "key"), Command::DebugObject(Bytes::from("key")))]
+    #[case(
+        cmd!("DEBUG", "SLEEP", "0.5"),
+        Command::DebugSleep(Duration::from_millis(500))
+    )]
+    #[case(
+        cmd!("CONFIG", "SET", "maxmemory", "1mb"),
+        Command::ConfigSet { parameter: Bytes::from("maxmemory"), value: Bytes::from("1mb") }
+    )]
+    #[case(cmd!("mget", "a", "b"), Command::MGet(vec![Bytes::from("a"), Bytes::from("b")]))]
+    #[case(
+        cmd!("mset", "a", "1", "b", "2"),
+        Command::MSet(vec![(Bytes::from("a"), Bytes::from("1")), (Bytes::from("b"), Bytes::from("2"))])
+    )]
+    #[case(
+        cmd!("getrange", "key", "0", "-1"),
+        Command::GetRange { key: Bytes::from("key"), start: 0, end: -1 }
+    )]
+    #[case(
+        cmd!("setbit", "key", "7", "1"),
+        Command::SetBit { key: Bytes::from("key"), offset: 7, value: true }
+    )]
+    #[case(cmd!("getbit", "key", "7"), Command::GetBit { key: Bytes::from("key"), offset: 7 })]
+    #[case(cmd!("bitcount", "key"), Command::BitCount { key: Bytes::from("key"), range: None })]
+    #[case(
+        cmd!("bitcount", "key", "1", "-1"),
+        Command::BitCount { key: Bytes::from("key"), range: Some((1, -1)) }
+    )]
+    #[case(
+        cmd!("setrange", "key", "3", "abc"),
+        Command::SetRange { key: Bytes::from("key"), offset: 3, value: Bytes::from("abc") }
+    )]
+    #[case(
+        cmd!("hset", "key", "a", "1", "b", "2"),
+        Command::HSet {
+            key: Bytes::from("key"),
+            fields: vec![(Bytes::from("a"), Bytes::from("1")), (Bytes::from("b"), Bytes::from("2"))],
+        }
+    )]
+    #[case(
+        cmd!("zadd", "key", "1.5", "a", "-inf", "b"),
+        Command::ZAdd {
+            key: Bytes::from("key"),
+            members: vec![(1.5, Bytes::from("a")), (f64::NEG_INFINITY, Bytes::from("b"))],
+        }
+    )]
+    #[case(
+        cmd!("zrange", "key", "0", "-1", "WithScores"),
+        Command::ZRange { key: Bytes::from("key"), start: 0, stop: -1, with_scores: true }
+    )]
+    #[case(
+        cmd!("sadd", "key", "a", "b"),
+        Command::SAdd { key: Bytes::from("key"), members: vec![Bytes::from("a"), Bytes::from("b")] }
+    )]
+    #[case(
+        cmd!("sismember", "key", "a"),
+        Command::SIsMember { key: Bytes::from("key"), member: Bytes::from("a") }
+    )]
+    #[case(
+        cmd!("sinterstore", "dest", "a", "b"),
+        Command::SInterStore { destination: Bytes::from("dest"), keys: vec![Bytes::from("a"), Bytes::from("b")] }
+    )]
+    #[case(
+        cmd!("hdel", "key", "a"),
+        Command::HDel { key: Bytes::from("key"), fields: vec![Bytes::from("a")] }
+    )]
+    fn test_from_frame(#[case] frame: Frame, #[case] expected: Command) {
+        assert!(command_info(expected.name()).is_some());
+        assert_eq!(Ok(expected), Command::from_frame(frame));
+    }
+
+    #[rstest]
+    #[case(cmd!("SET", "key"), CommandError::WrongArity("SET".into()))]
+    #[case(cmd!("GET"), CommandError::WrongArity("GET".into()))]
+    #[case(cmd!("LPUSH", "key"), CommandError::WrongArity("LPUSH".into()))]
+    #[case(cmd!("HSET", "key", "a"), CommandError::WrongArity("HSET".into()))]
+    #[case(cmd!("MSET", "a", "1", "b"), CommandError::WrongArity("MSET".into()))]
+    #[case(cmd!("MGET"), CommandError::WrongArity("MGET".into()))]
+    #[case(cmd!("NOPE", "x"), CommandError::Unknown("NOPE".into()))]
+    #[case(cmd!("GET", "a", "b"), CommandError::WrongArity("GET".into()))]
+    #[case(cmd!("DBSIZE", "a"), CommandError::WrongArity("DBSIZE".into()))]
+    #[case(cmd!("ZADD", "key", "1"), CommandError::WrongArity("ZADD".into()))]
+    fn test_from_frame_error(#[case] frame: Frame, #[case] expected: CommandError) {
+        assert_eq!(Err(expected), Command::from_frame(frame));
+    }
+
+    #[rstest]
+    #[case(CommandError::Unknown("NOPE".into()), "ERR unknown command 'NOPE'")]
+    #[case(
+        CommandError::WrongArity("LPUSH".into()),
+        "ERR wrong number of arguments for 'lpush' command"
+    )]
+    #[case(
+        CommandError::WrongType,
+        "WRONGTYPE Operation against a key holding the wrong kind of value"
+    )]
+    #[case(CommandError::Syntax, "ERR syntax error")]
+    #[case(
+        CommandError::NotInteger,
+        "ERR value is not an integer or out of range"
+    )]
+    #[case(CommandError::OutOfRange("offset".into()), "ERR offset is out of range")]
+    #[case(CommandError::Invalid("invalid cursor".into()), "ERR invalid cursor")]
+    fn test_error_frame(#[case] error: CommandError, #[case] expected: &str) {
+        assert_eq!(Frame::Error(expected.into()), error.to_frame());
+    }
+
+    #[test]
+    fn test_unknown_command_reply_stays_on_one_line() {
+        let frame = Frame::Array(vec![Frame::Bulk(Bytes::from("X\r\n+INJ"))]);
+        let error = Command::from_frame(frame).unwrap_err();
+
+        assert_eq!(
+            &b"-ERR unknown command 'X  +INJ'\r\n"[..],
+            &error.to_frame().encode(RespVersion::V2)[..]
+        );
+    }
+
+    #[rstest]
+    #[case(0, -1, 4, Some(0..=3))]
+    #[case(-2, -1, 4, Some(2..=3))]
+    #[case(-10, 10, 4, Some(0..=3))]
+    #[case(3, 1, 4, None)]
+    #[case(0, -1, 0, None)]
+    fn test_index_range(
+        #[case] start: i64,
+        #[case] stop: i64,
+        #[case] len: usize,
+        #[case] expected: Option<RangeInclusive<usize>>,
+    ) {
+        assert_eq!(expected, index_range(start, stop, len));
+    }
+
+    #[rstest]
+    #[case(cmd!(), CommandError::Invalid("missing command name".into()))]
+    #[case(Frame::Bulk(Bytes::from("GET")), CommandError::Invalid("request must be an array of bulk strings".into()))]
+    #[case(Frame::Array(vec![Frame::Bulk(Bytes::from("GET")), Frame::Integer(1)]), CommandError::Invalid("request must be an array of bulk strings".into()))]
+    #[case(cmd!("EXPIRE", "key", "ten"), CommandError::NotInteger)]
+    #[case(cmd!("EXPIRE", "key", "99999999999999999999"), CommandError::NotInteger)]
+    #[case(cmd!("EXPIREAT", "key", "9223372036854775807"), CommandError::Invalid("invalid expire time in 'expireat' command".into()))]
+    #[case(cmd!("SET", "key", "value", "other"), CommandError::Syntax)]
+    #[case(cmd!("SET", "key", "value", "NX", "XX"), CommandError::Syntax)]
+    #[case(cmd!("SET", "key", "value", "EX", "1", "PX", "1000"), CommandError::Syntax)]
+    #[case(cmd!("SET", "key", "value", "EX"), CommandError::Syntax)]
+    #[case(cmd!("SET", "key", "value", "EX", "0"), CommandError::Invalid("invalid expire time in 'set' command".into()))]
+    #[case(cmd!("SET", "key", "value", "EX", "9223372036854775807"), CommandError::Invalid("invalid expire time in 'set' command".into()))]
+    #[case(cmd!("SETEX", "key", "-1", "value"), CommandError::Invalid("invalid expire time in 'setex' command".into()))]
+    #[case(cmd!("SET", "key", "value", "EXAT", "0"), CommandError::Invalid("invalid expire time in 'set' command".into()))]
+    #[case(cmd!("SET", "key", "value", "PX", "10", "PXAT", "10"), CommandError::Syntax)]
+    #[case(cmd!("CONFIG", "GET"), CommandError::Invalid("unknown subcommand or wrong number of arguments for 'GET'".into()))]
+    #[case(cmd!("DEBUG", "SLEEP", "-1"), CommandError::Invalid("invalid sleep time".into()))]
+    #[case(cmd!("DEBUG", "JMAP"), CommandError::Invalid("unknown subcommand or wrong number of arguments for 'JMAP'".into()))]
+    #[case(cmd!("OBJECT", "FREQ", "key"), CommandError::Invalid("unknown subcommand or wrong number of arguments for 'FREQ'".into()))]
+    #[case(cmd!("COMMAND", "DOCS"), CommandError::Invalid("unknown subcommand or wrong number of arguments for 'DOCS'".into()))]
+    #[case(cmd!("CONFIG", "REWRITE"), CommandError::Invalid("unknown subcommand or wrong number of arguments for 'REWRITE'".into()))]
+    #[case(cmd!("ZADD", "key", "nan", "a"), CommandError::Invalid("value is not a valid float".into()))]
+    #[case(cmd!("ZADD", "key", "one", "a"), CommandError::Invalid("value is not a valid float".into()))]
+    #[case(cmd!("ZRANGE", "key", "0", "1", "LIMIT"), CommandError::Syntax)]
+    #[case(cmd!("SCAN", "-1"), CommandError::Invalid("invalid cursor".into()))]
+    #[case(cmd!("SCAN", "0", "COUNT", "0"), CommandError::Syntax)]
+    #[case(cmd!("SCAN", "0", "MATCH"), CommandError::Syntax)]
+    #[case(cmd!("SCAN", "0", "TYPE", "string"), CommandError::Syntax)]
+    #[case(cmd!("SELECT", "one"), CommandError::NotInteger)]
+    #[case(cmd!("SETBIT", "key", "-1", "1"), CommandError::Invalid("bit offset is not an integer or out of range".into()))]
+    #[case(cmd!("SETBIT", "key", "4294967296", "1"), CommandError::Invalid("bit offset is not an integer or out of range".into()))]
+    #[case(cmd!("SETBIT", "key", "0", "2"), CommandError::Invalid("bit is not an integer or out of range".into()))]
+    #[case(cmd!("BITCOUNT", "key", "0"), CommandError::Syntax)]
+    #[case(cmd!("WAIT", "1", "-1"), CommandError::Invalid("timeout is negative".into()))]
+    #[case(cmd!("WAIT", "all", "0"), CommandError::NotInteger)]
+    #[case(cmd!("LPOP", "key", "-1"), CommandError::Invalid("value is out of range, must be positive".into()))]
+    #[case(cmd!("LPOP", "key", "1", "2"), CommandError::Syntax)]
+    #[case(cmd!("BLPOP", "key", "-1"), CommandError::Invalid("timeout is negative".into()))]
+    #[case(cmd!("BLPOP", "key", "soon"), CommandError::Invalid("timeout is not a float or out of range".into()))]
+    #[case(cmd!("BLPOP", "key", "1e20"), CommandError::Invalid("timeout is out of range".into()))]
+    #[case(cmd!("FLUSHDB", "LATER"), CommandError::Syntax)]
+    #[case(cmd!("GETEX", "key", "EX"), CommandError::Syntax)]
+    #[case(cmd!("GETEX", "key", "EX", "1", "PERSIST"), CommandError::Syntax)]
+    #[case(cmd!("GETEX", "key", "PERSIST", "1"), CommandError::Syntax)]
+    #[case(cmd!("GETEX", "key", "PX", "0"), CommandError::Invalid("invalid expire time in 'getex' command".into()))]
+    #[case(cmd!("GETEX", "key", "EXAT", "-5"), CommandError::Invalid("invalid expire time in 'getex' command".into()))]
+    #[case(cmd!("COPY", "a", "b", "DB"), CommandError::Syntax)]
+    #[case(cmd!("COPY", "a", "b", "DB", "one"), CommandError::NotInteger)]
+    #[case(cmd!("COPY", "a", "b", "REPLACE", "REPLACE"), CommandError::Syntax)]
+    #[case(cmd!("SLOWLOG", "GET", "-2"), CommandError::Invalid("count should be greater than or equal to -1".into()))]
+    #[case(cmd!("SLOWLOG", "LEN", "1"), CommandError::Invalid("unknown subcommand or wrong number of arguments for 'LEN'".into()))]
+    fn test_from_frame_invalid(#[case] frame: Frame, #[case] expected: CommandError) {
+        assert_eq!(Err(expected), Command::from_frame(frame));
+    }
+
+    #[rstest]
+    #[case(cmd!("SET", "key", "value"), true)]
+    #[case(cmd!("DEL", "key"), true)]
+    #[case(cmd!("RENAME", "a", "b"), true)]
+    #[case(cmd!("FLUSHALL"), true)]
+    #[case(cmd!("GET", "key"), false)]
+    #[case(cmd!("SELECT", "1"), false)]
+    #[case(cmd!("MULTI"), false)]
+    #[case(cmd!("SETEX", "key", "10", "value"), true)]
+    #[case(cmd!("BLPOP", "key", "0"), true)]
+    fn test_is_write(#[case] frame: Frame, #[case] expected: bool) {
+        assert_eq!(expected, Command::from_frame(frame).unwrap().is_write());
+    }
+
+    #[rstest]
+    #[case(cmd!("SET", "key", "value"), true)]
+    #[case(cmd!("PSETEX", "key", "10", "value"), true)]
+    #[case(cmd!("ZADD", "key", "1", "a"), true)]
+    #[case(cmd!("DEL", "key"), false)]
+    #[case(cmd!("GET", "key"), false)]
+    fn test_denied_when_oom(#[case] frame: Frame, #[case] expected: bool) {
+        assert_eq!(
+            expected,
+            Command::from_frame(frame).unwrap().denied_when_oom()
+        );
+    }
+
+    #[test]
+    fn test_command_table() {
+        assert!(COMMANDS.windows(2).all(|pair| pair[0].name < pair[1].name));
+        for info in COMMANDS {
+            assert_ne!(
+                Err(CommandError::Unknown(info.name.into())),
+                Command::from_frame(cmd!(info.name)),
+            );
+            assert!(
+                !info.flags.contains(&"denyoom") || info.flags.contains(&"write"),
+                "{} may take memory without writing",
+                info.name
+            );
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use bytes::Bytes;
+
+use crate::{messages::Request, resp::types::Frame, server::Server, store::Value};
+
+// Set all the keys at once, no client seeing only some of them set
+pub async fn command(server: &Server, request: &Request, pairs: Vec<(Bytes, Bytes)>) {
+    server.db(request).set_many(
+        pairs
+            .into_iter()
+            .map(|(key, value)| (key, Value::String(value))),
+    );
+    request.data(Frame::ok()).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{mset::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_mset() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("mset", "a", "1", "b", "2"));
+        server
+            .db(&request)
+            .set(Bytes::from("a"), Value::List([Bytes::from("x")].into()));
+
+        let pairs = vec![
+            (Bytes::from("a"), Bytes::from("1")),
+            (Bytes::from("b"), Bytes::from("2")),
+        ];
+        command(&server, &request, pairs).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::ok())
+        );
+        assert_eq!(
+            Some(Value::String(Bytes::from("1"))),
+            server.db(&request).get(b"a")
+        );
+        assert_eq!(
+            Some(Value::String(Bytes::from("2"))),
+            server.db(&request).get(b"b")
+        );
+    }
+}
@@ -0,0 +1,13 @@
+use crate::{
+    messages::Request,
+    resp::types::Frame,
+    server::{Server, Transaction},
+};
+
+// Start queuing the client's commands, see Server::handle_message
+pub async fn command(server: &mut Server, request: &Request) {
+    if let Some(client) = server.clients.get_mut(&request.client_id) {
+        client.state.transaction = Some(Transaction::default());
+    }
+    request.data(Frame::ok()).await;
+}
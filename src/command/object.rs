@@ -0,0 +1,67 @@
+use bytes::Bytes;
+
+use crate::{command::CommandError, messages::Request, resp::types::Frame, server::Server};
+
+// OBJECT ENCODING: how the value at the key would be stored by Redis
+pub async fn encoding(server: &Server, request: &Request, key: Bytes) {
+    match server
+        .db(request)
+        .view(&key, |value| value.map(|value| value.encoding()))
+    {
+        Some(encoding) => request.data(Frame::Simple(encoding.into())).await,
+        None => {
+            request
+                .error(CommandError::Invalid("no such key".into()))
+                .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{send_command, setup_command_test, CommandError},
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::ServerError,
+    };
+
+    #[rstest]
+    #[case("12345", "int")]
+    #[case("hello world", "embstr")]
+    #[case(&"x".repeat(100), "raw")]
+    #[tokio::test]
+    async fn test_object_encoding(#[case] value: &str, #[case] expected: &str) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("object"));
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        send(cmd!("SET", "key", value.to_string())).await;
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Simple(expected.into())),
+            send(cmd!("OBJECT", "ENCODING", "key")).await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_object_encoding_missing() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("object"));
+
+        assert_eq!(
+            ServerMessage::Error(ServerError::Command(CommandError::Invalid(
+                "no such key".into()
+            ))),
+            send_command(
+                &mut server,
+                &mut receiver,
+                &request,
+                cmd!("OBJECT", "ENCODING", "missing")
+            )
+            .await
+        );
+    }
+}
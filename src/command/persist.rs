@@ -0,0 +1,50 @@
+use bytes::Bytes;
+
+use crate::{messages::Request, resp::types::Frame, server::Server};
+
+// Remove the key's expiration, replying 1 if it had one
+pub async fn command(server: &Server, request: &Request, key: Bytes) {
+    let persisted = server.db(request).persist(&key);
+    request.data(Frame::Integer(persisted as i64)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{persist::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn test_persist() {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!("persist", "key"));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("value")));
+        server.db(&request).expire(b"key", Duration::from_secs(10));
+
+        command(&server, &request, Bytes::from("key")).await;
+        command(&server, &request, Bytes::from("key")).await;
+        command(&server, &request, Bytes::from("missing")).await;
+
+        for expected in [1, 0, 0] {
+            assert_eq!(
+                connection_receiver.try_recv().unwrap(),
+                ServerMessage::Data(Frame::Integer(expected))
+            );
+        }
+        assert_eq!(Some(None), server.db(&request).ttl(b"key"));
+        tokio::time::advance(Duration::from_secs(20)).await;
+        assert_eq!(
+            Some(Value::String(Bytes::from("value"))),
+            server.db(&request).get(b"key")
+        );
+    }
+}
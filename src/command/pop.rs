@@ -0,0 +1,308 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+
+use crate::{
+    blocking, cmd,
+    command::{push::End, CommandError},
+    messages::Request,
+    resp::types::Frame,
+    server::Server,
+    store::{Db, Value},
+};
+
+// Take up to `count` elements from the given end of the list, deleting it
+// once empty. None if the key is missing.
+pub(crate) fn pop(
+    db: &Db,
+    key: Bytes,
+    count: usize,
+    end: End,
+) -> Result<Option<Vec<Bytes>>, CommandError> {
+    db.update(key, |value| {
+        let list = match value {
+            Some(Value::List(list)) => list,
+            Some(_) => return Err(CommandError::WrongType),
+            None => return Ok((None, false)),
+        };
+        let count = count.min(list.len());
+        let popped = match end {
+            End::Left => list.drain(..count).collect(),
+            End::Right => list.drain(list.len() - count..).rev().collect(),
+        };
+        if list.is_empty() {
+            *value = None;
+        }
+        Ok((Some(popped), count > 0))
+    })
+}
+
+// LPOP and RPOP: a single element, or an array of up to `count` of them
+pub async fn command(
+    server: &Server,
+    request: &Request,
+    key: Bytes,
+    count: Option<usize>,
+    end: End,
+) {
+    let result = pop(server.db(request), key, count.unwrap_or(1), end);
+
+    let reply = match (result, count) {
+        (Err(error), _) => return request.error(error).await,
+        (Ok(None), _) => Frame::Null,
+        (Ok(Some(popped)), Some(_)) => Frame::Array(popped.into_iter().map(Frame::Bulk).collect()),
+        (Ok(Some(mut popped)), None) => Frame::Bulk(popped.remove(0)),
+    };
+    request.data(reply).await;
+}
+
+// BLPOP: pop from the first of the keys holding a list, replying with the key
+// and the element. If none does the client is blocked, without holding up
+// the server, until a push lets it pop or the timeout (if any) elapses.
+pub async fn blocking(
+    server: &mut Server,
+    request: &Request,
+    keys: Vec<Bytes>,
+    timeout: Option<Duration>,
+) {
+    for key in &keys {
+        match pop(server.db(request), key.clone(), 1, End::Left) {
+            Ok(Some(mut popped)) => {
+                let value = popped.remove(0);
+                server.log_write(request, &cmd!("LPOP", key.clone()));
+                request
+                    .data(Frame::Array(vec![
+                        Frame::Bulk(key.clone()),
+                        Frame::Bulk(value),
+                    ]))
+                    .await;
+                return;
+            }
+            Ok(None) => {}
+            Err(error) => return request.error(error).await,
+        }
+    }
+
+    // Commands run by EXEC reply elsewhere than the client's connection, and
+    // can't block
+    let Some(client) = server
+        .clients
+        .get(&request.client_id)
+        .filter(|client| client.sender.same_channel(&request.connection))
+    else {
+        request.data(Frame::Null).await;
+        return;
+    };
+    let db = client.state.db;
+    let token = server
+        .blocked
+        .block(request.client_id, db, keys, request.connection.clone());
+
+    if let Some(timeout) = timeout {
+        blocking::time_out(server.sender.clone(), request.client_id, token, timeout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, time::Duration};
+
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{
+            connect_test_client, send_command, setup_command_test, with_frame, CommandError,
+        },
+        messages::{ConnectionMessage, ServerMessage},
+        resp::types::Frame,
+        server::ServerError,
+        store::Value,
+    };
+
+    fn bulks(items: &[&'static str]) -> Frame {
+        Frame::Array(
+            items
+                .iter()
+                .map(|item| Frame::Bulk(Bytes::from(*item)))
+                .collect(),
+        )
+    }
+
+    #[rstest]
+    #[case(cmd!("LPOP", "key"), Frame::Bulk(Bytes::from("a")), &["b", "c", "d"])]
+    #[case(cmd!("RPOP", "key"), Frame::Bulk(Bytes::from("d")), &["a", "b", "c"])]
+    #[case(cmd!("LPOP", "key", "2"), bulks(&["a", "b"]), &["c", "d"])]
+    #[case(cmd!("RPOP", "key", "3"), bulks(&["d", "c", "b"]), &["a"])]
+    #[case(cmd!("LPOP", "key", "0"), bulks(&[]), &["a", "b", "c", "d"])]
+    #[case(cmd!("LPOP", "key", "10"), bulks(&["a", "b", "c", "d"]), &[])]
+    #[case(cmd!("LPOP", "missing"), Frame::Null, &["a", "b", "c", "d"])]
+    #[case(cmd!("RPOP", "missing", "2"), Frame::Null, &["a", "b", "c", "d"])]
+    #[tokio::test]
+    async fn test_pop(
+        #[case] frame: Frame,
+        #[case] expected: Frame,
+        #[case] left: &[&'static str],
+    ) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("lpop"));
+        let list = ["a", "b", "c", "d"].map(Bytes::from);
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::List(list.into()));
+
+        assert_eq!(
+            ServerMessage::Data(expected),
+            send_command(&mut server, &mut receiver, &request, frame).await
+        );
+        // Emptied lists are deleted
+        let left: VecDeque<Bytes> = left.iter().map(|item| Bytes::from(*item)).collect();
+        let expected = (!left.is_empty()).then_some(Value::List(left));
+        assert_eq!(expected, server.db(&request).get(b"key"));
+    }
+
+    #[rstest]
+    #[case(cmd!("LPOP", "key"))]
+    #[case(cmd!("RPOP", "key", "2"))]
+    #[case(cmd!("BLPOP", "missing", "key", "0"))]
+    #[tokio::test]
+    async fn test_pop_wrong_type(#[case] frame: Frame) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("lpop"));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("value")));
+
+        assert_eq!(
+            ServerMessage::Error(ServerError::Command(CommandError::WrongType)),
+            send_command(&mut server, &mut receiver, &request, frame).await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_blpop_available() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("blpop"));
+        server.db(&request).set(
+            Bytes::from("second"),
+            Value::List([Bytes::from("a"), Bytes::from("b")].into()),
+        );
+
+        assert_eq!(
+            ServerMessage::Data(bulks(&["second", "a"])),
+            send_command(
+                &mut server,
+                &mut receiver,
+                &request,
+                cmd!("BLPOP", "first", "second", "1")
+            )
+            .await
+        );
+        assert!(server.blocked.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_blpop_served_by_push() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("blpop"));
+        let (mut other_receiver, other) = connect_test_client(&mut server, 1);
+
+        server
+            .run_requests(VecDeque::from([
+                with_frame(&request, cmd!("BLPOP", "a", "b", "0")),
+                // Waits for the client to be unblocked
+                with_frame(&request, cmd!("PING")),
+            ]))
+            .await;
+        assert!(receiver.try_recv().is_err());
+        assert!(server.blocked.is_blocked(0));
+
+        server
+            .run_requests(VecDeque::from([with_frame(
+                &other,
+                cmd!("RPUSH", "b", "x", "y"),
+            )]))
+            .await;
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Integer(2)),
+            other_receiver.try_recv().unwrap()
+        );
+        assert_eq!(
+            ServerMessage::Data(bulks(&["b", "x"])),
+            receiver.try_recv().unwrap()
+        );
+        assert_eq!(
+            ServerMessage::Data(Frame::simple("PONG")),
+            receiver.try_recv().unwrap()
+        );
+        assert_eq!(
+            Some(Value::List([Bytes::from("y")].into())),
+            server.db(&request).get(b"b")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_blpop_served_in_order() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("blpop"));
+        let (mut other_receiver, other) = connect_test_client(&mut server, 1);
+        let (mut pusher_receiver, pusher) = connect_test_client(&mut server, 2);
+
+        server
+            .run_requests(VecDeque::from([
+                with_frame(&request, cmd!("BLPOP", "key", "0")),
+                with_frame(&other, cmd!("BLPOP", "key", "0")),
+                with_frame(&pusher, cmd!("LPUSH", "key", "x")),
+            ]))
+            .await;
+
+        pusher_receiver.try_recv().unwrap();
+        assert_eq!(
+            ServerMessage::Data(bulks(&["key", "x"])),
+            receiver.try_recv().unwrap()
+        );
+        assert!(other_receiver.try_recv().is_err());
+        assert!(server.blocked.is_blocked(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_blpop_timeout() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("blpop"));
+
+        server
+            .run_requests(VecDeque::from([with_frame(
+                &request,
+                cmd!("BLPOP", "key", "1.5"),
+            )]))
+            .await;
+        assert!(receiver.try_recv().is_err());
+
+        let start = tokio::time::Instant::now();
+        let Some(ConnectionMessage::BlockTimeout { client_id, token }) =
+            server.receiver.recv().await
+        else {
+            panic!("expected the timeout to elapse");
+        };
+        assert_eq!(Duration::from_millis(1500), start.elapsed());
+        server.block_timeout(client_id, token).await;
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Null),
+            receiver.try_recv().unwrap()
+        );
+        assert!(server.blocked.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_blpop_in_transaction_does_not_block() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("multi"));
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        send(cmd!("MULTI")).await;
+        send(cmd!("BLPOP", "key", "0")).await;
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Array(vec![Frame::Null])),
+            send(cmd!("EXEC")).await
+        );
+        assert!(server.blocked.is_empty());
+    }
+}
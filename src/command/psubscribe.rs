@@ -0,0 +1,51 @@
+use bytes::Bytes;
+
+use crate::{messages::Request, resp::types::Frame, server::Server};
+
+// Subscribe to the glob patterns, confirming each one like SUBSCRIBE does
+pub async fn command(server: &mut Server, request: &Request, patterns: Vec<Bytes>) {
+    for pattern in patterns {
+        let count = server.pubsub.psubscribe(request.client_id, pattern.clone());
+        request
+            .data(Frame::Push(vec![
+                Frame::from("psubscribe"),
+                Frame::Bulk(pattern),
+                Frame::Integer(count as i64),
+            ]))
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{psubscribe::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+    };
+
+    #[tokio::test]
+    async fn test_psubscribe() {
+        let (mut server, mut connection_receiver, request) =
+            setup_command_test(cmd!("psubscribe", "news.*"));
+        server.pubsub.subscribe(0, Bytes::from("news"));
+
+        command(&mut server, &request, vec![Bytes::from("news.*")]).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Push(vec![
+                Frame::from("psubscribe"),
+                Frame::from("news.*"),
+                Frame::Integer(2),
+            ]))
+        );
+        assert_eq!(
+            vec![(Bytes::from("news.*"), 0)],
+            server.pubsub.pattern_subscribers(b"news.tech")
+        );
+    }
+}
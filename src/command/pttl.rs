@@ -0,0 +1,66 @@
+use bytes::Bytes;
+
+use crate::{messages::Request, resp::types::Frame, server::Server};
+
+// Milliseconds left before the key expires, -1 if it never does and -2 if it
+// doesn't exist
+pub async fn command(server: &Server, request: &Request, key: Bytes) {
+    let ttl = match server.db(request).ttl(&key) {
+        Some(Some(ttl)) => ttl.as_millis() as i64,
+        Some(None) => -1,
+        None => -2,
+    };
+    request.data(Frame::Integer(ttl)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{pttl::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn test_pttl() {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!("pttl", "key"));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("value")));
+        server.db(&request).expire(b"key", Duration::from_secs(2));
+
+        tokio::time::advance(Duration::from_millis(750)).await;
+        command(&server, &request, Bytes::from("key")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(1250))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pttl_without_expiration() {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!("pttl", "key"));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("value")));
+
+        command(&server, &request, Bytes::from("key")).await;
+        command(&server, &request, Bytes::from("missing")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(-1))
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(-2))
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use bytes::Bytes;
+
+use crate::{
+    messages::{Request, ServerMessage},
+    resp::types::Frame,
+    server::Server,
+};
+
+// Push the message to every subscriber of the channel and of the patterns
+// matching it, replying with how many pushes were delivered
+pub async fn command(server: &Server, request: &Request, channel: Bytes, message: Bytes) {
+    let push = Frame::Push(vec![
+        Frame::from("message"),
+        Frame::Bulk(channel.clone()),
+        Frame::Bulk(message.clone()),
+    ]);
+    let mut receivers = 0;
+    for id in server.pubsub.subscribers(&channel) {
+        receivers += deliver(server, id, push.clone());
+    }
+    for (pattern, id) in server.pubsub.pattern_subscribers(&channel) {
+        let push = Frame::Push(vec![
+            Frame::from("pmessage"),
+            Frame::Bulk(pattern),
+            Frame::Bulk(channel.clone()),
+            Frame::Bulk(message.clone()),
+        ]);
+        receivers += deliver(server, id, push);
+    }
+
+    request.data(Frame::Integer(receivers)).await;
+}
+
+// Send the push to the client, returning 1 if it was delivered
+fn deliver(server: &Server, id: u64, push: Frame) -> i64 {
+    let Some(client) = server.clients.get(&id) else {
+        return 0;
+    };
+    // Never wait on a subscriber: its connection may be waiting on the
+    // server itself. Messages to clients not keeping up are dropped.
+    match client.sender.try_send(ServerMessage::Data(push)) {
+        Ok(()) => 1,
+        Err(e) => {
+            eprintln!("Error publishing to client {}: {}", id, e);
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{publish::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+    };
+
+    #[tokio::test]
+    async fn test_publish() {
+        let (mut server, mut connection_receiver, request) =
+            setup_command_test(cmd!("publish", "news", "hello"));
+        server.pubsub.subscribe(0, Bytes::from("news"));
+
+        command(&server, &request, Bytes::from("news"), Bytes::from("hello")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Push(vec![
+                Frame::from("message"),
+                Frame::from("news"),
+                Frame::from("hello"),
+            ]))
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(1))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_publish_to_patterns() {
+        let (mut server, mut connection_receiver, request) =
+            setup_command_test(cmd!("publish", "news.tech", "hello"));
+        server.pubsub.psubscribe(0, Bytes::from("news.*"));
+        server.pubsub.psubscribe(0, Bytes::from("sports.*"));
+
+        command(
+            &server,
+            &request,
+            Bytes::from("news.tech"),
+            Bytes::from("hello"),
+        )
+        .await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Push(vec![
+                Frame::from("pmessage"),
+                Frame::from("news.*"),
+                Frame::from("news.tech"),
+                Frame::from("hello"),
+            ]))
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(1))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_publish_without_subscribers() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("publish", "news", "hello"));
+
+        command(&server, &request, Bytes::from("news"), Bytes::from("hello")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(0))
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::VecDeque;
+
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError, messages::Request, resp::types::Frame, server::Server, store::Value,
+};
+
+// Which end of a list to work on
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum End {
+    Left,
+    Right,
+}
+
+// Push the values one after the other at the given end of the list, creating
+// it if missing, and reply with its new length. LPUSH and RPUSH only differ
+// in the end.
+pub async fn command(server: &Server, request: &Request, key: Bytes, values: Vec<Bytes>, end: End) {
+    let result = server.db(request).update(key, |value| {
+        let list = match value.get_or_insert_with(|| Value::List(VecDeque::new())) {
+            Value::List(list) => list,
+            _ => return Err(CommandError::WrongType),
+        };
+        for item in values {
+            match end {
+                End::Left => list.push_front(item),
+                End::Right => list.push_back(item),
+            }
+        }
+        Ok((list.len(), true))
+    });
+
+    match result {
+        Ok(len) => request.data(Frame::Integer(len as i64)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{
+            push::{command, End},
+            send_command, setup_command_test, CommandError,
+        },
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::ServerError,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_push_both_ends() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("lpush", "key", "b", "a"));
+        let key = Bytes::from("key");
+
+        command(
+            &server,
+            &request,
+            key.clone(),
+            vec![Bytes::from("b"), Bytes::from("a")],
+            End::Left,
+        )
+        .await;
+        command(
+            &server,
+            &request,
+            key.clone(),
+            vec![Bytes::from("c")],
+            End::Right,
+        )
+        .await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(2))
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(3))
+        );
+        assert_eq!(
+            Some(Value::List(
+                ["a", "b", "c"].into_iter().map(Bytes::from).collect()
+            )),
+            server.db(&request).get(b"key")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_push_wrong_type() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("lpush", "key", "a"));
+        let value = Value::String(Bytes::from("text"));
+        server.db(&request).set(Bytes::from("key"), value.clone());
+
+        command(
+            &server,
+            &request,
+            Bytes::from("key"),
+            vec![Bytes::from("a")],
+            End::Left,
+        )
+        .await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Error(ServerError::Command(CommandError::WrongType))
+        );
+        assert_eq!(Some(value), server.db(&request).get(b"key"));
+    }
+
+    #[tokio::test]
+    async fn test_lpush_wrong_type_reply() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("lpush"));
+        send_command(
+            &mut server,
+            &mut receiver,
+            &request,
+            cmd!("SET", "key", "text"),
+        )
+        .await;
+
+        let reply = send_command(
+            &mut server,
+            &mut receiver,
+            &request,
+            cmd!("LPUSH", "key", "a"),
+        )
+        .await;
+
+        let ServerMessage::Error(error) = reply else {
+            panic!("unexpected reply {:?}", reply);
+        };
+        assert_eq!(
+            Frame::Error(
+                "WRONGTYPE Operation against a key holding the wrong kind of value".into()
+            ),
+            error.to_frame()
+        );
+    }
+}
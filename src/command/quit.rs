@@ -0,0 +1,33 @@
+use crate::{messages::Request, resp::types::Frame, server::Server};
+
+// Reply OK, then have the connection closed once the reply is written
+pub async fn command(_server: &Server, request: &Request) {
+    request.data(Frame::ok()).await;
+    request.close().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cmd,
+        command::{quit::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+    };
+
+    #[tokio::test]
+    async fn test_quit() {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!("quit"));
+
+        command(&server, &request).await;
+
+        assert_eq!(
+            ServerMessage::Data(Frame::ok()),
+            connection_receiver.try_recv().unwrap()
+        );
+        assert_eq!(
+            ServerMessage::Close,
+            connection_receiver.try_recv().unwrap()
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use bytes::Bytes;
+
+use crate::{command::CommandError, messages::Request, resp::types::Frame, server::Server};
+
+// RENAME: move the value and its expiration, overwriting the destination
+pub async fn command(server: &Server, request: &Request, from: Bytes, to: Bytes) {
+    match server.db(request).rename(&from, to, true) {
+        Some(_) => request.data(Frame::ok()).await,
+        None => request.error(no_such_key()).await,
+    }
+}
+
+// RENAMENX: like RENAME, but replies 0 and leaves both keys alone if the
+// destination exists
+pub async fn nx(server: &Server, request: &Request, from: Bytes, to: Bytes) {
+    match server.db(request).rename(&from, to, false) {
+        Some(renamed) => request.data(Frame::Integer(renamed as i64)).await,
+        None => request.error(no_such_key()).await,
+    }
+}
+
+fn no_such_key() -> CommandError {
+    CommandError::Invalid("no such key".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{
+            rename::{command, nx},
+            setup_command_test, CommandError,
+        },
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::ServerError,
+        store::Value,
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rename_keeps_value_and_ttl() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("rename", "a", "b"));
+        let value = Value::List([Bytes::from("x"), Bytes::from("y")].into());
+        server.db(&request).set(Bytes::from("a"), value.clone());
+        server.db(&request).expire(b"a", Duration::from_secs(10));
+        tokio::time::advance(Duration::from_secs(4)).await;
+
+        command(&server, &request, Bytes::from("a"), Bytes::from("b")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::ok())
+        );
+        assert_eq!(None, server.db(&request).get(b"a"));
+        assert_eq!(Some(value), server.db(&request).get(b"b"));
+        assert_eq!(
+            Some(Some(Duration::from_secs(6))),
+            server.db(&request).ttl(b"b")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rename_overwrites_destination() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("rename", "a", "b"));
+        server
+            .db(&request)
+            .set(Bytes::from("a"), Value::String(Bytes::from("new")));
+        server
+            .db(&request)
+            .set(Bytes::from("b"), Value::String(Bytes::from("old")));
+        server.db(&request).expire(b"b", Duration::from_secs(10));
+
+        command(&server, &request, Bytes::from("a"), Bytes::from("b")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::ok())
+        );
+        assert_eq!(
+            Some(Value::String(Bytes::from("new"))),
+            server.db(&request).get(b"b")
+        );
+        // The destination takes the source's expiration, here none
+        assert_eq!(Some(None), server.db(&request).ttl(b"b"));
+    }
+
+    #[tokio::test]
+    async fn test_rename_missing_source() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("rename", "a", "b"));
+        server
+            .db(&request)
+            .set(Bytes::from("b"), Value::String(Bytes::from("value")));
+
+        command(&server, &request, Bytes::from("a"), Bytes::from("b")).await;
+        nx(&server, &request, Bytes::from("a"), Bytes::from("c")).await;
+
+        for _ in 0..2 {
+            assert_eq!(
+                connection_receiver.try_recv().unwrap(),
+                ServerMessage::Error(ServerError::Command(CommandError::Invalid(
+                    "no such key".into()
+                )))
+            );
+        }
+        assert_eq!(
+            Some(Value::String(Bytes::from("value"))),
+            server.db(&request).get(b"b")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_renamenx() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("renamenx", "a", "b"));
+        server
+            .db(&request)
+            .set(Bytes::from("a"), Value::String(Bytes::from("a")));
+        server
+            .db(&request)
+            .set(Bytes::from("b"), Value::String(Bytes::from("b")));
+
+        nx(&server, &request, Bytes::from("a"), Bytes::from("b")).await;
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(0))
+        );
+        assert_eq!(
+            Some(Value::String(Bytes::from("a"))),
+            server.db(&request).get(b"a")
+        );
+        assert_eq!(
+            Some(Value::String(Bytes::from("b"))),
+            server.db(&request).get(b"b")
+        );
+
+        nx(&server, &request, Bytes::from("a"), Bytes::from("c")).await;
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(1))
+        );
+        assert_eq!(None, server.db(&request).get(b"a"));
+        assert_eq!(
+            Some(Value::String(Bytes::from("a"))),
+            server.db(&request).get(b"c")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rename_to_itself() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("rename", "a", "a"));
+        server
+            .db(&request)
+            .set(Bytes::from("a"), Value::String(Bytes::from("value")));
+
+        command(&server, &request, Bytes::from("a"), Bytes::from("a")).await;
+        nx(&server, &request, Bytes::from("a"), Bytes::from("a")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::ok())
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(0))
+        );
+        assert_eq!(
+            Some(Value::String(Bytes::from("value"))),
+            server.db(&request).get(b"a")
+        );
+    }
+}
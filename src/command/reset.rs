@@ -0,0 +1,90 @@
+use crate::{
+    messages::Request,
+    resp::types::Frame,
+    server::{ConnectionState, Server},
+};
+
+// RESET: bring the connection back to the state of a newly opened one,
+// dropping its transaction, watched keys and subscriptions, and going back
+// to database 0 and RESP2
+pub async fn command(server: &mut Server, request: &Request) {
+    let Some(client) = server.clients.get_mut(&request.client_id) else {
+        return;
+    };
+    client.state = ConnectionState {
+        authenticated: server.config.requirepass.is_empty(),
+        ..ConnectionState::default()
+    };
+    server.pubsub.remove_client(request.client_id);
+
+    request.protocol(client.state.protocol).await;
+    request.data(Frame::simple("RESET")).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{send_command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::{Frame, RespVersion},
+        server::ConnectionState,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_reset() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("reset"));
+        server.dbs[0].set(Bytes::from("key"), Value::String(Bytes::from("value")));
+        server.clients.get_mut(&0).unwrap().state.protocol = RespVersion::V3;
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        send(cmd!("SELECT", "1")).await;
+        send(cmd!("WATCH", "key")).await;
+        send(cmd!("SUBSCRIBE", "news")).await;
+        send(cmd!("MULTI")).await;
+        assert_eq!(
+            ServerMessage::Protocol(RespVersion::V2),
+            send(cmd!("RESET")).await
+        );
+        assert_eq!(
+            ServerMessage::Data(Frame::simple("RESET")),
+            receiver.try_recv().unwrap()
+        );
+
+        assert_eq!(
+            ConnectionState {
+                authenticated: true,
+                ..ConnectionState::default()
+            },
+            server.clients[&0].state
+        );
+        assert!(server.pubsub.subscribers(b"news").is_empty());
+        assert_eq!(0, server.pubsub.count(0));
+
+        // Commands run right away, on database 0
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+        assert_eq!(
+            ServerMessage::Data(Frame::Bulk(Bytes::from("value"))),
+            send(cmd!("GET", "key")).await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reset_requires_auth_again() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("reset"));
+        server.config.requirepass = "secret".into();
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        send(cmd!("AUTH", "secret")).await;
+        send(cmd!("RESET")).await;
+        receiver.try_recv().unwrap();
+
+        assert!(!server.clients[&0].state.authenticated);
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::HashSet;
+
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError, messages::Request, resp::types::Frame, server::Server, store::Value,
+};
+
+// Add the members to the set, creating it if missing, and reply with how many
+// weren't already in it
+pub async fn command(server: &Server, request: &Request, key: Bytes, members: Vec<Bytes>) {
+    let result = server.db(request).update(key, |value| {
+        let set = match value.get_or_insert_with(|| Value::Set(HashSet::new())) {
+            Value::Set(set) => set,
+            _ => return Err(CommandError::WrongType),
+        };
+        let added = members
+            .into_iter()
+            .filter(|member| set.insert(member.clone()))
+            .count();
+        Ok((added, added > 0))
+    });
+
+    match result {
+        Ok(added) => request.data(Frame::Integer(added as i64)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{sadd::command, setup_command_test, CommandError},
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::ServerError,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_sadd_dedup() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("sadd", "key", "a", "b", "a"));
+
+        let members = vec![Bytes::from("a"), Bytes::from("b"), Bytes::from("a")];
+        command(&server, &request, Bytes::from("key"), members).await;
+        let members = vec![Bytes::from("b"), Bytes::from("c")];
+        command(&server, &request, Bytes::from("key"), members).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(2))
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(1))
+        );
+        assert_eq!(
+            Some(Value::Set(
+                ["a", "b", "c"].into_iter().map(Bytes::from).collect()
+            )),
+            server.db(&request).get(b"key")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sadd_wrong_type() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("sadd", "key", "a"));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("text")));
+
+        command(
+            &server,
+            &request,
+            Bytes::from("key"),
+            vec![Bytes::from("a")],
+        )
+        .await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Error(ServerError::Command(CommandError::WrongType))
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{
+    messages::Request,
+    resp::types::Frame,
+    server::{Server, ServerError},
+    snapshot::Snapshot,
+};
+
+// SAVE: write the snapshot before replying, blocking every client meanwhile
+pub async fn command(server: &Server, request: &Request) {
+    let path = server.config.snapshot_path();
+    match Snapshot::take(&server.dbs).save(&path) {
+        Ok(()) => request.data(Frame::ok()).await,
+        Err(e) => {
+            eprintln!("Error saving snapshot to {}: {}", path.display(), e);
+            request.error(ServerError::ServerIoError).await;
+        }
+    }
+}
+
+// BGSAVE: write the snapshot from another task, replying right away
+pub async fn background(server: &mut Server, request: &Request) {
+    if server.spawn_save() {
+        request
+            .data(Frame::simple("Background saving started"))
+            .await;
+    } else {
+        request
+            .error(ServerError::InvalidValue(
+                "Background save already in progress".into(),
+            ))
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{save, setup_command_test, temporary_dir},
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::ServerError,
+        snapshot::Snapshot,
+        store::{Db, Value},
+    };
+
+    #[tokio::test]
+    async fn test_save() {
+        let (mut server, mut connection_receiver, request) = setup_command_test(cmd!("save"));
+        server.config.dir = temporary_dir("save").to_string_lossy().into_owned();
+        server.dbs[1].set(Bytes::from("key"), Value::String(Bytes::from("value")));
+
+        save::command(&server, &request).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::ok())
+        );
+        let dbs = vec![Db::new(), Db::new()];
+        Snapshot::load(&server.config.snapshot_path())
+            .unwrap()
+            .unwrap()
+            .restore(&dbs)
+            .unwrap();
+        assert_eq!(
+            Some(Value::String(Bytes::from("value"))),
+            dbs[1].get(b"key")
+        );
+        std::fs::remove_dir_all(&server.config.dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_save_error() {
+        let (mut server, mut connection_receiver, request) = setup_command_test(cmd!("save"));
+        server.config.dir = "/nonexistent/yarrs".into();
+
+        save::command(&server, &request).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Error(ServerError::ServerIoError)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bgsave() {
+        let (mut server, mut connection_receiver, request) = setup_command_test(cmd!("bgsave"));
+        server.config.dir = temporary_dir("bgsave").to_string_lossy().into_owned();
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("value")));
+
+        save::background(&mut server, &request).await;
+        // Written after the snapshot was taken, so not saved
+        server
+            .db(&request)
+            .set(Bytes::from("later"), Value::String(Bytes::from("value")));
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::simple("Background saving started"))
+        );
+        let path = server.config.snapshot_path();
+        while !path.exists() {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        let db = Db::new();
+        Snapshot::load(&path)
+            .unwrap()
+            .unwrap()
+            .restore(std::slice::from_ref(&db))
+            .unwrap();
+        assert_eq!(vec![Bytes::from("key")], db.keys(b"*"));
+        std::fs::remove_dir_all(&server.config.dir).unwrap();
+    }
+}
@@ -0,0 +1,68 @@
+use bytes::Bytes;
+
+use crate::{messages::Request, resp::types::Frame, server::Server};
+
+// Next batch of keys starting from the cursor, replying with the cursor to
+// continue from (0 once the scan is complete) and the keys found
+pub async fn command(
+    server: &Server,
+    request: &Request,
+    cursor: u64,
+    pattern: Option<Bytes>,
+    count: usize,
+) {
+    let (cursor, keys) = server.db(request).scan(cursor, pattern.as_deref(), count);
+    request
+        .data(Frame::Array(vec![
+            Frame::from(cursor.to_string()),
+            Frame::Array(keys.into_iter().map(Frame::Bulk).collect()),
+        ]))
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{scan::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_scan_to_completion() {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!("scan", "0"));
+        for i in 0..25 {
+            let key = Bytes::from(format!("key:{}", i));
+            server.db(&request).set(key.clone(), Value::String(key));
+        }
+
+        let mut cursor = 0;
+        let mut keys = HashSet::new();
+        let mut calls = 0;
+        loop {
+            command(&server, &request, cursor, None, 10).await;
+            calls += 1;
+            let Ok(ServerMessage::Data(Frame::Array(reply))) = connection_receiver.try_recv()
+            else {
+                panic!("expected an array reply");
+            };
+            let [next, Frame::Array(batch)] = reply.as_slice() else {
+                panic!("expected cursor and keys, got {:?}", reply);
+            };
+            keys.extend(batch.iter().cloned());
+            cursor = String::try_from(next.clone()).unwrap().parse().unwrap();
+            if cursor == 0 {
+                break;
+            }
+        }
+
+        assert!(calls >= 3);
+        assert_eq!(25, keys.len());
+    }
+}
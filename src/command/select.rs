@@ -0,0 +1,83 @@
+use crate::{command::CommandError, messages::Request, resp::types::Frame, server::Server};
+
+// Switch the connection to another logical database
+pub async fn command(server: &mut Server, request: &Request, index: i64) {
+    let Some(index) = usize::try_from(index)
+        .ok()
+        .filter(|index| *index < server.dbs.len())
+    else {
+        request
+            .error(CommandError::OutOfRange("DB index".into()))
+            .await;
+        return;
+    };
+    let Some(client) = server.clients.get_mut(&request.client_id) else {
+        return;
+    };
+    client.state.db = index;
+    request.data(Frame::ok()).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{get, select::command, setup_command_test, CommandError},
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::ServerError,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_select_switches_db() {
+        let (mut server, mut connection_receiver, request) =
+            setup_command_test(cmd!("select", "1"));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("value")));
+
+        command(&mut server, &request, 1).await;
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::ok())
+        );
+        assert_eq!(1, server.clients[&0].state.db);
+
+        get::command(&server, &request, Bytes::from("key")).await;
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Null)
+        );
+
+        command(&mut server, &request, 0).await;
+        connection_receiver.try_recv().unwrap();
+        get::command(&server, &request, Bytes::from("key")).await;
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Bulk("value".into()))
+        );
+    }
+
+    #[rstest]
+    #[case(-1)]
+    #[case(16)]
+    #[tokio::test]
+    async fn test_select_out_of_range(#[case] index: i64) {
+        let (mut server, mut connection_receiver, request) =
+            setup_command_test(cmd!("select", index.to_string()));
+
+        command(&mut server, &request, index).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Error(ServerError::Command(CommandError::OutOfRange(
+                "DB index".into()
+            )))
+        );
+        assert_eq!(0, server.clients[&0].state.db);
+    }
+}
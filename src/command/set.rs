@@ -0,0 +1,223 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError,
+    messages::Request,
+    resp::types::Frame,
+    server::{Server, ServerError},
+    store::{Db, Value},
+};
+
+// When SET may write the value
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Condition {
+    #[default]
+    Always,
+    // NX
+    IfMissing,
+    // XX
+    IfExists,
+}
+
+// Options of SET, also used by SETEX, PSETEX and SETNX
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SetOptions {
+    // Expire the key after this long, rather than clearing its expiration
+    pub expire: Option<Duration>,
+    pub condition: Condition,
+    // GET: reply with the value being replaced
+    pub get: bool,
+}
+
+pub async fn command(
+    server: &Server,
+    request: &Request,
+    key: Bytes,
+    value: Bytes,
+    options: SetOptions,
+) {
+    let get = options.get;
+    match apply(server.db(request), key, value, &options) {
+        Ok((_, previous)) if get => match previous {
+            Some(previous) => request.data(Frame::Bulk(previous)).await,
+            None => request.data(Frame::Null).await,
+        },
+        Ok((true, _)) => request.data(Frame::ok()).await,
+        Ok((false, _)) => request.data(Frame::Null).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+// Store the value if the condition holds, in a single step. Returns whether
+// it was written along with the string it replaced, when GET asked for it.
+pub(crate) fn apply(
+    db: &Db,
+    key: Bytes,
+    value: Bytes,
+    options: &SetOptions,
+) -> Result<(bool, Option<Bytes>), ServerError> {
+    let mut previous = None;
+    let written = db.set_if(key, Value::String(value), options.expire, |current| {
+        match current {
+            Some(Value::String(bytes)) if options.get => previous = Some(bytes.clone()),
+            // Only strings can be returned, so nothing gets written
+            Some(_) if options.get => return Err(CommandError::WrongType),
+            _ => {}
+        }
+        Ok(match options.condition {
+            Condition::Always => true,
+            Condition::IfMissing => current.is_none(),
+            Condition::IfExists => current.is_some(),
+        })
+    })?;
+    Ok((written, previous))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{
+            set::{command, Condition, SetOptions},
+            setup_command_test, CommandError,
+        },
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::ServerError,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_set() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("set", "key", "value"));
+
+        command(
+            &server,
+            &request,
+            Bytes::from("key"),
+            Bytes::from("value"),
+            SetOptions::default(),
+        )
+        .await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::ok())
+        );
+        assert_eq!(
+            Some(Value::String(Bytes::from("value"))),
+            server.db(&request).get(b"key")
+        );
+    }
+
+    #[rstest]
+    #[case(None, Condition::Always, false, Frame::ok(), "new")]
+    #[case(Some("old"), Condition::Always, false, Frame::ok(), "new")]
+    #[case(None, Condition::IfMissing, false, Frame::ok(), "new")]
+    #[case(Some("old"), Condition::IfMissing, false, Frame::Null, "old")]
+    #[case(None, Condition::IfExists, false, Frame::Null, "")]
+    #[case(Some("old"), Condition::IfExists, false, Frame::ok(), "new")]
+    #[case(Some("old"), Condition::Always, true, Frame::from("old"), "new")]
+    #[case(None, Condition::Always, true, Frame::Null, "new")]
+    #[case(Some("old"), Condition::IfMissing, true, Frame::from("old"), "old")]
+    #[tokio::test]
+    async fn test_set_options(
+        #[case] current: Option<&str>,
+        #[case] condition: Condition,
+        #[case] get: bool,
+        #[case] reply: Frame,
+        #[case] stored: &str,
+    ) {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("set", "key", "new"));
+        if let Some(current) = current {
+            server.db(&request).set(
+                Bytes::from("key"),
+                Value::String(Bytes::copy_from_slice(current.as_bytes())),
+            );
+        }
+        let options = SetOptions {
+            condition,
+            get,
+            ..SetOptions::default()
+        };
+
+        command(
+            &server,
+            &request,
+            Bytes::from("key"),
+            Bytes::from("new"),
+            options,
+        )
+        .await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(reply)
+        );
+        let expected =
+            (!stored.is_empty()).then(|| Value::String(Bytes::copy_from_slice(stored.as_bytes())));
+        assert_eq!(expected, server.db(&request).get(b"key"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_set_with_expiration() {
+        let (server, _connection_receiver, request) =
+            setup_command_test(cmd!("set", "key", "value", "px", "1500"));
+        let options = SetOptions {
+            expire: Some(Duration::from_millis(1500)),
+            ..SetOptions::default()
+        };
+
+        command(
+            &server,
+            &request,
+            Bytes::from("key"),
+            Bytes::from("value"),
+            options,
+        )
+        .await;
+
+        assert_eq!(
+            Some(Some(Duration::from_millis(1500))),
+            server.db(&request).ttl(b"key")
+        );
+        tokio::time::advance(Duration::from_millis(1500)).await;
+        assert_eq!(None, server.db(&request).get(b"key"));
+    }
+
+    #[tokio::test]
+    async fn test_set_get_wrong_type() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("set", "key", "value", "get"));
+        let list = Value::List([Bytes::from("a")].into());
+        server.db(&request).set(Bytes::from("key"), list.clone());
+        let options = SetOptions {
+            get: true,
+            ..SetOptions::default()
+        };
+
+        command(
+            &server,
+            &request,
+            Bytes::from("key"),
+            Bytes::from("value"),
+            options,
+        )
+        .await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Error(ServerError::Command(CommandError::WrongType))
+        );
+        assert_eq!(Some(list), server.db(&request).get(b"key"));
+    }
+}
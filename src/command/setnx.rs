@@ -0,0 +1,55 @@
+use bytes::Bytes;
+
+use crate::{
+    command::set::{apply, Condition, SetOptions},
+    messages::Request,
+    resp::types::Frame,
+    server::Server,
+};
+
+// SET NX, replying 1 if the key was set and 0 otherwise
+pub async fn command(server: &Server, request: &Request, key: Bytes, value: Bytes) {
+    let options = SetOptions {
+        condition: Condition::IfMissing,
+        ..SetOptions::default()
+    };
+    match apply(server.db(request), key, value, &options) {
+        Ok((written, _)) => request.data(Frame::Integer(written as i64)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{setnx::command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_setnx() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("setnx", "key", "first"));
+
+        command(&server, &request, Bytes::from("key"), Bytes::from("first")).await;
+        command(&server, &request, Bytes::from("key"), Bytes::from("second")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(1))
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(0))
+        );
+        assert_eq!(
+            Some(Value::String(Bytes::from("first"))),
+            server.db(&request).get(b"key")
+        );
+    }
+}
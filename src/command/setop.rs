@@ -0,0 +1,198 @@
+use std::collections::HashSet;
+
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError,
+    messages::Request,
+    resp::types::Frame,
+    server::Server,
+    store::{Db, Value},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SetOp {
+    Inter,
+    Union,
+    Diff,
+}
+
+// Combine the sets stored under the keys, in order: the difference keeps the
+// members of the first set missing from all the others. Missing keys count as
+// empty sets.
+fn combine(db: &Db, op: SetOp, keys: &[Bytes]) -> Result<HashSet<Bytes>, CommandError> {
+    let empty = HashSet::new();
+    db.view_many(keys, |values| {
+        let mut sets = Vec::with_capacity(values.len());
+        for value in values {
+            sets.push(match value {
+                Some(Value::Set(set)) => set,
+                Some(_) => return Err(CommandError::WrongType),
+                None => &empty,
+            });
+        }
+        let Some((first, rest)) = sets.split_first() else {
+            return Ok(HashSet::new());
+        };
+        let result = match op {
+            SetOp::Inter => first
+                .iter()
+                .filter(|member| rest.iter().all(|set| set.contains(*member)))
+                .cloned()
+                .collect(),
+            SetOp::Union => sets.iter().flat_map(|set| set.iter()).cloned().collect(),
+            SetOp::Diff => first
+                .iter()
+                .filter(|member| !rest.iter().any(|set| set.contains(*member)))
+                .cloned()
+                .collect(),
+        };
+        Ok(result)
+    })
+}
+
+// SINTER, SUNION and SDIFF: reply with the resulting set
+pub async fn command(server: &Server, request: &Request, op: SetOp, keys: Vec<Bytes>) {
+    match combine(server.db(request), op, &keys) {
+        Ok(set) => {
+            request
+                .data(Frame::Set(set.into_iter().map(Frame::Bulk).collect()))
+                .await
+        }
+        Err(error) => request.error(error).await,
+    }
+}
+
+// SINTERSTORE, SUNIONSTORE and SDIFFSTORE: overwrite the destination with
+// the resulting set, deleting it if the set is empty, and reply with its size
+pub async fn store(
+    server: &Server,
+    request: &Request,
+    op: SetOp,
+    destination: Bytes,
+    keys: Vec<Bytes>,
+) {
+    let db = server.db(request);
+    let set = match combine(db, op, &keys) {
+        Ok(set) => set,
+        Err(error) => return request.error(error).await,
+    };
+
+    let len = set.len();
+    if set.is_empty() {
+        db.remove(&destination);
+    } else {
+        db.set(destination, Value::Set(set));
+    }
+    request.data(Frame::Integer(len as i64)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{send_command, setup_command_test, CommandError},
+        messages::{Request, ServerMessage},
+        resp::types::Frame,
+        server::{Server, ServerError},
+        store::Value,
+    };
+
+    fn set(members: &[&'static str]) -> HashSet<Bytes> {
+        members.iter().map(|member| Bytes::from(*member)).collect()
+    }
+
+    fn fill(server: &Server, request: &Request) {
+        let db = server.db(request);
+        db.set(Bytes::from("a"), Value::Set(set(&["1", "2", "3", "4"])));
+        db.set(Bytes::from("b"), Value::Set(set(&["2", "3", "5"])));
+        db.set(Bytes::from("c"), Value::Set(set(&["3", "4", "6"])));
+    }
+
+    fn members(reply: ServerMessage) -> HashSet<Bytes> {
+        let ServerMessage::Data(Frame::Set(members)) = reply else {
+            panic!("expected a set, got {reply:?}");
+        };
+        members
+            .into_iter()
+            .map(|member| match member {
+                Frame::Bulk(member) => member,
+                other => panic!("expected a bulk string, got {other:?}"),
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case(cmd!("SINTER", "a", "b"), &["2", "3"])]
+    #[case(cmd!("SINTER", "a", "b", "c"), &["3"])]
+    #[case(cmd!("SINTER", "a", "missing"), &[])]
+    #[case(cmd!("SUNION", "a", "b"), &["1", "2", "3", "4", "5"])]
+    #[case(cmd!("SUNION", "b", "missing", "c"), &["2", "3", "4", "5", "6"])]
+    #[case(cmd!("SDIFF", "a", "b"), &["1", "4"])]
+    #[case(cmd!("SDIFF", "a", "b", "c"), &["1"])]
+    #[case(cmd!("SDIFF", "a", "missing"), &["1", "2", "3", "4"])]
+    #[case(cmd!("SDIFF", "missing", "a"), &[])]
+    #[tokio::test]
+    async fn test_setop(#[case] frame: Frame, #[case] expected: &[&'static str]) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("sinter"));
+        fill(&server, &request);
+
+        let reply = send_command(&mut server, &mut receiver, &request, frame).await;
+        assert_eq!(set(expected), members(reply));
+    }
+
+    #[rstest]
+    #[case(cmd!("SINTERSTORE", "dest", "a", "b"), "dest", &["2", "3"])]
+    #[case(cmd!("SUNIONSTORE", "dest", "b", "c"), "dest", &["2", "3", "4", "5", "6"])]
+    #[case(cmd!("SDIFFSTORE", "dest", "a", "b", "c"), "dest", &["1"])]
+    #[case(cmd!("SINTERSTORE", "dest", "b", "c", "missing"), "dest", &[])]
+    #[case(cmd!("SUNIONSTORE", "a", "a", "b"), "a", &["1", "2", "3", "4", "5"])]
+    #[tokio::test]
+    async fn test_setop_store(
+        #[case] frame: Frame,
+        #[case] destination: &str,
+        #[case] expected: &[&'static str],
+    ) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("sinterstore"));
+        fill(&server, &request);
+        if destination == "dest" {
+            // Overwritten whatever its type
+            server
+                .db(&request)
+                .set(Bytes::from("dest"), Value::String(Bytes::from("old")));
+        }
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Integer(expected.len() as i64)),
+            send_command(&mut server, &mut receiver, &request, frame).await
+        );
+        // An empty result deletes the destination
+        let stored = (!expected.is_empty()).then(|| Value::Set(set(expected)));
+        assert_eq!(stored, server.db(&request).get(destination.as_bytes()));
+    }
+
+    #[rstest]
+    #[case(cmd!("SINTER", "a", "string"))]
+    #[case(cmd!("SUNION", "string"))]
+    #[case(cmd!("SDIFF", "missing", "string"))]
+    #[case(cmd!("SDIFFSTORE", "dest", "a", "string"))]
+    #[tokio::test]
+    async fn test_setop_wrong_type(#[case] frame: Frame) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("sinter"));
+        fill(&server, &request);
+        server
+            .db(&request)
+            .set(Bytes::from("string"), Value::String(Bytes::from("text")));
+
+        assert_eq!(
+            ServerMessage::Error(ServerError::Command(CommandError::WrongType)),
+            send_command(&mut server, &mut receiver, &request, frame).await
+        );
+        assert_eq!(None, server.db(&request).get(b"dest"));
+    }
+}
@@ -0,0 +1,134 @@
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError, messages::Request, resp::types::Frame, server::Server, store::Value,
+};
+
+// Largest string SETRANGE may build, like the default proto-max-bulk-len
+const MAX_STRING_LEN: usize = 512 * 1024 * 1024;
+
+// Overwrite the string starting at `offset`, padding it with zero bytes if
+// it's shorter, and reply with its new length. A missing key counts as an
+// empty string, which isn't stored if nothing gets written.
+pub async fn command(server: &Server, request: &Request, key: Bytes, offset: i64, data: Bytes) {
+    let result = server.db(request).update(key, |value| {
+        let current = match value {
+            Some(Value::String(bytes)) => bytes.clone(),
+            Some(_) => return Err(CommandError::WrongType),
+            None => Bytes::new(),
+        };
+        let offset =
+            usize::try_from(offset).map_err(|_| CommandError::OutOfRange("offset".into()))?;
+        if data.is_empty() {
+            return Ok((current.len(), false));
+        }
+        let end = offset + data.len();
+        if end > MAX_STRING_LEN {
+            return Err(CommandError::Invalid(
+                "string exceeds maximum allowed size (proto-max-bulk-len)".into(),
+            ));
+        }
+
+        let mut updated = current.to_vec();
+        if updated.len() < end {
+            updated.resize(end, 0);
+        }
+        updated[offset..end].copy_from_slice(&data);
+        let len = updated.len();
+        *value = Some(Value::String(Bytes::from(updated)));
+        Ok((len, true))
+    });
+
+    match result {
+        Ok(len) => request.data(Frame::Integer(len as i64)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{setrange::command, setup_command_test, CommandError},
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::ServerError,
+        store::Value,
+    };
+
+    #[rstest]
+    #[case(Some("Hello World"), 6, "Redis", b"Hello Redis")]
+    #[case(Some("Hello"), 7, "!", b"Hello\x00\x00!")]
+    #[case(None, 2, "ab", b"\x00\x00ab")]
+    #[case(Some("Hello"), 3, "", b"Hello")]
+    #[tokio::test]
+    async fn test_setrange(
+        #[case] current: Option<&str>,
+        #[case] offset: i64,
+        #[case] data: &str,
+        #[case] expected: &[u8],
+    ) {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!(
+            "setrange",
+            "key",
+            offset.to_string(),
+            data.to_string()
+        ));
+        if let Some(current) = current {
+            server.db(&request).set(
+                Bytes::from("key"),
+                Value::String(Bytes::copy_from_slice(current.as_bytes())),
+            );
+        }
+
+        command(
+            &server,
+            &request,
+            Bytes::from("key"),
+            offset,
+            Bytes::copy_from_slice(data.as_bytes()),
+        )
+        .await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(expected.len() as i64))
+        );
+        assert_eq!(
+            Some(Value::String(Bytes::copy_from_slice(expected))),
+            server.db(&request).get(b"key")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_setrange_missing_key_with_empty_value() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("setrange", "key", "5", ""));
+
+        command(&server, &request, Bytes::from("key"), 5, Bytes::new()).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(0))
+        );
+        assert_eq!(None, server.db(&request).get(b"key"));
+    }
+
+    #[tokio::test]
+    async fn test_setrange_negative_offset() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("setrange", "key", "-1", "a"));
+
+        command(&server, &request, Bytes::from("key"), -1, Bytes::from("a")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Error(ServerError::Command(CommandError::OutOfRange(
+                "offset".into()
+            )))
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError, messages::Request, resp::types::Frame, server::Server, store::Value,
+};
+
+pub async fn command(server: &Server, request: &Request, key: Bytes, member: Bytes) {
+    let result = server.db(request).view(&key, |value| match value {
+        Some(Value::Set(set)) => Ok(set.contains(&member)),
+        Some(_) => Err(CommandError::WrongType),
+        None => Ok(false),
+    });
+
+    match result {
+        Ok(found) => request.data(Frame::Integer(found as i64)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{setup_command_test, sismember::command},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[rstest]
+    #[case("key", "a", 1)]
+    #[case("key", "b", 0)]
+    #[case("missing", "a", 0)]
+    #[tokio::test]
+    async fn test_sismember(#[case] key: &str, #[case] member: &str, #[case] expected: i64) {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("sismember", key.to_string(), member.to_string()));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::Set([Bytes::from("a")].into()));
+
+        command(
+            &server,
+            &request,
+            Bytes::from(key.to_string()),
+            Bytes::from(member.to_string()),
+        )
+        .await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(expected))
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{messages::Request, resp::types::Frame, server::Server};
+
+// SLOWLOG GET: the `count` newest entries, each with its id, unix time,
+// duration in microseconds and arguments
+pub async fn get(server: &Server, request: &Request, count: usize) {
+    let entries = server
+        .slowlog
+        .newest(count)
+        .map(|entry| {
+            Frame::Array(vec![
+                Frame::Integer(entry.id as i64),
+                Frame::Integer(entry.timestamp as i64),
+                Frame::Integer(entry.duration.as_micros() as i64),
+                Frame::Array(entry.args.iter().cloned().map(Frame::Bulk).collect()),
+            ])
+        })
+        .collect();
+    request.data(Frame::Array(entries)).await;
+}
+
+pub async fn len(server: &Server, request: &Request) {
+    request
+        .data(Frame::Integer(server.slowlog.len() as i64))
+        .await;
+}
+
+pub async fn reset(server: &mut Server, request: &Request) {
+    server.slowlog.reset();
+    request.data(Frame::ok()).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{send_command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn test_slowlog() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("slowlog"));
+        server.config.enable_debug_command = true;
+        server.config.slowlog_log_slower_than = 100_000;
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        // DEBUG SLEEP only takes its time in a transaction, otherwise the
+        // client is parked instead
+        send(cmd!("MULTI")).await;
+        send(cmd!("DEBUG", "SLEEP", "0.05")).await;
+        send(cmd!("DEBUG", "SLEEP", "0.25")).await;
+        send(cmd!("EXEC")).await;
+
+        let ServerMessage::Data(Frame::Array(entries)) = send(cmd!("SLOWLOG", "GET")).await else {
+            panic!("expected the slow log entries");
+        };
+        let [Frame::Array(entry)] = entries.as_slice() else {
+            panic!("expected one entry, got {:?}", entries);
+        };
+        let [Frame::Integer(0), Frame::Integer(timestamp), Frame::Integer(duration), args] =
+            entry.as_slice()
+        else {
+            panic!("unexpected entry {:?}", entry);
+        };
+        assert!(*timestamp > 0);
+        assert!((250_000..260_000).contains(duration), "took {duration}us");
+        assert_eq!(
+            &Frame::Array(vec![
+                Frame::Bulk(Bytes::from("DEBUG")),
+                Frame::Bulk(Bytes::from("SLEEP")),
+                Frame::Bulk(Bytes::from("0.25")),
+            ]),
+            args
+        );
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Integer(1)),
+            send(cmd!("SLOWLOG", "LEN")).await
+        );
+        assert_eq!(
+            ServerMessage::Data(Frame::ok()),
+            send(cmd!("SLOWLOG", "RESET")).await
+        );
+        assert_eq!(
+            ServerMessage::Data(Frame::Integer(0)),
+            send(cmd!("SLOWLOG", "LEN")).await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_slowlog_get_count() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("slowlog"));
+        server.config.slowlog_log_slower_than = 0;
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        send(cmd!("PING")).await;
+        send(cmd!("ECHO", "hi")).await;
+
+        // Every command is logged with a threshold of 0, SLOWLOG itself too
+        let ServerMessage::Data(Frame::Array(entries)) = send(cmd!("SLOWLOG", "GET", "1")).await
+        else {
+            panic!("expected the slow log entries");
+        };
+        assert_eq!(1, entries.len());
+        let ServerMessage::Data(Frame::Array(entries)) = send(cmd!("SLOWLOG", "GET", "-1")).await
+        else {
+            panic!("expected the slow log entries");
+        };
+        assert_eq!(3, entries.len());
+    }
+}
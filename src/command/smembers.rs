@@ -0,0 +1,54 @@
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError, messages::Request, resp::types::Frame, server::Server, store::Value,
+};
+
+// Members of the set, in no particular order. The set reply is sent as an
+// array under RESP2.
+pub async fn command(server: &Server, request: &Request, key: Bytes) {
+    let result = server.db(request).view(&key, |value| match value {
+        Some(Value::Set(set)) => Ok(set.iter().cloned().map(Frame::Bulk).collect()),
+        Some(_) => Err(CommandError::WrongType),
+        None => Ok(vec![]),
+    });
+
+    match result {
+        Ok(members) => request.data(Frame::Set(members)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{setup_command_test, smembers::command},
+        messages::ServerMessage,
+        resp::types::{Frame, RespVersion},
+        store::Value,
+    };
+
+    #[rstest]
+    #[case(RespVersion::V2, "*1\r\n$1\r\na\r\n")]
+    #[case(RespVersion::V3, "~1\r\n$1\r\na\r\n")]
+    #[tokio::test]
+    async fn test_smembers(#[case] version: RespVersion, #[case] encoded: &str) {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("smembers", "key"));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::Set([Bytes::from("a")].into()));
+
+        command(&server, &request, Bytes::from("key")).await;
+
+        let Ok(ServerMessage::Data(reply)) = connection_receiver.try_recv() else {
+            panic!("expected a reply");
+        };
+        assert_eq!(Frame::Set(vec![Frame::from("a")]), reply);
+        assert_eq!(encoded.as_bytes(), &reply.encode(version)[..]);
+    }
+}
@@ -0,0 +1,70 @@
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError, messages::Request, resp::types::Frame, server::Server, store::Value,
+};
+
+// Remove the members from the set, deleting the key once the set is empty,
+// and reply with how many were removed
+pub async fn command(server: &Server, request: &Request, key: Bytes, members: Vec<Bytes>) {
+    let result = server.db(request).update(key, |value| {
+        let set = match value {
+            Some(Value::Set(set)) => set,
+            Some(_) => return Err(CommandError::WrongType),
+            None => return Ok((0, false)),
+        };
+        let removed = members.iter().filter(|member| set.remove(*member)).count();
+        if set.is_empty() {
+            *value = None;
+        }
+        Ok((removed, removed > 0))
+    });
+
+    match result {
+        Ok(removed) => request.data(Frame::Integer(removed as i64)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{setup_command_test, srem::command},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_srem() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("srem", "key", "a", "missing"));
+        server.db(&request).set(
+            Bytes::from("key"),
+            Value::Set([Bytes::from("a"), Bytes::from("b")].into()),
+        );
+
+        let members = vec![Bytes::from("a"), Bytes::from("missing")];
+        command(&server, &request, Bytes::from("key"), members).await;
+        command(
+            &server,
+            &request,
+            Bytes::from("key"),
+            vec![Bytes::from("b")],
+        )
+        .await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(1))
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(1))
+        );
+        assert_eq!(None, server.db(&request).get(b"key"));
+    }
+}
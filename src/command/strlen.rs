@@ -0,0 +1,51 @@
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError, messages::Request, resp::types::Frame, server::Server, store::Value,
+};
+
+pub async fn command(server: &Server, request: &Request, key: Bytes) {
+    let result = server.db(request).view(&key, |value| match value {
+        Some(Value::String(bytes)) => Ok(bytes.len()),
+        Some(_) => Err(CommandError::WrongType),
+        None => Ok(0),
+    });
+
+    match result {
+        Ok(len) => request.data(Frame::Integer(len as i64)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{setup_command_test, strlen::command},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[rstest]
+    #[case("key", 5)]
+    #[case("missing", 0)]
+    #[tokio::test]
+    async fn test_strlen(#[case] key: &str, #[case] expected: i64) {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("strlen", key.to_string()));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("hello")));
+
+        command(&server, &request, Bytes::from(key.to_string())).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(expected))
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use bytes::Bytes;
+
+use crate::{messages::Request, resp::types::Frame, server::Server};
+
+// Subscribe to the channels, confirming each one with a push carrying the
+// number of channels the client is subscribed to
+pub async fn command(server: &mut Server, request: &Request, channels: Vec<Bytes>) {
+    for channel in channels {
+        let count = server.pubsub.subscribe(request.client_id, channel.clone());
+        request
+            .data(Frame::Push(vec![
+                Frame::from("subscribe"),
+                Frame::Bulk(channel),
+                Frame::Integer(count as i64),
+            ]))
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{setup_command_test, subscribe::command},
+        messages::ServerMessage,
+        resp::types::Frame,
+    };
+
+    #[tokio::test]
+    async fn test_subscribe() {
+        let (mut server, mut connection_receiver, request) =
+            setup_command_test(cmd!("subscribe", "a", "b"));
+
+        command(
+            &mut server,
+            &request,
+            vec![Bytes::from("a"), Bytes::from("b")],
+        )
+        .await;
+
+        for (channel, count) in [("a", 1), ("b", 2)] {
+            assert_eq!(
+                connection_receiver.try_recv().unwrap(),
+                ServerMessage::Data(Frame::Push(vec![
+                    Frame::from("subscribe"),
+                    Frame::from(channel),
+                    Frame::Integer(count),
+                ]))
+            );
+        }
+        assert_eq!(vec![0], server.pubsub.subscribers(b"b"));
+    }
+}
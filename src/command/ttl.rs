@@ -0,0 +1,79 @@
+use bytes::Bytes;
+
+use crate::{messages::Request, resp::types::Frame, server::Server};
+
+// Seconds left before the key expires, -1 if it never does and -2 if it
+// doesn't exist
+pub async fn command(server: &Server, request: &Request, key: Bytes) {
+    let ttl = match server.db(request).ttl(&key) {
+        Some(Some(ttl)) => ((ttl.as_millis() + 500) / 1000) as i64,
+        Some(None) => -1,
+        None => -2,
+    };
+    request.data(Frame::Integer(ttl)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{setup_command_test, ttl::command},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::Value,
+    };
+
+    #[rstest]
+    #[case(None, -2)]
+    #[case(Some(None), -1)]
+    #[case(Some(Some(Duration::from_secs(100))), 100)]
+    #[case(Some(Some(Duration::from_millis(1499))), 1)]
+    #[case(Some(Some(Duration::from_millis(1500))), 2)]
+    #[tokio::test(start_paused = true)]
+    async fn test_ttl(#[case] key: Option<Option<Duration>>, #[case] expected: i64) {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!("ttl", "key"));
+        if let Some(expiration) = key {
+            server
+                .db(&request)
+                .set(Bytes::from("key"), Value::String(Bytes::from("value")));
+            if let Some(after) = expiration {
+                server.db(&request).expire(b"key", after);
+            }
+        }
+
+        command(&server, &request, Bytes::from("key")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(expected))
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ttl_decreases() {
+        let (server, mut connection_receiver, request) = setup_command_test(cmd!("ttl", "key"));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("value")));
+        server.db(&request).expire(b"key", Duration::from_secs(10));
+
+        tokio::time::advance(Duration::from_secs(3)).await;
+        command(&server, &request, Bytes::from("key")).await;
+        tokio::time::advance(Duration::from_secs(7)).await;
+        command(&server, &request, Bytes::from("key")).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(7))
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(-2))
+        );
+    }
+}
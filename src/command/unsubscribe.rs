@@ -0,0 +1,88 @@
+use bytes::Bytes;
+
+use crate::{messages::Request, resp::types::Frame, server::Server};
+
+// Unsubscribe from the channels, or from all of them without arguments
+pub async fn command(server: &mut Server, request: &Request, channels: Vec<Bytes>) {
+    let channels = match channels.is_empty() {
+        true => server.pubsub.channels(request.client_id),
+        false => channels,
+    };
+    if channels.is_empty() {
+        let count = server.pubsub.count(request.client_id);
+        request.data(unsubscribed(Frame::Null, count)).await;
+    }
+    for channel in channels {
+        let count = server.pubsub.unsubscribe(request.client_id, &channel);
+        request
+            .data(unsubscribed(Frame::Bulk(channel), count))
+            .await;
+    }
+}
+
+fn unsubscribed(channel: Frame, count: usize) -> Frame {
+    Frame::Push(vec![
+        Frame::from("unsubscribe"),
+        channel,
+        Frame::Integer(count as i64),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{setup_command_test, unsubscribe::command},
+        messages::ServerMessage,
+        resp::types::Frame,
+    };
+
+    #[tokio::test]
+    async fn test_unsubscribe() {
+        let (mut server, mut connection_receiver, request) =
+            setup_command_test(cmd!("unsubscribe", "a"));
+        server.pubsub.subscribe(0, Bytes::from("a"));
+        server.pubsub.subscribe(0, Bytes::from("b"));
+
+        command(&mut server, &request, vec![Bytes::from("a")]).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Push(vec![
+                Frame::from("unsubscribe"),
+                Frame::from("a"),
+                Frame::Integer(1),
+            ]))
+        );
+        assert!(server.pubsub.subscribers(b"a").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unsubscribe_all() {
+        let (mut server, mut connection_receiver, request) =
+            setup_command_test(cmd!("unsubscribe"));
+        server.pubsub.subscribe(0, Bytes::from("a"));
+
+        command(&mut server, &request, vec![]).await;
+        command(&mut server, &request, vec![]).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Push(vec![
+                Frame::from("unsubscribe"),
+                Frame::from("a"),
+                Frame::Integer(0),
+            ]))
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Push(vec![
+                Frame::from("unsubscribe"),
+                Frame::Null,
+                Frame::Integer(0),
+            ]))
+        );
+    }
+}
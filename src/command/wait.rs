@@ -0,0 +1,26 @@
+use crate::{messages::Request, resp::types::Frame, server::Server};
+
+// WAIT: there are no replicas to wait for, so none acknowledged the writes
+pub async fn command(_server: &Server, request: &Request) {
+    request.data(Frame::Integer(0)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cmd,
+        command::{send_command, setup_command_test},
+        messages::ServerMessage,
+        resp::types::Frame,
+    };
+
+    #[tokio::test]
+    async fn test_wait() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("wait"));
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Integer(0)),
+            send_command(&mut server, &mut receiver, &request, cmd!("WAIT", "1", "0")).await
+        );
+    }
+}
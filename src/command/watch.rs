@@ -0,0 +1,158 @@
+use bytes::Bytes;
+
+use crate::{messages::Request, resp::types::Frame, server::Server};
+
+// Remember the current version of the keys, so that EXEC only runs the next
+// transaction if none of them changed in the meantime
+pub async fn command(server: &mut Server, request: &Request, keys: Vec<Bytes>) {
+    let index = server
+        .clients
+        .get(&request.client_id)
+        .map_or(0, |client| client.state.db);
+    let versions: Vec<_> = keys
+        .into_iter()
+        .map(|key| {
+            let version = server.dbs[index].version(&key);
+            (index, key, version)
+        })
+        .collect();
+    if let Some(client) = server.clients.get_mut(&request.client_id) {
+        client.state.watched.extend(versions);
+    }
+    request.data(Frame::ok()).await;
+}
+
+// Forget every watched key
+pub async fn unwatch(server: &mut Server, request: &Request) {
+    if let Some(client) = server.clients.get_mut(&request.client_id) {
+        client.state.watched.clear();
+    }
+    request.data(Frame::ok()).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{send_command, setup_command_test},
+        messages::{Request, ServerMessage},
+        resp::types::Frame,
+        server::{Client, ConnectionState, ServerError},
+    };
+
+    #[rstest]
+    #[case(cmd!("SET", "watched", "2"), false)]
+    #[case(cmd!("DEL", "watched"), false)]
+    #[case(cmd!("SET", "other", "2"), true)]
+    #[case(cmd!("GET", "watched"), true)]
+    #[tokio::test]
+    async fn test_exec_aborts_when_watched_key_changes(
+        #[case] other_command: Frame,
+        #[case] runs: bool,
+    ) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("watch"));
+        let (other_sender, mut other_receiver) = tokio::sync::mpsc::channel(32);
+        server.clients.insert(
+            1,
+            Client {
+                id: 1,
+                sender: other_sender.clone(),
+                state: ConnectionState::default(),
+            },
+        );
+        let other = Request {
+            client_id: 1,
+            frame: cmd!("set"),
+            connection: other_sender,
+        };
+
+        for frame in [
+            cmd!("SET", "watched", "1"),
+            cmd!("WATCH", "watched"),
+            cmd!("MULTI"),
+            cmd!("INCR", "watched"),
+        ] {
+            send_command(&mut server, &mut receiver, &request, frame).await;
+        }
+        send_command(&mut server, &mut other_receiver, &other, other_command).await;
+        let reply = send_command(&mut server, &mut receiver, &request, cmd!("EXEC")).await;
+
+        if runs {
+            assert_eq!(
+                ServerMessage::Data(Frame::Array(vec![Frame::Integer(2)])),
+                reply
+            );
+        } else {
+            assert_eq!(ServerMessage::Data(Frame::Null), reply);
+        }
+        assert!(server.clients[&0].state.watched.is_empty());
+    }
+
+    // Writes which leave the watched key as it was don't abort the transaction
+    #[rstest]
+    #[case(cmd!("HSET", "key", "field", "1"), cmd!("HDEL", "key", "missing"))]
+    #[case(cmd!("SET", "key", "1"), cmd!("LPUSH", "key", "x"))]
+    #[case(cmd!("SADD", "key", "a"), cmd!("SADD", "key", "a"))]
+    #[case(cmd!("SADD", "key", "a"), cmd!("SREM", "key", "b"))]
+    #[case(cmd!("ZADD", "key", "1", "a"), cmd!("ZADD", "key", "1", "a"))]
+    #[case(cmd!("RPUSH", "key", "a"), cmd!("LPOP", "key", "0"))]
+    #[case(cmd!("SET", "key", "1"), cmd!("SETRANGE", "key", "0", ""))]
+    #[tokio::test]
+    async fn test_exec_runs_after_noop_write(#[case] setup: Frame, #[case] noop: Frame) {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("watch"));
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        send(setup).await;
+        send(cmd!("WATCH", "key")).await;
+        send(noop).await;
+        send(cmd!("MULTI")).await;
+        send(cmd!("PING")).await;
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Array(vec![Frame::simple("PONG")])),
+            send(cmd!("EXEC")).await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unwatch() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("watch"));
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        assert_eq!(
+            ServerMessage::Data(Frame::ok()),
+            send(cmd!("WATCH", "key")).await
+        );
+        send(cmd!("SET", "key", "1")).await;
+        assert_eq!(
+            ServerMessage::Data(Frame::ok()),
+            send(cmd!("UNWATCH")).await
+        );
+        send(cmd!("MULTI")).await;
+        send(cmd!("GET", "key")).await;
+
+        assert_eq!(
+            ServerMessage::Data(Frame::Array(vec![Frame::Bulk(Bytes::from("1"))])),
+            send(cmd!("EXEC")).await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_watch_inside_multi() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("watch"));
+        let mut send =
+            async |frame| send_command(&mut server, &mut receiver, &request, frame).await;
+
+        send(cmd!("MULTI")).await;
+
+        assert_eq!(
+            ServerMessage::Error(ServerError::WatchInMulti),
+            send(cmd!("WATCH", "key")).await
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError,
+    messages::Request,
+    resp::types::Frame,
+    server::Server,
+    store::{sorted_set::SortedSet, Value},
+};
+
+// Add the members with their score, or update the score of the existing ones,
+// and reply with how many were added
+pub async fn command(server: &Server, request: &Request, key: Bytes, members: Vec<(f64, Bytes)>) {
+    let result = server.db(request).update(key, |value| {
+        let set = match value.get_or_insert_with(|| Value::SortedSet(SortedSet::new())) {
+            Value::SortedSet(set) => set,
+            _ => return Err(CommandError::WrongType),
+        };
+        // Updating a score to the one it already has changes nothing
+        let mut changed = false;
+        let added = members
+            .into_iter()
+            .filter(|(score, member)| {
+                changed |= set.score(member) != Some(*score);
+                set.insert(member.clone(), *score)
+            })
+            .count();
+        Ok((added, changed))
+    });
+
+    match result {
+        Ok(added) => request.data(Frame::Integer(added as i64)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{setup_command_test, zadd::command, CommandError},
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::ServerError,
+        store::Value,
+    };
+
+    #[tokio::test]
+    async fn test_zadd_updates_score() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("zadd", "key", "1", "a"));
+
+        let members = vec![(1.0, Bytes::from("a")), (2.0, Bytes::from("b"))];
+        command(&server, &request, Bytes::from("key"), members).await;
+        command(
+            &server,
+            &request,
+            Bytes::from("key"),
+            vec![(3.0, Bytes::from("a"))],
+        )
+        .await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(2))
+        );
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Integer(0))
+        );
+        let Some(Value::SortedSet(set)) = server.db(&request).get(b"key") else {
+            panic!("expected a sorted set");
+        };
+        assert_eq!(Some(3.0), set.score(b"a"));
+        assert_eq!(
+            vec![Bytes::from("b"), Bytes::from("a")],
+            set.iter()
+                .map(|(member, _)| member.clone())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_zadd_wrong_type() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("zadd", "key", "1", "a"));
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::String(Bytes::from("text")));
+
+        command(
+            &server,
+            &request,
+            Bytes::from("key"),
+            vec![(1.0, Bytes::from("a"))],
+        )
+        .await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Error(ServerError::Command(CommandError::WrongType))
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use bytes::Bytes;
+
+use crate::{
+    command::{index_range, CommandError},
+    messages::Request,
+    resp::types::Frame,
+    server::Server,
+    store::Value,
+};
+
+// Members ranked from `start` to `stop` included, by increasing score, with
+// the same index rules as LRANGE. WITHSCORES follows each member with its
+// score.
+pub async fn command(
+    server: &Server,
+    request: &Request,
+    key: Bytes,
+    start: i64,
+    stop: i64,
+    with_scores: bool,
+) {
+    let result = server.db(request).view(&key, |value| match value {
+        Some(Value::SortedSet(set)) => {
+            let Some(range) = index_range(start, stop, set.len()) else {
+                return Ok(vec![]);
+            };
+            let mut reply = Vec::new();
+            for (member, score) in set.iter().skip(*range.start()).take(range.count()) {
+                reply.push(Frame::Bulk(member.clone()));
+                if with_scores {
+                    reply.push(Frame::Double(score));
+                }
+            }
+            Ok(reply)
+        }
+        Some(_) => Err(CommandError::WrongType),
+        None => Ok(vec![]),
+    });
+
+    match result {
+        Ok(reply) => request.data(Frame::Array(reply)).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{setup_command_test, zrange::command},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::{sorted_set::SortedSet, Value},
+    };
+
+    fn seeded() -> Value {
+        let mut set = SortedSet::new();
+        set.insert(Bytes::from("c"), 3.0);
+        set.insert(Bytes::from("a"), 1.0);
+        set.insert(Bytes::from("b"), 2.0);
+        Value::SortedSet(set)
+    }
+
+    #[rstest]
+    #[case(0, -1, vec!["a", "b", "c"])]
+    #[case(1, 1, vec!["b"])]
+    #[case(-2, 10, vec!["b", "c"])]
+    #[case(2, 0, vec![])]
+    #[tokio::test]
+    async fn test_zrange(#[case] start: i64, #[case] stop: i64, #[case] expected: Vec<&str>) {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("zrange", "key", start.to_string(), stop.to_string()));
+        server.db(&request).set(Bytes::from("key"), seeded());
+
+        command(&server, &request, Bytes::from("key"), start, stop, false).await;
+
+        let expected = expected.into_iter().map(Frame::from).collect();
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Array(expected))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_zrange_with_scores() {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("zrange", "key", "0", "1", "withscores"));
+        server.db(&request).set(Bytes::from("key"), seeded());
+
+        command(&server, &request, Bytes::from("key"), 0, 1, true).await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(Frame::Array(vec![
+                Frame::from("a"),
+                Frame::Double(1.0),
+                Frame::from("b"),
+                Frame::Double(2.0),
+            ]))
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use bytes::Bytes;
+
+use crate::{
+    command::CommandError, messages::Request, resp::types::Frame, server::Server, store::Value,
+};
+
+pub async fn command(server: &Server, request: &Request, key: Bytes, member: Bytes) {
+    let result = server.db(request).view(&key, |value| match value {
+        Some(Value::SortedSet(set)) => Ok(set.score(&member)),
+        Some(_) => Err(CommandError::WrongType),
+        None => Ok(None),
+    });
+
+    match result {
+        Ok(Some(score)) => request.data(Frame::Double(score)).await,
+        Ok(None) => request.data(Frame::Null).await,
+        Err(error) => request.error(error).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use crate::{
+        cmd,
+        command::{setup_command_test, zscore::command},
+        messages::ServerMessage,
+        resp::types::Frame,
+        store::{sorted_set::SortedSet, Value},
+    };
+
+    #[rstest]
+    #[case("key", "a", Frame::Double(1.5))]
+    #[case("key", "missing", Frame::Null)]
+    #[case("missing", "a", Frame::Null)]
+    #[tokio::test]
+    async fn test_zscore(#[case] key: &str, #[case] member: &str, #[case] expected: Frame) {
+        let (server, mut connection_receiver, request) =
+            setup_command_test(cmd!("zscore", key.to_string(), member.to_string()));
+        let mut set = SortedSet::new();
+        set.insert(Bytes::from("a"), 1.5);
+        server
+            .db(&request)
+            .set(Bytes::from("key"), Value::SortedSet(set));
+
+        command(
+            &server,
+            &request,
+            Bytes::from(key.to_string()),
+            Bytes::from(member.to_string()),
+        )
+        .await;
+
+        assert_eq!(
+            connection_receiver.try_recv().unwrap(),
+            ServerMessage::Data(expected)
+        );
+    }
+}
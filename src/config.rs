@@ -0,0 +1,575 @@
+use std::{path::PathBuf, time::Duration};
+
+use thiserror::Error;
+
+use crate::{glob, resp::types::ParseLimits, store::ExpirationConfig};
+
+// What to do when a write would take memory beyond maxmemory
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MaxMemoryPolicy {
+    // Fail the write
+    #[default]
+    NoEviction,
+    // Evict the least recently used keys
+    AllKeysLru,
+    AllKeysRandom,
+    // Evict the keys closest to expiring
+    VolatileTtl,
+}
+
+impl MaxMemoryPolicy {
+    const ALL: [MaxMemoryPolicy; 4] = [
+        MaxMemoryPolicy::NoEviction,
+        MaxMemoryPolicy::AllKeysLru,
+        MaxMemoryPolicy::AllKeysRandom,
+        MaxMemoryPolicy::VolatileTtl,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MaxMemoryPolicy::NoEviction => "noeviction",
+            MaxMemoryPolicy::AllKeysLru => "allkeys-lru",
+            MaxMemoryPolicy::AllKeysRandom => "allkeys-random",
+            MaxMemoryPolicy::VolatileTtl => "volatile-ttl",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.name().eq_ignore_ascii_case(name))
+    }
+}
+
+// When the append only file is synced to disk
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AppendFsync {
+    // After every write
+    Always,
+    // At most once a second
+    #[default]
+    EverySec,
+    // Whenever the operating system decides to
+    No,
+}
+
+impl AppendFsync {
+    const ALL: [AppendFsync; 3] = [AppendFsync::Always, AppendFsync::EverySec, AppendFsync::No];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppendFsync::Always => "always",
+            AppendFsync::EverySec => "everysec",
+            AppendFsync::No => "no",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|fsync| fsync.name().eq_ignore_ascii_case(name))
+    }
+}
+
+// Tunables which can be read and changed at runtime with CONFIG
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServerConfig {
+    // Memory the keyspace may use, in bytes. 0 means no limit.
+    pub maxmemory: u64,
+    pub maxmemory_policy: MaxMemoryPolicy,
+    // Limits applied to the requests read from clients
+    pub limits: ParseLimits,
+    pub expiration: ExpirationConfig,
+    // Number of logical databases, fixed when the server starts
+    pub databases: usize,
+    // Password clients must AUTH with, empty when none is required
+    pub requirepass: String,
+    // Where snapshots are saved to and loaded from
+    pub dir: String,
+    pub dbfilename: String,
+    // Save a snapshot when the server shuts down
+    pub save_on_shutdown: bool,
+    // Log writes to the append only file, replaying it on startup instead of
+    // loading the snapshot. Fixed when the server starts.
+    pub appendonly: bool,
+    pub appendfilename: String,
+    pub appendfsync: AppendFsync,
+    // Allow the DEBUG command, fixed when the server starts
+    pub enable_debug_command: bool,
+    // Seconds a connection may go without sending anything before it's
+    // closed, 0 to never close it
+    pub timeout: u64,
+    // Where clients connect: the TCP address and port (0 to not listen on
+    // TCP) and the path of a unix socket (empty for none). Fixed when the
+    // server starts.
+    pub bind: String,
+    pub port: u16,
+    pub unixsocket: String,
+    // PEM files with the certificate chain and private key to terminate TLS
+    // with on TCP connections, when both are set. Fixed when the server starts.
+    pub tls_cert: String,
+    pub tls_key: String,
+    // Commands taking at least this many microseconds are logged to the
+    // slow log, none if negative
+    pub slowlog_log_slower_than: i64,
+    // Entries kept in the slow log, the oldest ones are dropped
+    pub slowlog_max_len: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            maxmemory: 0,
+            maxmemory_policy: MaxMemoryPolicy::default(),
+            limits: ParseLimits::default(),
+            expiration: ExpirationConfig::default(),
+            databases: 16,
+            requirepass: String::new(),
+            dir: ".".into(),
+            dbfilename: "dump.rdb".into(),
+            save_on_shutdown: false,
+            appendonly: false,
+            appendfilename: "appendonly.aof".into(),
+            appendfsync: AppendFsync::default(),
+            enable_debug_command: false,
+            timeout: 0,
+            bind: "127.0.0.1".into(),
+            port: 6379,
+            unixsocket: String::new(),
+            tls_cert: String::new(),
+            tls_key: String::new(),
+            slowlog_log_slower_than: 10_000,
+            slowlog_max_len: 128,
+        }
+    }
+}
+
+// Names of the parameters, as CONFIG knows them
+pub const PARAMETERS: &[&str] = &[
+    "maxmemory",
+    "maxmemory-policy",
+    "proto-max-bulk-len",
+    "proto-max-depth",
+    "active-expire-interval",
+    "databases",
+    "requirepass",
+    "dir",
+    "dbfilename",
+    "save-on-shutdown",
+    "appendonly",
+    "appendfilename",
+    "appendfsync",
+    "enable-debug-command",
+    "timeout",
+    "bind",
+    "port",
+    "unixsocket",
+    "tls-cert",
+    "tls-key",
+    "slowlog-log-slower-than",
+    "slowlog-max-len",
+];
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ConfigError {
+    #[error("Unknown option or number of arguments for CONFIG SET - '{0}'")]
+    Unknown(String),
+    #[error("Invalid argument '{value}' for CONFIG SET '{name}'")]
+    InvalidValue { name: String, value: String },
+    #[error("CONFIG SET failed (possibly related to argument '{0}') - can't set immutable config")]
+    Immutable(String),
+}
+
+impl ServerConfig {
+    // How long connections may stay idle, if they may not for ever
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        (self.timeout > 0).then(|| Duration::from_secs(self.timeout))
+    }
+
+    // File snapshots are saved to
+    pub fn snapshot_path(&self) -> PathBuf {
+        PathBuf::from(&self.dir).join(&self.dbfilename)
+    }
+
+    pub fn aof_path(&self) -> PathBuf {
+        PathBuf::from(&self.dir).join(&self.appendfilename)
+    }
+
+    pub fn unix_socket_path(&self) -> Option<PathBuf> {
+        (!self.unixsocket.is_empty()).then(|| PathBuf::from(&self.unixsocket))
+    }
+
+    // Certificate and key files, if TLS is enabled
+    pub fn tls_files(&self) -> Option<(PathBuf, PathBuf)> {
+        (!self.tls_cert.is_empty() && !self.tls_key.is_empty())
+            .then(|| (PathBuf::from(&self.tls_cert), PathBuf::from(&self.tls_key)))
+    }
+
+    // Configuration given when starting the server, as `--name value` pairs
+    // like redis-server takes them. Parameters fixed while the server runs
+    // can be set here.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, ConfigError> {
+        let mut config = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                return Err(ConfigError::Unknown(arg));
+            };
+            let value = args.next().ok_or_else(|| ConfigError::InvalidValue {
+                name: name.to_string(),
+                value: String::new(),
+            })?;
+            config.apply(name, &value, true)?;
+        }
+        Ok(config)
+    }
+
+    // Current value of the parameter, None if there's no such parameter
+    pub fn get(&self, name: &str) -> Option<String> {
+        let value = match name.to_ascii_lowercase().as_str() {
+            "maxmemory" => self.maxmemory.to_string(),
+            "maxmemory-policy" => self.maxmemory_policy.name().to_string(),
+            "proto-max-bulk-len" => self.limits.max_bulk_len.to_string(),
+            "proto-max-depth" => self.limits.max_depth.to_string(),
+            "active-expire-interval" => self.expiration.interval.as_millis().to_string(),
+            "databases" => self.databases.to_string(),
+            "requirepass" => self.requirepass.clone(),
+            "dir" => self.dir.clone(),
+            "dbfilename" => self.dbfilename.clone(),
+            "save-on-shutdown" => if self.save_on_shutdown { "yes" } else { "no" }.to_string(),
+            "appendonly" => if self.appendonly { "yes" } else { "no" }.to_string(),
+            "appendfilename" => self.appendfilename.clone(),
+            "appendfsync" => self.appendfsync.name().to_string(),
+            "enable-debug-command" => if self.enable_debug_command {
+                "yes"
+            } else {
+                "no"
+            }
+            .to_string(),
+            "timeout" => self.timeout.to_string(),
+            "bind" => self.bind.clone(),
+            "port" => self.port.to_string(),
+            "unixsocket" => self.unixsocket.clone(),
+            "tls-cert" => self.tls_cert.clone(),
+            "tls-key" => self.tls_key.clone(),
+            "slowlog-log-slower-than" => self.slowlog_log_slower_than.to_string(),
+            "slowlog-max-len" => self.slowlog_max_len.to_string(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    // Parameters and their values, for the parameters whose name matches the
+    // glob pattern
+    pub fn matching(&self, pattern: &[u8]) -> Vec<(&'static str, String)> {
+        let pattern = pattern.to_ascii_lowercase();
+        PARAMETERS
+            .iter()
+            .filter(|name| glob::matches(&pattern, name.as_bytes()))
+            .map(|name| (*name, self.get(name).unwrap()))
+            .collect()
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), ConfigError> {
+        self.apply(name, value, false)
+    }
+
+    // Parameters fixed while the server runs can only be set at `startup`
+    fn apply(&mut self, name: &str, value: &str, startup: bool) -> Result<(), ConfigError> {
+        let invalid = || ConfigError::InvalidValue {
+            name: name.to_string(),
+            value: value.to_string(),
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "maxmemory" => self.maxmemory = parse_memory(value).ok_or_else(invalid)?,
+            "maxmemory-policy" => {
+                self.maxmemory_policy = MaxMemoryPolicy::from_name(value).ok_or_else(invalid)?
+            }
+            "proto-max-bulk-len" => {
+                self.limits.max_bulk_len = parse_memory(value)
+                    .and_then(|len| usize::try_from(len).ok())
+                    .filter(|len| *len > 0)
+                    .ok_or_else(invalid)?
+            }
+            "proto-max-depth" => {
+                self.limits.max_depth = value
+                    .parse()
+                    .ok()
+                    .filter(|depth| *depth > 0)
+                    .ok_or_else(invalid)?
+            }
+            "active-expire-interval" => {
+                self.expiration.interval = value
+                    .parse()
+                    .ok()
+                    .filter(|millis| *millis > 0)
+                    .map(Duration::from_millis)
+                    .ok_or_else(invalid)?
+            }
+            "databases"
+            | "appendonly"
+            | "appendfilename"
+            | "enable-debug-command"
+            | "bind"
+            | "port"
+            | "unixsocket"
+            | "tls-cert"
+            | "tls-key"
+                if !startup =>
+            {
+                return Err(ConfigError::Immutable(name.to_string()))
+            }
+            "databases" => {
+                self.databases = value
+                    .parse()
+                    .ok()
+                    .filter(|databases| *databases > 0)
+                    .ok_or_else(invalid)?
+            }
+            "appendonly" => self.appendonly = parse_bool(value).ok_or_else(invalid)?,
+            "appendfilename" if !value.is_empty() && !value.contains('/') => {
+                self.appendfilename = value.to_string()
+            }
+            "enable-debug-command" => {
+                self.enable_debug_command = parse_bool(value).ok_or_else(invalid)?
+            }
+            "bind" if !value.is_empty() => self.bind = value.to_string(),
+            "port" => self.port = value.parse().ok().ok_or_else(invalid)?,
+            "unixsocket" => self.unixsocket = value.to_string(),
+            "tls-cert" => self.tls_cert = value.to_string(),
+            "tls-key" => self.tls_key = value.to_string(),
+            "appendfsync" => {
+                self.appendfsync = AppendFsync::from_name(value).ok_or_else(invalid)?
+            }
+            "requirepass" => self.requirepass = value.to_string(),
+            "save-on-shutdown" => self.save_on_shutdown = parse_bool(value).ok_or_else(invalid)?,
+            // Capped like Redis does, at INT_MAX seconds
+            "timeout" => {
+                self.timeout = value
+                    .parse()
+                    .ok()
+                    .filter(|seconds| *seconds <= i32::MAX as u64)
+                    .ok_or_else(invalid)?
+            }
+            "slowlog-log-slower-than" => {
+                self.slowlog_log_slower_than = value.parse().ok().ok_or_else(invalid)?
+            }
+            "slowlog-max-len" => self.slowlog_max_len = value.parse().ok().ok_or_else(invalid)?,
+            "dir" if !value.is_empty() => self.dir = value.to_string(),
+            // Only a file name, the directory is set apart
+            "dbfilename" if !value.is_empty() && !value.contains('/') => {
+                self.dbfilename = value.to_string()
+            }
+            "dir" | "dbfilename" | "appendfilename" | "bind" => return Err(invalid()),
+            _ => return Err(ConfigError::Unknown(name.to_string())),
+        }
+        Ok(())
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
+    }
+}
+
+// A number of bytes, optionally followed by a unit like in the Redis config:
+// k, m and g for powers of 1000, kb, mb and gb for powers of 1024
+fn parse_memory(value: &str) -> Option<u64> {
+    let value = value.to_ascii_lowercase();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let multiplier = match unit {
+        "" | "b" => 1,
+        "k" => 1000,
+        "kb" => 1024,
+        "m" => 1000 * 1000,
+        "mb" => 1024 * 1024,
+        "g" => 1000 * 1000 * 1000,
+        "gb" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::{parse_memory, AppendFsync, ConfigError, MaxMemoryPolicy, ServerConfig};
+
+    #[rstest]
+    #[case("maxmemory", "100mb", "104857600")]
+    #[case("maxmemory", "1000", "1000")]
+    #[case("MAXMEMORY", "2k", "2000")]
+    #[case("maxmemory-policy", "allkeys-lru", "allkeys-lru")]
+    #[case("proto-max-bulk-len", "1kb", "1024")]
+    #[case("proto-max-depth", "8", "8")]
+    #[case("active-expire-interval", "250", "250")]
+    #[case("requirepass", "secret", "secret")]
+    #[case("dbfilename", "backup.rdb", "backup.rdb")]
+    #[case("appendfsync", "ALWAYS", "always")]
+    #[case("timeout", "300", "300")]
+    #[case("timeout", "2147483647", "2147483647")]
+    #[case("save-on-shutdown", "YES", "yes")]
+    #[case("slowlog-log-slower-than", "-1", "-1")]
+    #[case("slowlog-max-len", "16", "16")]
+    fn test_set_get(#[case] name: &str, #[case] value: &str, #[case] expected: &str) {
+        let mut config = ServerConfig::default();
+
+        config.set(name, value).unwrap();
+
+        assert_eq!(Some(expected.to_string()), config.get(name));
+    }
+
+    #[rstest]
+    #[case("maxmemory", "lots")]
+    #[case("maxmemory-policy", "everything")]
+    #[case("proto-max-bulk-len", "0")]
+    #[case("proto-max-depth", "-1")]
+    #[case("active-expire-interval", "0")]
+    #[case("dir", "")]
+    #[case("dbfilename", "../dump.rdb")]
+    #[case("appendfsync", "sometimes")]
+    #[case("timeout", "-1")]
+    #[case("timeout", "18446744073709551615")]
+    #[case("save-on-shutdown", "maybe")]
+    #[case("slowlog-max-len", "-1")]
+    fn test_set_invalid(#[case] name: &str, #[case] value: &str) {
+        let mut config = ServerConfig::default();
+
+        assert_eq!(
+            Err(ConfigError::InvalidValue {
+                name: name.into(),
+                value: value.into()
+            }),
+            config.set(name, value)
+        );
+        assert_eq!(ServerConfig::default(), config);
+    }
+
+    #[test]
+    fn test_set_unknown() {
+        let mut config = ServerConfig::default();
+        assert_eq!(
+            Err(ConfigError::Unknown("nope".into())),
+            config.set("nope", "1")
+        );
+        assert_eq!(None, config.get("nope"));
+    }
+
+    #[test]
+    fn test_set_immutable() {
+        let mut config = ServerConfig::default();
+        assert_eq!(
+            Err(ConfigError::Immutable("databases".into())),
+            config.set("databases", "4")
+        );
+        assert_eq!(
+            Err(ConfigError::Immutable("appendonly".into())),
+            config.set("appendonly", "yes")
+        );
+        assert_eq!(
+            Err(ConfigError::Immutable("enable-debug-command".into())),
+            config.set("enable-debug-command", "yes")
+        );
+        assert_eq!(
+            Err(ConfigError::Immutable("port".into())),
+            config.set("port", "6380")
+        );
+        assert_eq!(
+            Err(ConfigError::Immutable("tls-cert".into())),
+            config.set("tls-cert", "cert.pem")
+        );
+        assert_eq!(
+            Err(ConfigError::Immutable("tls-key".into())),
+            config.set("tls-key", "key.pem")
+        );
+        assert_eq!(Some("16".to_string()), config.get("databases"));
+        assert_eq!(Some("6379".to_string()), config.get("port"));
+        assert_eq!(Some(String::new()), config.get("tls-cert"));
+    }
+
+    #[test]
+    fn test_from_args() {
+        let args = [
+            "--port",
+            "0",
+            "--unixsocket",
+            "/tmp/yarrs.sock",
+            "--databases",
+            "4",
+            "--appendonly",
+            "yes",
+            "--maxmemory",
+            "1mb",
+        ];
+        let config = ServerConfig::from_args(args.map(String::from)).unwrap();
+
+        assert_eq!(0, config.port);
+        assert_eq!(Some("/tmp/yarrs.sock".into()), config.unix_socket_path());
+        assert_eq!(4, config.databases);
+        assert!(config.appendonly);
+        assert_eq!(1024 * 1024, config.maxmemory);
+        assert_eq!("127.0.0.1", config.bind);
+    }
+
+    #[rstest]
+    #[case(&["port", "1"], ConfigError::Unknown("port".into()))]
+    #[case(&["--port"], ConfigError::InvalidValue { name: "port".into(), value: "".into() })]
+    #[case(&["--port", "65536"], ConfigError::InvalidValue { name: "port".into(), value: "65536".into() })]
+    #[case(&["--databases", "0"], ConfigError::InvalidValue { name: "databases".into(), value: "0".into() })]
+    #[case(&["--nope", "1"], ConfigError::Unknown("nope".into()))]
+    fn test_from_args_invalid(#[case] args: &[&str], #[case] error: ConfigError) {
+        let args = args.iter().map(|arg| arg.to_string());
+        assert_eq!(Err(error), ServerConfig::from_args(args));
+    }
+
+    #[test]
+    fn test_matching() {
+        let mut config = ServerConfig::default();
+        config.set("maxmemory", "10").unwrap();
+
+        assert_eq!(
+            vec![
+                ("maxmemory", "10".to_string()),
+                ("maxmemory-policy", "noeviction".to_string())
+            ],
+            config.matching(b"maxmemory*")
+        );
+        assert_eq!(
+            vec![("proto-max-depth", "128".to_string())],
+            config.matching(b"PROTO-*-DEPTH")
+        );
+        assert!(config.matching(b"missing").is_empty());
+    }
+
+    #[rstest]
+    #[case("1", Some(1))]
+    #[case("1gb", Some(1024 * 1024 * 1024))]
+    #[case("3G", Some(3_000_000_000))]
+    #[case("1tb", None)]
+    #[case("mb", None)]
+    #[case("-1", None)]
+    fn test_parse_memory(#[case] value: &str, #[case] expected: Option<u64>) {
+        assert_eq!(expected, parse_memory(value));
+    }
+
+    #[test]
+    fn test_policy_names() {
+        for policy in MaxMemoryPolicy::ALL {
+            assert_eq!(Some(policy), MaxMemoryPolicy::from_name(policy.name()));
+        }
+    }
+
+    #[test]
+    fn test_fsync_names() {
+        for fsync in AppendFsync::ALL {
+            assert_eq!(Some(fsync), AppendFsync::from_name(fsync.name()));
+        }
+    }
+}
@@ -0,0 +1,114 @@
+// Redis style glob matching, as used by KEYS and PSUBSCRIBE:
+// `*` matches any sequence, `?` any single byte, `[...]` one byte out of a
+// set of bytes and ranges (negated by a leading `^`), while `\` escapes the
+// next byte
+pub fn matches(pattern: &[u8], string: &[u8]) -> bool {
+    let (mut p, mut s) = (0, 0);
+    // Where to resume from after the last `*` when a match fails
+    let mut backtrack = None;
+
+    while s < string.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                p += 1;
+                backtrack = Some((p, s));
+                continue;
+            }
+            Some(_) => {
+                let (matched, next) = match_one(pattern, p, string[s]);
+                if matched {
+                    p = next;
+                    s += 1;
+                    continue;
+                }
+            }
+            None => {}
+        }
+        match backtrack {
+            // Let the last star swallow one more byte
+            Some((star_p, star_s)) => {
+                backtrack = Some((star_p, star_s + 1));
+                p = star_p;
+                s = star_s + 1;
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+// Whether the byte matches the pattern token starting at `p`, along with
+// where the next token starts
+fn match_one(pattern: &[u8], p: usize, byte: u8) -> (bool, usize) {
+    match pattern[p] {
+        b'?' => (true, p + 1),
+        b'[' => match_class(pattern, p + 1, byte),
+        b'\\' if p + 1 < pattern.len() => (pattern[p + 1] == byte, p + 2),
+        c => (c == byte, p + 1),
+    }
+}
+
+// Match a `[...]` class, `p` pointing right after the opening bracket. An
+// unterminated class extends up to the end of the pattern.
+fn match_class(pattern: &[u8], mut p: usize, byte: u8) -> (bool, usize) {
+    let negated = pattern.get(p) == Some(&b'^');
+    if negated {
+        p += 1;
+    }
+
+    let mut matched = false;
+    while p < pattern.len() && pattern[p] != b']' {
+        if pattern[p] == b'\\' && p + 1 < pattern.len() {
+            matched |= pattern[p + 1] == byte;
+            p += 2;
+        } else if pattern.get(p + 1) == Some(&b'-') && p + 2 < pattern.len() {
+            let (start, end) = (pattern[p], pattern[p + 2]);
+            let (start, end) = (start.min(end), start.max(end));
+            matched |= (start..=end).contains(&byte);
+            p += 3;
+        } else {
+            matched |= pattern[p] == byte;
+            p += 1;
+        }
+    }
+
+    (matched != negated, (p + 1).min(pattern.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::matches;
+
+    #[rstest]
+    #[case("*", "", true)]
+    #[case("*", "anything", true)]
+    #[case("news.*", "news.tech", true)]
+    #[case("news.*", "news.", true)]
+    #[case("news.*", "sports.news", false)]
+    #[case("*.news", "sports.news", true)]
+    #[case("h?llo", "hello", true)]
+    #[case("h?llo", "hllo", false)]
+    #[case("h*llo", "heeeello", true)]
+    #[case("h*llo", "hello world", false)]
+    #[case("h[ae]llo", "hallo", true)]
+    #[case("h[ae]llo", "hillo", false)]
+    #[case("h[^e]llo", "hallo", true)]
+    #[case("h[^e]llo", "hello", false)]
+    #[case("h[a-c]llo", "hbllo", true)]
+    #[case("h[c-a]llo", "hbllo", true)]
+    #[case("h[a-c]llo", "hdllo", false)]
+    #[case("h\\*llo", "h*llo", true)]
+    #[case("h\\*llo", "hello", false)]
+    #[case("[\\]]", "]", true)]
+    #[case("a*b*c", "axxbxxc", true)]
+    #[case("a*b*c", "axxcxxb", false)]
+    #[case("**", "abc", true)]
+    #[case("abc", "abcd", false)]
+    #[case("abcd", "abc", false)]
+    fn test_matches(#[case] pattern: &str, #[case] string: &str, #[case] expected: bool) {
+        assert_eq!(expected, matches(pattern.as_bytes(), string.as_bytes()));
+    }
+}
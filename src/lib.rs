@@ -1,5 +1,16 @@
-mod command;
+mod macros;
+
+pub mod aof;
+pub mod blocking;
+pub mod client;
+pub mod command;
+pub mod config;
+pub mod glob;
 pub mod listener;
 pub mod messages;
+pub mod pubsub;
 pub mod resp;
 pub mod server;
+pub mod slowlog;
+pub mod snapshot;
+pub mod store;
@@ -1,31 +1,243 @@
+use std::{path::Path, sync::Arc, time::Duration};
+
 use tokio::{
-    net::{TcpListener, TcpStream},
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufWriter, ReadHalf, WriteHalf},
+    net::{TcpListener, UnixListener},
     select,
     sync::mpsc,
 };
+use tokio_rustls::{
+    rustls::{
+        self,
+        pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    },
+    TlsAcceptor,
+};
+use tokio_util::sync::CancellationToken;
 
 use crate::{
     messages::{ConnectionMessage, Request, ServerMessage},
-    resp::{connection::Connection, error::FrameParsingError, types::Frame},
+    resp::{
+        error::FrameParsingError,
+        reader::FrameReader,
+        types::{Frame, ParseLimits, RespVersion},
+    },
 };
 
+// How long to wait before accepting again after failing to
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
 pub async fn bind(host: String, port: u16) -> TcpListener {
     TcpListener::bind(format!("{}:{}", host, port))
         .await
         .expect("Couldn't create tcp listener")
 }
 
-pub async fn run_listener(listener: &mut TcpListener, sender: mpsc::Sender<ConnectionMessage>) {
+// A socket file left behind by a previous run is replaced
+pub fn bind_unix(path: &Path) -> UnixListener {
+    if let Err(e) = std::fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            panic!("Couldn't remove stale unix socket: {}", e);
+        }
+    }
+    UnixListener::bind(path).expect("Couldn't create unix socket listener")
+}
+
+// Accept connections until the server shuts down
+pub async fn run_listener(
+    listener: &mut TcpListener,
+    sender: mpsc::Sender<ConnectionMessage>,
+    shutdown: CancellationToken,
+) {
+    loop {
+        let (socket, _) = select! {
+            result = listener.accept() => match result {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    accept_failed(e).await;
+                    continue;
+                }
+            },
+            _ = shutdown.cancelled() => return,
+        };
+        let sender = sender.clone();
+        tokio::spawn(async move {
+            handle_connection(ClientConnection::new(socket), sender).await;
+        });
+    }
+}
+
+// Failing to accept a connection, e.g. when out of file descriptors, doesn't
+// stop the listener: it waits a bit for things to get better before retrying
+async fn accept_failed(error: std::io::Error) {
+    eprintln!("Error accepting connection: {}", error);
+    tokio::time::sleep(ACCEPT_BACKOFF).await;
+}
+
+// Acceptor terminating TLS with the certificate chain and private key read
+// from PEM files
+pub fn tls_acceptor(cert: &Path, key: &Path) -> std::io::Result<TlsAcceptor> {
+    let invalid = |e: String| std::io::Error::new(std::io::ErrorKind::InvalidData, e);
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| invalid(format!("{}: {}", cert.display(), e)))?;
+    let key = PrivateKeyDer::from_pem_file(key)
+        .map_err(|e| invalid(format!("{}: {}", key.display(), e)))?;
+    let config = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+    .map_err(|e| invalid(e.to_string()))?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+// Like `run_listener`, with every connection going through a TLS handshake
+// first. Connections failing it are dropped.
+pub async fn run_tls_listener(
+    listener: &mut TcpListener,
+    acceptor: TlsAcceptor,
+    sender: mpsc::Sender<ConnectionMessage>,
+    shutdown: CancellationToken,
+) {
+    loop {
+        let (socket, address) = select! {
+            result = listener.accept() => match result {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    accept_failed(e).await;
+                    continue;
+                }
+            },
+            _ = shutdown.cancelled() => return,
+        };
+        let acceptor = acceptor.clone();
+        let sender = sender.clone();
+        tokio::spawn(async move {
+            match acceptor.accept(socket).await {
+                Ok(stream) => handle_connection(ClientConnection::new(stream), sender).await,
+                Err(e) => eprintln!("TLS handshake with {} failed: {}", address, e),
+            }
+        });
+    }
+}
+
+pub async fn run_unix_listener(
+    listener: &mut UnixListener,
+    sender: mpsc::Sender<ConnectionMessage>,
+    shutdown: CancellationToken,
+) {
     loop {
-        let (mut socket, _) = listener.accept().await.unwrap();
+        let (socket, _) = select! {
+            result = listener.accept() => match result {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    accept_failed(e).await;
+                    continue;
+                }
+            },
+            _ = shutdown.cancelled() => return,
+        };
         let sender = sender.clone();
         tokio::spawn(async move {
-            handle_connection(&mut socket, sender).await;
+            handle_connection(ClientConnection::new(socket), sender).await;
         });
     }
 }
 
-async fn handle_connection(socket: &mut TcpStream, sender: mpsc::Sender<ConnectionMessage>) {
+// The connection with a client: frames are read from the stream, replies are
+// written back encoded with the protocol the client negotiated. Replies are
+// buffered until flushed.
+pub struct ClientConnection<S>
+where
+    S: AsyncRead + AsyncWrite,
+{
+    reader: FrameReader<ReadHalf<S>>,
+    writer: BufWriter<WriteHalf<S>>,
+    pub protocol: RespVersion,
+    // Close the connection once nothing was received for this long
+    pub idle_timeout: Option<Duration>,
+}
+
+impl<S> ClientConnection<S>
+where
+    S: AsyncRead + AsyncWrite,
+{
+    pub fn new(stream: S) -> Self {
+        let (reader, writer) = tokio::io::split(stream);
+        let mut reader = FrameReader::new(reader);
+        reader.accept_inline();
+        Self {
+            reader,
+            writer: BufWriter::new(writer),
+            protocol: RespVersion::V2,
+            idle_timeout: None,
+        }
+    }
+
+    pub async fn read_frame(&mut self) -> Result<Option<Frame>, FrameParsingError> {
+        self.reader.read_frame().await
+    }
+
+    // Read the next frame, or None once the connection is idle: nothing was
+    // received, not even part of a frame, for the idle timeout
+    pub async fn read_frame_until_idle(
+        &mut self,
+    ) -> Option<Result<Option<Frame>, FrameParsingError>> {
+        let Some(idle_timeout) = self.idle_timeout else {
+            return Some(self.read_frame().await);
+        };
+        loop {
+            // A timeout too far away to represent never elapses
+            let Some(deadline) = self.reader.last_read().checked_add(idle_timeout) else {
+                return Some(self.read_frame().await);
+            };
+            match tokio::time::timeout_at(deadline, self.reader.read_frame()).await {
+                Ok(result) => return Some(result),
+                // Part of a frame arrived meanwhile, which pushes the deadline
+                Err(_) if self.reader.last_read() > deadline - idle_timeout => continue,
+                Err(_) => return None,
+            }
+        }
+    }
+
+    pub fn set_limits(&mut self, limits: ParseLimits) {
+        self.reader.set_limits(limits);
+    }
+
+    pub async fn write_frame(&mut self, frame: &Frame) -> std::io::Result<()> {
+        self.writer.write_all(&frame.encode(self.protocol)).await
+    }
+
+    pub async fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush().await
+    }
+
+    // Flush the pending replies and close the writing side of the stream
+    pub async fn shutdown(&mut self) -> std::io::Result<()> {
+        self.writer.shutdown().await
+    }
+}
+
+// Why a connection stopped being served
+#[derive(Debug, PartialEq)]
+enum Disconnect {
+    // The client closed the stream
+    Closed,
+    // The client sent QUIT
+    Quit,
+    // The client went idle for longer than the timeout
+    Idle,
+    // Reading, writing or forwarding a request failed
+    Error,
+}
+
+async fn handle_connection<S>(
+    mut connection: ClientConnection<S>,
+    sender: mpsc::Sender<ConnectionMessage>,
+) where
+    S: AsyncRead + AsyncWrite,
+{
     let (connection_sender, mut connection_receiver) = mpsc::channel::<ServerMessage>(32);
 
     if let Err(e) = sender
@@ -36,34 +248,301 @@ async fn handle_connection(socket: &mut TcpStream, sender: mpsc::Sender<Connecti
         return;
     }
 
-    let id = match connection_receiver.recv().await {
-        Some(ServerMessage::ClientInitialized(id)) => id,
-        _ => {
-            eprintln!("Error initializing client");
-            return;
+    // The server sends the current limits before the client id, so that they
+    // apply from the first request
+    let id = loop {
+        match connection_receiver.recv().await {
+            Some(ServerMessage::ClientInitialized(id)) => break id,
+            Some(ServerMessage::Limits(limits)) => connection.set_limits(limits),
+            Some(ServerMessage::IdleTimeout(timeout)) => connection.idle_timeout = timeout,
+            _ => {
+                eprintln!("Error initializing client");
+                return;
+            }
         }
     };
 
-    let mut connection = Connection::new(socket);
+    #[cfg(feature = "tracing")]
+    let span = tracing::info_span!("connection", client_id = id);
+    let reason = serve(
+        &mut connection,
+        id,
+        &sender,
+        &connection_sender,
+        connection_receiver,
+    );
+    #[cfg(feature = "tracing")]
+    let reason = tracing::Instrument::instrument(reason, span.clone());
+    let reason = reason.await;
+    #[cfg(feature = "tracing")]
+    span.in_scope(|| tracing::info!(?reason, "connection closed"));
+    // The replies still buffered are written first, since the server may exit
+    // as soon as it knows every connection is closed
+    let closing = matches!(reason, Disconnect::Quit | Disconnect::Idle);
+    if closing {
+        if let Err(e) = connection.flush().await {
+            eprintln!("Error sending reply: {}", e);
+        }
+    }
+    // Subscriptions and the rest of the client's state are dropped whatever
+    // the reason, before the client can see the connection closed
+    if let Err(e) = sender.send(ConnectionMessage::ClientClosed(id)).await {
+        eprintln!("Error sending client closed: {}", e);
+    }
+    if closing {
+        if let Err(e) = connection.shutdown().await {
+            eprintln!("Error closing connection: {}", e);
+        }
+    }
+}
+
+// Forward the client's requests to the server and write back the replies,
+// until either side goes away
+async fn serve<S>(
+    connection: &mut ClientConnection<S>,
+    id: u64,
+    sender: &mpsc::Sender<ConnectionMessage>,
+    connection_sender: &mpsc::Sender<ServerMessage>,
+    mut connection_receiver: mpsc::Receiver<ServerMessage>,
+) -> Disconnect
+where
+    S: AsyncRead + AsyncWrite,
+{
     loop {
         select! {
-            Ok(Some((frame, _))) = connection.read::<Frame, FrameParsingError>() => {
-                if let Err(e) = sender.send(ConnectionMessage::ClientRequest(Request {
-                    client_id: id,
-                    frame,
-                    connection: connection_sender.clone()
-                })).await {
-                    eprintln!("Error sending request: {}", e);
-                    return;
+            result = connection.read_frame_until_idle() => match result {
+                None => return Disconnect::Idle,
+                Some(Ok(Some(frame))) => {
+                    if let Err(e) = sender.send(ConnectionMessage::ClientRequest(Request {
+                        client_id: id,
+                        frame,
+                        connection: connection_sender.clone()
+                    })).await {
+                        eprintln!("Error sending request: {}", e);
+                        return Disconnect::Error;
+                    }
+                }
+                // Connection closed by the client
+                Some(Ok(None)) => return Disconnect::Closed,
+                // The stream can't be resynchronized after a malformed frame,
+                // so reply with the error and drop the connection
+                Some(Err(e)) => {
+                    let reply = Frame::Error(format!("ERR Protocol error: {}", e));
+                    if let Err(e) = write_reply(connection, &reply).await {
+                        eprintln!("Error sending reply: {}", e);
+                    }
+                    return Disconnect::Error;
                 }
             },
 
-            Some(ServerMessage::Data(frame)) = connection_receiver.recv() =>  {
-                if let Err(e) = connection.write(&frame).await {
-                    eprintln!("Error sending request: {}", e);
-                    return;
+            Some(message) = connection_receiver.recv() => {
+                // Write every reply which is already available before
+                // flushing, so pipelined requests share the same write
+                let mut message = Some(message);
+                while let Some(current) = message {
+                    let reply = match current {
+                        ServerMessage::Data(frame) => Some(frame),
+                        ServerMessage::Error(error) => Some(error.to_frame()),
+                        ServerMessage::Protocol(version) => {
+                            connection.protocol = version;
+                            None
+                        }
+                        ServerMessage::Limits(limits) => {
+                            connection.set_limits(limits);
+                            None
+                        }
+                        ServerMessage::IdleTimeout(timeout) => {
+                            connection.idle_timeout = timeout;
+                            None
+                        }
+                        ServerMessage::ClientInitialized(_) => None,
+                        // Replies to requests pipelined after QUIT are dropped
+                        ServerMessage::Close => return Disconnect::Quit,
+                    };
+                    if let Some(reply) = reply {
+                        if let Err(e) = connection.write_frame(&reply).await {
+                            eprintln!("Error sending reply: {}", e);
+                            return Disconnect::Error;
+                        }
+                    }
+                    message = connection_receiver.try_recv().ok();
+                }
+                if let Err(e) = connection.flush().await {
+                    eprintln!("Error sending reply: {}", e);
+                    return Disconnect::Error;
                 }
             }
         };
     }
 }
+
+async fn write_reply<S>(connection: &mut ClientConnection<S>, reply: &Frame) -> std::io::Result<()>
+where
+    S: AsyncRead + AsyncWrite,
+{
+    connection.write_frame(reply).await?;
+    connection.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        sync::mpsc,
+    };
+
+    use super::{handle_connection, ClientConnection};
+    use crate::{
+        messages::{ConnectionMessage, ServerMessage},
+        resp::types::{Frame, RespVersion},
+    };
+
+    #[tokio::test]
+    async fn test_replies_use_connection_protocol() {
+        let (mut resp2_client, resp2_server) = tokio::io::duplex(64);
+        let (mut resp3_client, resp3_server) = tokio::io::duplex(64);
+        let mut resp2 = ClientConnection::new(resp2_server);
+        let mut resp3 = ClientConnection::new(resp3_server);
+        resp3.protocol = RespVersion::V3;
+
+        resp2.write_frame(&Frame::Null).await.unwrap();
+        resp2.flush().await.unwrap();
+        resp3.write_frame(&Frame::Null).await.unwrap();
+        resp3.flush().await.unwrap();
+
+        let mut buf = [0; 16];
+        let n = resp2_client.read(&mut buf).await.unwrap();
+        assert_eq!(b"$-1\r\n", &buf[..n]);
+        let n = resp3_client.read(&mut buf).await.unwrap();
+        assert_eq!(b"_\r\n", &buf[..n]);
+    }
+
+    #[tokio::test]
+    async fn test_replies_are_buffered_until_flush() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut connection = ClientConnection::new(server);
+
+        connection.write_frame(&Frame::ok()).await.unwrap();
+        connection.write_frame(&Frame::Integer(1)).await.unwrap();
+        let mut buf = [0; 16];
+        assert!(
+            tokio::time::timeout(std::time::Duration::from_millis(10), client.read(&mut buf))
+                .await
+                .is_err()
+        );
+
+        connection.flush().await.unwrap();
+        let n = client.read(&mut buf).await.unwrap();
+        assert_eq!(b"+OK\r\n:1\r\n", &buf[..n]);
+    }
+
+    #[tokio::test]
+    async fn test_read_frame() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut connection = ClientConnection::new(server);
+
+        client.write_all(b"*1\r\n$4\r\nPING\r\n").await.unwrap();
+        drop(client);
+
+        assert_eq!(
+            Some(Frame::Array(vec![Frame::from("PING")])),
+            connection.read_frame().await.unwrap()
+        );
+        assert!(connection.read_frame().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_huge_idle_timeout() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut connection = ClientConnection::new(server);
+        connection.idle_timeout = Some(Duration::MAX);
+
+        client.write_all(b"*1\r\n$4\r\nPING\r\n").await.unwrap();
+
+        assert_eq!(
+            Some(Frame::Array(vec![Frame::from("PING")])),
+            connection.read_frame_until_idle().await.unwrap().unwrap()
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_idle_connection_closed() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let (sender, mut receiver) = mpsc::channel(8);
+        tokio::spawn(handle_connection(ClientConnection::new(server), sender));
+
+        let Some(ConnectionMessage::NewClient(connection)) = receiver.recv().await else {
+            panic!("expected a new client");
+        };
+        let timeout = Some(Duration::from_secs(10));
+        connection
+            .send(ServerMessage::IdleTimeout(timeout))
+            .await
+            .unwrap();
+        connection
+            .send(ServerMessage::ClientInitialized(7))
+            .await
+            .unwrap();
+
+        // Part of a frame is enough to keep the connection open
+        tokio::time::sleep(Duration::from_secs(8)).await;
+        client.write_all(b"*1\r\n$4\r\nPI").await.unwrap();
+        tokio::time::sleep(Duration::from_secs(8)).await;
+        assert!(receiver.try_recv().is_err());
+
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        assert!(matches!(
+            receiver.recv().await,
+            Some(ConnectionMessage::ClientClosed(7))
+        ));
+        assert_eq!(0, client.read(&mut [0; 16]).await.unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_pending_reply_written_before_closed() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let (sender, mut receiver) = mpsc::channel(1);
+        tokio::spawn(handle_connection(
+            ClientConnection::new(server),
+            sender.clone(),
+        ));
+
+        let Some(ConnectionMessage::NewClient(connection)) = receiver.recv().await else {
+            panic!("expected a new client");
+        };
+        connection
+            .send(ServerMessage::ClientInitialized(7))
+            .await
+            .unwrap();
+        // The server's queue is full, so the connection can't report it's
+        // closed until the server reads from it
+        sender
+            .send(ConnectionMessage::ClientClosed(8))
+            .await
+            .unwrap();
+        connection
+            .send(ServerMessage::Data(Frame::ok()))
+            .await
+            .unwrap();
+        connection.send(ServerMessage::Close).await.unwrap();
+
+        let mut buf = [0; 16];
+        let n = tokio::time::timeout(Duration::from_secs(1), client.read(&mut buf))
+            .await
+            .expect("the reply should be written before the server is told")
+            .unwrap();
+        assert_eq!(b"+OK\r\n", &buf[..n]);
+
+        assert!(matches!(
+            receiver.recv().await,
+            Some(ConnectionMessage::ClientClosed(8))
+        ));
+        assert!(matches!(
+            receiver.recv().await,
+            Some(ConnectionMessage::ClientClosed(7))
+        ));
+        assert_eq!(0, client.read(&mut buf).await.unwrap());
+    }
+}
@@ -0,0 +1,46 @@
+// Build a command as an array of bulk strings, e.g. `cmd!("SET", key, value)`.
+// Every argument can be anything convertible into `Bytes`.
+#[macro_export]
+macro_rules! cmd {
+    ($($arg:expr),* $(,)?) => {
+        $crate::resp::types::Frame::Array(vec![
+            $($crate::resp::types::Frame::Bulk(::std::convert::Into::into($arg))),*
+        ])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::resp::types::Frame;
+
+    #[test]
+    fn test_cmd_single_argument() {
+        assert_eq!(
+            Frame::Array(vec![Frame::Bulk(Bytes::from("PING"))]),
+            cmd!("PING")
+        );
+    }
+
+    #[test]
+    fn test_cmd_mixed_arguments() {
+        let key = String::from("key");
+        let value = vec![0u8, 1, 2];
+        assert_eq!(
+            Frame::Array(vec![
+                Frame::Bulk(Bytes::from("SET")),
+                Frame::Bulk(Bytes::from("key")),
+                Frame::Bulk(Bytes::from_static(&[0, 1, 2])),
+                Frame::Bulk(Bytes::from("EX")),
+                Frame::Bulk(Bytes::from("10")),
+            ]),
+            cmd!("SET", key, value, Bytes::from("EX"), "10",)
+        );
+    }
+
+    #[test]
+    fn test_cmd_empty() {
+        assert_eq!(Frame::Array(vec![]), cmd!());
+    }
+}
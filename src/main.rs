@@ -1,3 +1,92 @@
-fn main() {
-    println!("Hello from yarrs!")
+use tokio::signal::unix::{signal, SignalKind};
+use yarrs::{
+    config::ServerConfig,
+    listener::{bind, bind_unix, run_listener, run_tls_listener, run_unix_listener, tls_acceptor},
+    server::Server,
+};
+
+#[tokio::main]
+async fn main() {
+    // Spans and events are filtered through RUST_LOG, e.g. RUST_LOG=yarrs=info
+    #[cfg(feature = "tracing")]
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .init();
+
+    let config = match ServerConfig::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error in the configuration: {}", e);
+            return;
+        }
+    };
+    let unix_socket = config.unix_socket_path();
+    if config.port == 0 && unix_socket.is_none() {
+        eprintln!("Nothing to listen on: port is 0 and no unixsocket is set");
+        return;
+    }
+
+    let tls = match config.tls_files() {
+        Some((cert, key)) => match tls_acceptor(&cert, &key) {
+            Ok(acceptor) => Some(acceptor),
+            Err(e) => {
+                eprintln!("Error setting up TLS: {}", e);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let mut server = Server::with_config(config.bind.clone(), config.port, config);
+    match server.load().await {
+        Ok(Some(path)) => println!("Loaded data from {}", path.display()),
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("Error loading data: {}", e);
+            return;
+        }
+    }
+
+    if server.info.port != 0 {
+        let mut listener = bind(server.info.host.clone(), server.info.port).await;
+        let sender = server.sender.clone();
+        let shutdown = server.shutdown.clone();
+        match tls {
+            Some(acceptor) => tokio::spawn(async move {
+                run_tls_listener(&mut listener, acceptor, sender, shutdown).await;
+            }),
+            None => tokio::spawn(async move {
+                run_listener(&mut listener, sender, shutdown).await;
+            }),
+        };
+        println!("yarrs listening on {}", server.info.address());
+    }
+    if let Some(path) = unix_socket {
+        let mut listener = bind_unix(&path);
+        let sender = server.sender.clone();
+        let shutdown = server.shutdown.clone();
+        tokio::spawn(async move {
+            run_unix_listener(&mut listener, sender, shutdown).await;
+        });
+        println!("yarrs listening on {}", path.display());
+    }
+
+    let shutdown = server.shutdown.clone();
+    tokio::spawn(async move {
+        wait_for_signal().await;
+        println!("Shutting down");
+        shutdown.cancel();
+    });
+
+    server.run().await;
+}
+
+// Resolves on the first SIGINT or SIGTERM
+async fn wait_for_signal() {
+    let mut interrupt = signal(SignalKind::interrupt()).expect("Couldn't listen for SIGINT");
+    let mut terminate = signal(SignalKind::terminate()).expect("Couldn't listen for SIGTERM");
+    tokio::select! {
+        _ = interrupt.recv() => {}
+        _ = terminate.recv() => {}
+    }
 }
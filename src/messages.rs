@@ -1,11 +1,20 @@
+use std::time::Duration;
+
 use tokio::sync::mpsc;
 
-use crate::{resp::types::Frame, server::ServerError};
+use crate::{
+    resp::types::{Frame, ParseLimits, RespVersion},
+    server::ServerError,
+};
 
 #[derive(Debug)]
 pub enum ConnectionMessage {
     NewClient(mpsc::Sender<ServerMessage>),
     ClientRequest(Request),
+    // The client disconnected
+    ClientClosed(u64),
+    // The wait of a parked client timed out
+    BlockTimeout { client_id: u64, token: u64 },
 }
 
 #[derive(Debug, PartialEq)]
@@ -13,6 +22,14 @@ pub enum ServerMessage {
     ClientInitialized(u64),
     Data(Frame),
     Error(ServerError),
+    // Encode the following replies with this protocol version
+    Protocol(RespVersion),
+    // Parse the following requests with these limits
+    Limits(ParseLimits),
+    // Close the connection once idle for this long, if set
+    IdleTimeout(Option<Duration>),
+    // Close the connection once the previous replies are written
+    Close,
 }
 
 #[derive(Debug)]
@@ -24,16 +41,46 @@ pub struct Request {
 
 impl Request {
     pub async fn data(&self, frame: Frame) {
-        self.connection
-            .send(ServerMessage::Data(frame))
-            .await
-            .unwrap();
+        self.send(ServerMessage::Data(frame)).await;
+    }
+
+    pub async fn protocol(&self, version: RespVersion) {
+        self.send(ServerMessage::Protocol(version)).await;
+    }
+
+    pub async fn close(&self) {
+        self.send(ServerMessage::Close).await;
+    }
+
+    pub async fn error(&self, error: impl Into<ServerError>) {
+        self.send(ServerMessage::Error(error.into())).await;
     }
 
-    pub async fn error(&self, error: ServerError) {
-        self.connection
-            .send(ServerMessage::Error(error))
-            .await
-            .unwrap();
+    // The client may have disconnected before its reply is sent, which
+    // leaves nobody to reply to rather than being an error
+    async fn send(&self, message: ServerMessage) {
+        let _ = self.connection.send(message).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc;
+
+    use super::Request;
+    use crate::{cmd, resp::types::Frame};
+
+    #[tokio::test]
+    async fn test_reply_to_closed_connection() {
+        let (sender, receiver) = mpsc::channel(1);
+        let request = Request {
+            client_id: 0,
+            frame: cmd!("ECHO", "a"),
+            connection: sender,
+        };
+        drop(receiver);
+
+        request.data(Frame::from("a")).await;
+        request.close().await;
     }
 }
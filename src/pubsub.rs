@@ -0,0 +1,202 @@
+use std::collections::{HashMap, HashSet};
+
+use bytes::Bytes;
+
+use crate::glob;
+
+// Which clients are subscribed to which channels, and to which patterns
+#[derive(Default)]
+pub struct PubSub {
+    channels: Subscriptions,
+    patterns: Subscriptions,
+}
+
+// Subscriptions indexed both by their name and by client
+#[derive(Default)]
+struct Subscriptions {
+    names: HashMap<Bytes, HashSet<u64>>,
+    clients: HashMap<u64, HashSet<Bytes>>,
+}
+
+impl Subscriptions {
+    fn add(&mut self, client: u64, name: Bytes) {
+        self.names.entry(name.clone()).or_default().insert(client);
+        self.clients.entry(client).or_default().insert(name);
+    }
+
+    fn remove(&mut self, client: u64, name: &[u8]) {
+        if let Some(subscribers) = self.names.get_mut(name) {
+            subscribers.remove(&client);
+            if subscribers.is_empty() {
+                self.names.remove(name);
+            }
+        }
+        if let Some(names) = self.clients.get_mut(&client) {
+            names.remove(name);
+            if names.is_empty() {
+                self.clients.remove(&client);
+            }
+        }
+    }
+
+    fn of(&self, client: u64) -> Vec<Bytes> {
+        self.clients
+            .get(&client)
+            .map(|names| names.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn count(&self, client: u64) -> usize {
+        self.clients.get(&client).map_or(0, HashSet::len)
+    }
+}
+
+impl PubSub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Subscribe the client, returning how many channels and patterns it's now
+    // subscribed to
+    pub fn subscribe(&mut self, client: u64, channel: Bytes) -> usize {
+        self.channels.add(client, channel);
+        self.count(client)
+    }
+
+    // Unsubscribe the client, returning how many channels and patterns it's
+    // still subscribed to
+    pub fn unsubscribe(&mut self, client: u64, channel: &[u8]) -> usize {
+        self.channels.remove(client, channel);
+        self.count(client)
+    }
+
+    pub fn psubscribe(&mut self, client: u64, pattern: Bytes) -> usize {
+        self.patterns.add(client, pattern);
+        self.count(client)
+    }
+
+    pub fn punsubscribe(&mut self, client: u64, pattern: &[u8]) -> usize {
+        self.patterns.remove(client, pattern);
+        self.count(client)
+    }
+
+    // Channels the client is subscribed to
+    pub fn channels(&self, client: u64) -> Vec<Bytes> {
+        self.channels.of(client)
+    }
+
+    // Patterns the client is subscribed to
+    pub fn patterns(&self, client: u64) -> Vec<Bytes> {
+        self.patterns.of(client)
+    }
+
+    pub fn subscribers(&self, channel: &[u8]) -> Vec<u64> {
+        self.channels
+            .names
+            .get(channel)
+            .map(|clients| clients.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    // Clients subscribed to a pattern matching the channel, along with the
+    // pattern. A client shows up once for each of its matching patterns.
+    pub fn pattern_subscribers(&self, channel: &[u8]) -> Vec<(Bytes, u64)> {
+        self.patterns
+            .names
+            .iter()
+            .filter(|(pattern, _)| glob::matches(pattern, channel))
+            .flat_map(|(pattern, clients)| clients.iter().map(|&client| (pattern.clone(), client)))
+            .collect()
+    }
+
+    // How many channels and patterns the client is subscribed to
+    pub fn count(&self, client: u64) -> usize {
+        self.channels.count(client) + self.patterns.count(client)
+    }
+
+    // Forget every subscription of a client which went away
+    pub fn remove_client(&mut self, client: u64) {
+        for channel in self.channels(client) {
+            self.channels.remove(client, &channel);
+        }
+        for pattern in self.patterns(client) {
+            self.patterns.remove(client, &pattern);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::PubSub;
+
+    #[test]
+    fn test_subscribe() {
+        let mut pubsub = PubSub::new();
+
+        assert_eq!(1, pubsub.subscribe(1, Bytes::from("news")));
+        assert_eq!(2, pubsub.subscribe(1, Bytes::from("sports")));
+        assert_eq!(2, pubsub.subscribe(1, Bytes::from("news")));
+        assert_eq!(1, pubsub.subscribe(2, Bytes::from("news")));
+
+        let mut subscribers = pubsub.subscribers(b"news");
+        subscribers.sort();
+        assert_eq!(vec![1, 2], subscribers);
+        assert_eq!(vec![1], pubsub.subscribers(b"sports"));
+        assert!(pubsub.subscribers(b"other").is_empty());
+    }
+
+    #[test]
+    fn test_unsubscribe() {
+        let mut pubsub = PubSub::new();
+        pubsub.subscribe(1, Bytes::from("news"));
+        pubsub.subscribe(1, Bytes::from("sports"));
+
+        assert_eq!(1, pubsub.unsubscribe(1, b"news"));
+        assert_eq!(1, pubsub.unsubscribe(1, b"missing"));
+        assert_eq!(0, pubsub.unsubscribe(1, b"sports"));
+        assert!(pubsub.subscribers(b"news").is_empty());
+        assert!(pubsub.channels(1).is_empty());
+    }
+
+    #[test]
+    fn test_remove_client() {
+        let mut pubsub = PubSub::new();
+        pubsub.subscribe(1, Bytes::from("news"));
+        pubsub.subscribe(1, Bytes::from("sports"));
+        pubsub.subscribe(2, Bytes::from("news"));
+        pubsub.psubscribe(1, Bytes::from("news.*"));
+
+        pubsub.remove_client(1);
+
+        assert_eq!(vec![2], pubsub.subscribers(b"news"));
+        assert!(pubsub.subscribers(b"sports").is_empty());
+        assert!(pubsub.pattern_subscribers(b"news.tech").is_empty());
+    }
+
+    #[test]
+    fn test_psubscribe() {
+        let mut pubsub = PubSub::new();
+
+        assert_eq!(1, pubsub.subscribe(1, Bytes::from("news")));
+        assert_eq!(2, pubsub.psubscribe(1, Bytes::from("news.*")));
+        assert_eq!(3, pubsub.psubscribe(1, Bytes::from("*.tech")));
+        assert_eq!(1, pubsub.psubscribe(2, Bytes::from("news.*")));
+
+        let mut subscribers = pubsub.pattern_subscribers(b"news.tech");
+        subscribers.sort();
+        assert_eq!(
+            vec![
+                (Bytes::from("*.tech"), 1),
+                (Bytes::from("news.*"), 1),
+                (Bytes::from("news.*"), 2)
+            ],
+            subscribers
+        );
+        assert!(pubsub.pattern_subscribers(b"sports.news").is_empty());
+
+        assert_eq!(2, pubsub.punsubscribe(1, b"*.tech"));
+        assert_eq!(vec![Bytes::from("news.*")], pubsub.patterns(1));
+    }
+}
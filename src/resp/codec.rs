@@ -0,0 +1,144 @@
+use std::io::Cursor;
+
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::resp::{
+    error::FrameParsingError,
+    types::{Frame, ParseLimits, RespVersion},
+};
+
+// tokio_util codec to use Frame with Framed streams
+#[derive(Debug, Clone)]
+pub struct FrameCodec {
+    pub version: RespVersion,
+    pub limits: ParseLimits,
+    // Whether inline commands are decoded too, only for requests read by the server
+    pub inline: bool,
+}
+
+impl FrameCodec {
+    pub fn new(version: RespVersion) -> Self {
+        Self::with_limits(version, ParseLimits::default())
+    }
+
+    pub fn with_limits(version: RespVersion, limits: ParseLimits) -> Self {
+        Self {
+            version,
+            limits,
+            inline: false,
+        }
+    }
+}
+
+impl Decoder for FrameCodec {
+    type Item = Frame;
+    type Error = FrameParsingError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let mut cursor = Cursor::new(&src[..]);
+        let parsed = if self.inline {
+            Frame::parse_request(&mut cursor, &self.limits)
+        } else {
+            Frame::parse_with_limits(&mut cursor, &self.limits)
+        };
+        match parsed {
+            Ok(frame) => {
+                let len = cursor.position() as usize;
+                src.advance(len);
+                Ok(Some(frame))
+            }
+            Err(FrameParsingError::Incomplete) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl Encoder<Frame> for FrameCodec {
+    type Error = FrameParsingError;
+
+    fn encode(&mut self, item: Frame, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        item.encode_to(dst, self.version);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    use super::FrameCodec;
+    use crate::resp::{
+        error::FrameParsingError,
+        types::{Frame, RespVersion},
+    };
+
+    #[test]
+    fn decode_partial_then_complete() {
+        let mut codec = FrameCodec::new(RespVersion::V3);
+        let mut buf = BytesMut::from("*2\r\n$4\r\nECHO\r\n$5\r\nhel");
+
+        assert!(matches!(codec.decode(&mut buf), Ok(None)));
+        assert_eq!(buf.len(), 21);
+
+        buf.extend_from_slice(b"lo\r\n:1\r\n");
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(Frame::Array(vec![
+                Frame::Bulk("ECHO".into()),
+                Frame::Bulk("hello".into())
+            ]))
+        );
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(Frame::Integer(1)));
+        assert!(buf.is_empty());
+        assert!(matches!(codec.decode(&mut buf), Ok(None)));
+    }
+
+    #[test]
+    fn decode_invalid_frame_returns_error() {
+        let mut codec = FrameCodec::new(RespVersion::V3);
+        let mut buf = BytesMut::from("@garbage\r\n");
+
+        assert!(matches!(
+            codec
+                .decode(&mut buf)
+                .as_ref()
+                .map_err(FrameParsingError::cause),
+            Err(FrameParsingError::UnknownType(b'@'))
+        ));
+    }
+
+    #[test]
+    fn decode_inline_only_when_enabled() {
+        let mut codec = FrameCodec::new(RespVersion::V3);
+        let mut buf = BytesMut::from("PING\r\n");
+        assert!(matches!(
+            codec
+                .decode(&mut buf)
+                .as_ref()
+                .map_err(FrameParsingError::cause),
+            Err(FrameParsingError::UnknownType(b'P'))
+        ));
+
+        codec.inline = true;
+        assert_eq!(
+            Some(Frame::Array(vec![Frame::Bulk("PING".into())])),
+            codec.decode(&mut buf).unwrap()
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn encode_uses_codec_version() {
+        let mut buf = BytesMut::new();
+        FrameCodec::new(RespVersion::V2)
+            .encode(Frame::Null, &mut buf)
+            .unwrap();
+        FrameCodec::new(RespVersion::V3)
+            .encode(Frame::Null, &mut buf)
+            .unwrap();
+
+        assert_eq!(&buf[..], b"$-1\r\n_\r\n");
+    }
+}
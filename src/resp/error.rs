@@ -6,14 +6,52 @@ use std::{
 };
 
 use anyhow::anyhow;
+use num_bigint::ParseBigIntError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum FrameParsingError {
     #[error("Incomplete buffer to parse message")]
     Incomplete,
+    #[error("Unknown frame type '{}'", *.0 as char)]
+    UnknownType(u8),
+    #[error("Frame length {0} exceeds the configured limit")]
+    LengthLimitExceeded(usize),
+    #[error("Frame nesting exceeds the configured depth limit")]
+    DepthLimitExceeded,
+    #[error("invalid integer: {0:?}")]
+    InvalidInteger(String),
+    #[error("invalid double: {0:?}")]
+    InvalidDouble(String),
+    #[error("invalid utf-8: {0}")]
+    InvalidUtf8(#[source] Utf8Error),
+    #[error("protocol error: {0}")]
+    Protocol(String),
     #[error(transparent)]
     Other(#[from] anyhow::Error),
+    // Offset (in the parsed buffer) of the frame which failed to parse
+    #[error("{source} at byte {offset}")]
+    At {
+        offset: usize,
+        source: Box<FrameParsingError>,
+    },
+}
+
+impl FrameParsingError {
+    // The error without its position
+    pub fn cause(&self) -> &FrameParsingError {
+        match self {
+            FrameParsingError::At { source, .. } => source,
+            other => other,
+        }
+    }
+
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            FrameParsingError::At { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
 }
 
 impl From<String> for FrameParsingError {
@@ -29,20 +67,31 @@ impl From<&str> for FrameParsingError {
 }
 
 impl From<FromUtf8Error> for FrameParsingError {
-    fn from(_value: FromUtf8Error) -> Self {
-        "invalid frame format".into()
+    fn from(value: FromUtf8Error) -> Self {
+        FrameParsingError::InvalidUtf8(value.utf8_error())
+    }
+}
+
+// Maps the error of a numeric parse to the FrameParsingError carrying the token
+pub(crate) trait InvalidToken {
+    fn invalid_token(token: &str) -> FrameParsingError;
+}
+
+impl InvalidToken for ParseIntError {
+    fn invalid_token(token: &str) -> FrameParsingError {
+        FrameParsingError::InvalidInteger(token.to_string())
     }
 }
 
-impl From<ParseIntError> for FrameParsingError {
-    fn from(_value: ParseIntError) -> Self {
-        "invalid integer format".into()
+impl InvalidToken for ParseBigIntError {
+    fn invalid_token(token: &str) -> FrameParsingError {
+        FrameParsingError::InvalidInteger(token.to_string())
     }
 }
 
-impl From<ParseFloatError> for FrameParsingError {
-    fn from(_value: ParseFloatError) -> Self {
-        "invalid double format".into()
+impl InvalidToken for ParseFloatError {
+    fn invalid_token(token: &str) -> FrameParsingError {
+        FrameParsingError::InvalidDouble(token.to_string())
     }
 }
 
@@ -60,6 +109,14 @@ impl From<TryFromSliceError> for FrameParsingError {
 
 impl From<Utf8Error> for FrameParsingError {
     fn from(value: Utf8Error) -> Self {
-        FrameParsingError::Other(anyhow!(value))
+        FrameParsingError::InvalidUtf8(value)
     }
 }
+
+#[derive(Error, Debug)]
+pub enum FrameConversionError {
+    #[error("expected {expected}, found frame type '{}'", *.found as char)]
+    UnexpectedType { expected: &'static str, found: u8 },
+    #[error("invalid utf-8: {0}")]
+    InvalidUtf8(#[from] FromUtf8Error),
+}
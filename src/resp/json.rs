@@ -0,0 +1,131 @@
+use serde_json::{Map, Number, Value};
+
+use crate::resp::types::Frame;
+
+impl Frame {
+    // Lossy mapping of a reply to JSON:
+    // - simple and bulk strings become strings. Bulk payloads which aren't valid
+    //   utf-8 are base64 encoded instead, so binary data can't be told apart from
+    //   a string holding its base64 text
+    // - errors become `{"error": message}`
+    // - integers and doubles become numbers, infinities and NaN become null
+    // - big numbers become strings, as they may not fit a JSON number
+    // - arrays, sets and pushes become arrays, maps become objects. Keys which
+    //   aren't strings are replaced by their JSON text
+    // - attributes are dropped
+    pub fn to_json(&self) -> Value {
+        match self {
+            Frame::Array(frames) | Frame::Push(frames) | Frame::Set(frames) => {
+                Value::Array(frames.iter().map(Frame::to_json).collect())
+            }
+            Frame::BigNumber(n) => Value::String(n.to_string()),
+            Frame::Boolean(b) => Value::Bool(*b),
+            Frame::Bulk(bytes) => bytes_to_json(bytes),
+            Frame::BulkError(bytes) => error_to_json(String::from_utf8_lossy(bytes).into_owned()),
+            Frame::Double(n) => Number::from_f64(*n).map_or(Value::Null, Value::Number),
+            Frame::Error(message) => error_to_json(message.clone()),
+            Frame::Integer(n) => Value::Number((*n).into()),
+            Frame::Map(pairs) => {
+                let mut object = Map::new();
+                for (key, value) in pairs {
+                    let key = match key.to_json() {
+                        Value::String(s) => s,
+                        other => other.to_string(),
+                    };
+                    object.insert(key, value.to_json());
+                }
+                Value::Object(object)
+            }
+            Frame::Null => Value::Null,
+            Frame::Simple(s) => Value::String(s.clone()),
+            Frame::Verbatim { data, .. } => bytes_to_json(data),
+            Frame::WithAttributes { value, .. } => value.to_json(),
+        }
+    }
+}
+
+fn bytes_to_json(bytes: &[u8]) -> Value {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => Value::String(s.to_string()),
+        Err(_) => Value::String(base64(bytes)),
+    }
+}
+
+fn error_to_json(message: String) -> Value {
+    let mut object = Map::new();
+    object.insert("error".to_string(), Value::String(message));
+    Value::Object(object)
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Standard, padded base64
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::base64;
+    use crate::resp::types::Frame;
+
+    #[test]
+    fn test_to_json_nested_array_of_maps() {
+        let frame = Frame::Array(vec![
+            Frame::Map(vec![
+                (
+                    Frame::Simple("name".to_string()),
+                    Frame::Bulk(Bytes::from("yarrs")),
+                ),
+                (Frame::Simple("port".to_string()), Frame::Integer(6379)),
+                (Frame::Integer(1), Frame::Boolean(true)),
+            ]),
+            Frame::Map(vec![(
+                Frame::Bulk(Bytes::from("tags")),
+                Frame::Set(vec![Frame::Double(0.5), Frame::Null]),
+            )]),
+            Frame::Error("ERR oops".to_string()),
+        ]);
+        let expected = json!([
+            {"name": "yarrs", "port": 6379, "1": true},
+            {"tags": [0.5, null]},
+            {"error": "ERR oops"},
+        ]);
+        assert_eq!(expected, frame.to_json());
+    }
+
+    #[test]
+    fn test_to_json_binary_bulk_is_base64() {
+        let frame = Frame::Bulk(Bytes::from_static(b"\xff\x00\x01"));
+        assert_eq!(json!("/wAB"), frame.to_json());
+    }
+
+    #[rstest]
+    #[case(b"", "")]
+    #[case(b"f", "Zg==")]
+    #[case(b"fo", "Zm8=")]
+    #[case(b"foo", "Zm9v")]
+    #[case(b"foobar", "Zm9vYmFy")]
+    fn test_base64(#[case] input: &[u8], #[case] expected: &str) {
+        assert_eq!(expected, base64(input));
+    }
+}
@@ -1,3 +1,9 @@
+pub mod codec;
 pub mod connection;
 pub mod error;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod reader;
+#[cfg(test)]
+pub(crate) mod strategy;
 pub mod types;
@@ -0,0 +1,367 @@
+use bytes::{Buf, BytesMut};
+use std::io::{Cursor, ErrorKind};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    time::Instant,
+};
+use tokio_util::codec::Decoder;
+
+use crate::resp::{
+    codec::FrameCodec,
+    error::FrameParsingError,
+    types::{Frame, ParseLimits, RespVersion},
+};
+
+// Capacity the buffer starts with, and goes back to after large frames
+const BASELINE_CAPACITY: usize = 4096;
+// Once the buffer held more than this, it's shrunk back to the baseline as
+// soon as it's drained, so that a huge frame doesn't pin its memory for as
+// long as the connection lasts
+const HIGH_WATER_LIMIT: usize = 64 * 1024;
+
+// Reads frames out of a stream, buffering partial frames until they're complete
+pub struct FrameReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    reader: R,
+    buffer: BytesMut,
+    // Most bytes the buffer held at once since it was last shrunk
+    high_water: usize,
+    codec: FrameCodec,
+    // When bytes were last received, or the reader created
+    last_read: Instant,
+}
+
+impl<R> FrameReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    pub fn new(reader: R) -> Self {
+        Self::with_limits(reader, ParseLimits::default())
+    }
+
+    pub fn with_limits(reader: R, limits: ParseLimits) -> Self {
+        Self {
+            reader,
+            buffer: BytesMut::with_capacity(BASELINE_CAPACITY),
+            high_water: 0,
+            codec: FrameCodec::with_limits(RespVersion::V3, limits),
+            last_read: Instant::now(),
+        }
+    }
+
+    // Limits for the frames read from now on
+    pub fn set_limits(&mut self, limits: ParseLimits) {
+        self.codec.limits = limits;
+    }
+
+    // Decode inline commands as well as RESP frames, as servers do
+    pub fn accept_inline(&mut self) {
+        self.codec.inline = true;
+    }
+
+    // When bytes were last received, even if they don't make a frame yet
+    pub fn last_read(&self) -> Instant {
+        self.last_read
+    }
+
+    // Returns None when the stream is closed between two frames
+    pub async fn read_frame(&mut self) -> Result<Option<Frame>, FrameParsingError> {
+        self.read_with(|codec, buffer| codec.decode(buffer)).await
+    }
+
+    // Start reading an array, whose elements are then read one at a time
+    // from the decoder. Returns None when the stream is closed between two frames.
+    pub async fn read_array(&mut self) -> Result<Option<ArrayDecoder<'_, R>>, FrameParsingError> {
+        let Some(len) = self
+            .read_with(|_, buffer| decode_with(buffer, Frame::parse_array_len))
+            .await?
+        else {
+            return Ok(None);
+        };
+        Ok(Some(ArrayDecoder {
+            reader: self,
+            len: len.unwrap_or(0),
+            remaining: len.unwrap_or(0),
+            null: len.is_none(),
+        }))
+    }
+
+    // Read from the stream until `decode` gets something out of the buffer
+    async fn read_with<T>(
+        &mut self,
+        mut decode: impl FnMut(&mut FrameCodec, &mut BytesMut) -> Result<Option<T>, FrameParsingError>,
+    ) -> Result<Option<T>, FrameParsingError> {
+        loop {
+            if let Some(value) = decode(&mut self.codec, &mut self.buffer)? {
+                self.shrink_if_drained();
+                return Ok(Some(value));
+            }
+
+            if 0 == self.reader.read_buf(&mut self.buffer).await? {
+                if !self.buffer.is_empty() {
+                    return Err(closed());
+                }
+                return Ok(None);
+            }
+            self.high_water = self.high_water.max(self.buffer.len());
+            self.last_read = Instant::now();
+        }
+    }
+
+    // Move what's left in the buffer to a new one of the baseline capacity,
+    // if it grew past the high water limit and what's left fits
+    fn shrink_if_drained(&mut self) {
+        if self.high_water <= HIGH_WATER_LIMIT || self.buffer.len() > BASELINE_CAPACITY {
+            return;
+        }
+        let mut buffer = BytesMut::with_capacity(BASELINE_CAPACITY);
+        buffer.extend_from_slice(&self.buffer);
+        self.buffer = buffer;
+        self.high_water = self.buffer.len();
+    }
+}
+
+// Elements of an array read one at a time as they arrive, so that huge
+// replies don't have to be held in memory all at once
+pub struct ArrayDecoder<'a, R>
+where
+    R: AsyncRead + Unpin,
+{
+    reader: &'a mut FrameReader<R>,
+    len: usize,
+    remaining: usize,
+    // Whether this is the null array (`*-1`) rather than an empty one
+    null: bool,
+}
+
+impl<R> ArrayDecoder<'_, R>
+where
+    R: AsyncRead + Unpin,
+{
+    // Number of elements declared by the array header
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // The null array, which has no elements either
+    pub fn is_null(&self) -> bool {
+        self.null
+    }
+
+    // Number of elements not read yet
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    // The next element, None once all of them have been read
+    pub async fn next(&mut self) -> Result<Option<Frame>, FrameParsingError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        let frame = self
+            .reader
+            .read_with(|codec, buffer| {
+                decode_with(buffer, |cursor| Frame::parse_element(cursor, &codec.limits))
+            })
+            .await?
+            .ok_or_else(closed)?;
+        self.remaining -= 1;
+        Ok(Some(frame))
+    }
+}
+
+// Run `parse` on the buffer, consuming what it parsed. None if the buffer
+// doesn't hold enough data yet.
+fn decode_with<T>(
+    buffer: &mut BytesMut,
+    parse: impl FnOnce(&mut Cursor<&[u8]>) -> Result<T, FrameParsingError>,
+) -> Result<Option<T>, FrameParsingError> {
+    let mut cursor = Cursor::new(&buffer[..]);
+    match parse(&mut cursor) {
+        Ok(value) => {
+            let len = cursor.position() as usize;
+            buffer.advance(len);
+            Ok(Some(value))
+        }
+        Err(FrameParsingError::Incomplete) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn closed() -> FrameParsingError {
+    std::io::Error::new(ErrorKind::BrokenPipe, "Connection closed").into()
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt;
+
+    use super::{FrameReader, BASELINE_CAPACITY};
+    use crate::resp::types::Frame;
+
+    #[tokio::test]
+    async fn read_pipelined_frames() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = FrameReader::new(server);
+
+        client
+            .write_all(b"+first\r\n:2\r\n$5\r\nthird\r\n")
+            .await
+            .unwrap();
+        drop(client);
+
+        assert_eq!(
+            reader.read_frame().await.unwrap(),
+            Some(Frame::Simple("first".into()))
+        );
+        assert_eq!(reader.read_frame().await.unwrap(), Some(Frame::Integer(2)));
+        assert_eq!(
+            reader.read_frame().await.unwrap(),
+            Some(Frame::Bulk("third".into()))
+        );
+        assert_eq!(reader.read_frame().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn read_frame_split_across_writes() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = FrameReader::new(server);
+
+        tokio::spawn(async move {
+            for chunk in ["*2\r\n$4\r\nEC", "HO\r\n$5", "\r\nhello", "\r\n"] {
+                client.write_all(chunk.as_bytes()).await.unwrap();
+                tokio::task::yield_now().await;
+            }
+        });
+
+        assert_eq!(
+            reader.read_frame().await.unwrap(),
+            Some(Frame::Array(vec![
+                Frame::Bulk("ECHO".into()),
+                Frame::Bulk("hello".into())
+            ]))
+        );
+        assert_eq!(reader.read_frame().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn closed_mid_frame_returns_error() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = FrameReader::new(server);
+
+        client.write_all(b"$5\r\nhel").await.unwrap();
+        drop(client);
+
+        assert!(reader.read_frame().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn invalid_frame_returns_error() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = FrameReader::new(server);
+
+        client.write_all(b"@garbage\r\n").await.unwrap();
+
+        assert!(reader.read_frame().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn read_array_incrementally() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = FrameReader::new(server);
+
+        tokio::spawn(async move {
+            let mut data = b"*1000\r\n".to_vec();
+            for i in 0..1000 {
+                data.extend_from_slice(format!("${}\r\n{}\r\n", i.to_string().len(), i).as_bytes());
+            }
+            data.extend_from_slice(b":1\r\n");
+            // Far more than the pipe holds, so elements are read as they arrive
+            client.write_all(&data).await.unwrap();
+        });
+
+        let mut array = reader.read_array().await.unwrap().unwrap();
+        assert_eq!(1000, array.len());
+        for i in 0..1000 {
+            assert_eq!(1000 - i, array.remaining());
+            assert_eq!(
+                Some(Frame::Bulk(i.to_string().into())),
+                array.next().await.unwrap()
+            );
+        }
+        assert_eq!(None, array.next().await.unwrap());
+        assert_eq!(Some(Frame::Integer(1)), reader.read_frame().await.unwrap());
+        assert_eq!(None, reader.read_frame().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn read_array_null_or_empty() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = FrameReader::new(server);
+
+        client.write_all(b"*-1\r\n*0\r\n").await.unwrap();
+
+        let mut array = reader.read_array().await.unwrap().unwrap();
+        assert!(array.is_null());
+        assert!(array.is_empty());
+        assert_eq!(None, array.next().await.unwrap());
+
+        let array = reader.read_array().await.unwrap().unwrap();
+        assert!(!array.is_null());
+        assert!(array.is_empty());
+    }
+
+    #[tokio::test]
+    async fn read_array_not_an_array() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = FrameReader::new(server);
+
+        client.write_all(b"+OK\r\n").await.unwrap();
+
+        assert!(reader.read_array().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn read_array_closed_mid_array() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = FrameReader::new(server);
+
+        client.write_all(b"*2\r\n:1\r\n").await.unwrap();
+        drop(client);
+
+        let mut array = reader.read_array().await.unwrap().unwrap();
+        assert_eq!(Some(Frame::Integer(1)), array.next().await.unwrap());
+        assert!(array.next().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn buffer_shrinks_after_large_frame() {
+        let (mut client, server) = tokio::io::duplex(64 * 1024);
+        let mut reader = FrameReader::new(server);
+        let len = 4 * 1024 * 1024;
+
+        tokio::spawn(async move {
+            let mut frames = format!("${}\r\n", len).into_bytes();
+            frames.resize(frames.len() + len, b'x');
+            frames.extend_from_slice(b"\r\n+after\r\n");
+            client.write_all(&frames).await.unwrap();
+        });
+
+        let Some(Frame::Bulk(bulk)) = reader.read_frame().await.unwrap() else {
+            panic!("expected the bulk string");
+        };
+        assert_eq!(len, bulk.len());
+        assert!(reader.buffer.capacity() <= BASELINE_CAPACITY);
+        // What was read past the large frame is kept
+        assert_eq!(
+            Some(Frame::Simple("after".into())),
+            reader.read_frame().await.unwrap()
+        );
+    }
+}
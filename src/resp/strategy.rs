@@ -0,0 +1,69 @@
+// proptest strategies generating arbitrary frames, for property tests
+// checking the parser against the encoder
+
+use bytes::Bytes;
+use num_bigint::BigInt;
+use proptest::{collection::vec, prelude::*};
+
+use crate::resp::types::{Frame, VerbatimEncoding};
+
+// Bounds of the generated trees, well within the default parse limits
+const MAX_DEPTH: u32 = 4;
+const MAX_FRAMES: u32 = 64;
+const MAX_WIDTH: usize = 8;
+
+fn bytes() -> impl Strategy<Value = Bytes> {
+    vec(any::<u8>(), 0..32).prop_map(Bytes::from)
+}
+
+// Simple strings and errors end at the first newline, so they can't hold any
+fn line() -> impl Strategy<Value = String> {
+    "[^\r\n]{0,32}"
+}
+
+fn verbatim_encoding() -> impl Strategy<Value = VerbatimEncoding> {
+    prop_oneof![
+        Just(VerbatimEncoding::Text),
+        Just(VerbatimEncoding::Markdown),
+        // `txt` and `mkd` are parsed back as the named variants
+        any::<[u8; 3]>()
+            .prop_filter("named encoding", |format| {
+                format != b"txt" && format != b"mkd"
+            })
+            .prop_map(VerbatimEncoding::Other),
+    ]
+}
+
+// Non aggregate frames. Doubles cover the whole f64 range, NaN and
+// infinities included.
+pub fn leaf() -> impl Strategy<Value = Frame> {
+    prop_oneof![
+        any::<i128>().prop_map(|n| Frame::BigNumber(BigInt::from(n))),
+        any::<bool>().prop_map(Frame::Boolean),
+        bytes().prop_map(Frame::Bulk),
+        bytes().prop_map(Frame::BulkError),
+        any::<f64>().prop_map(Frame::Double),
+        line().prop_map(Frame::Error),
+        any::<i64>().prop_map(Frame::Integer),
+        Just(Frame::Null),
+        line().prop_map(Frame::Simple),
+        (verbatim_encoding(), bytes()).prop_map(|(format, data)| Frame::Verbatim { format, data }),
+    ]
+}
+
+// Any frame, with aggregates (empty ones included) nested up to a few levels
+pub fn frame() -> impl Strategy<Value = Frame> {
+    leaf().prop_recursive(MAX_DEPTH, MAX_FRAMES, MAX_WIDTH as u32, |inner| {
+        let pairs = vec((inner.clone(), inner.clone()), 0..MAX_WIDTH);
+        prop_oneof![
+            vec(inner.clone(), 0..MAX_WIDTH).prop_map(Frame::Array),
+            pairs.clone().prop_map(Frame::Map),
+            vec(inner.clone(), 0..MAX_WIDTH).prop_map(Frame::Push),
+            vec(inner.clone(), 0..MAX_WIDTH).prop_map(Frame::Set),
+            (pairs, inner).prop_map(|(attributes, value)| Frame::WithAttributes {
+                attributes,
+                value: Box::new(value),
+            }),
+        ]
+    })
+}
@@ -1,15 +1,21 @@
 use std::{
-    collections::{HashMap, HashSet},
+    fmt,
     hash::Hash,
     io::{Cursor, Read},
     str::{self, FromStr},
 };
 
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
+use memchr::memchr;
+use num_bigint::BigInt;
 
-use crate::{resp::connection::Message, resp::error::FrameParsingError};
+use crate::resp::{
+    connection::Message,
+    error::{FrameConversionError, FrameParsingError, InvalidToken},
+};
 
-#[derive(Debug, PartialEq, Hash)]
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VerbatimEncoding {
     Text,
     Markdown,
@@ -29,23 +35,121 @@ impl TryFrom<&[u8]> for VerbatimEncoding {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RespVersion {
+    V2,
+    V3,
+}
+
+// Bounds applied while parsing untrusted input
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParseLimits {
+    // Maximum payload size of bulk strings, bulk errors and verbatim strings
+    pub max_bulk_len: usize,
+    // Maximum number of nested aggregates (arrays, maps, sets, pushes)
+    pub max_depth: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            max_bulk_len: 512 * 1024 * 1024,
+            max_depth: 128,
+        }
+    }
+}
+
+impl ParseLimits {
+    fn check_bulk_len(&self, len: usize) -> Result<usize, FrameParsingError> {
+        if len > self.max_bulk_len {
+            return Err(FrameParsingError::LengthLimitExceeded(len));
+        }
+        Ok(len)
+    }
+
+    // Limits to apply to the children of an aggregate
+    fn nested(&self) -> Result<ParseLimits, FrameParsingError> {
+        match self.max_depth {
+            0 => Err(FrameParsingError::DepthLimitExceeded),
+            depth => Ok(ParseLimits {
+                max_depth: depth - 1,
+                ..*self
+            }),
+        }
+    }
+}
+
+// With the `serde` feature frames are (de)serialized as `{"type": ..., "value": ...}`,
+// bulk payloads as arrays of bytes
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "value")
+)]
 pub enum Frame {
     Array(Vec<Frame>),
-    Attribute(HashMap<Frame, Frame>),
-    BigNumber(String),
+    BigNumber(BigInt),
     Boolean(bool),
     Bulk(Bytes),
-    BulkError(String),
+    BulkError(Bytes),
     Double(f64),
     Error(String),
     Integer(i64),
-    Map(HashMap<Frame, Frame>),
+    // Pairs are kept in wire order, duplicated keys included
+    Map(Vec<(Frame, Frame)>),
     Null,
     Push(Vec<Frame>),
-    Set(HashSet<Frame>),
+    // Elements are kept as received, duplicates included
+    Set(Vec<Frame>),
     Simple(String),
-    Verbatim(VerbatimEncoding, String),
+    Verbatim {
+        format: VerbatimEncoding,
+        data: Bytes,
+    },
+    // Attribute map (`|`) sent in front of the frame it describes
+    WithAttributes {
+        attributes: Vec<(Frame, Frame)>,
+        value: Box<Frame>,
+    },
+}
+
+// Implemented by hand so that doubles compare the same way they hash (NaN == NaN)
+impl PartialEq for Frame {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Frame::Array(a), Frame::Array(b)) => a == b,
+            (Frame::BigNumber(a), Frame::BigNumber(b)) => a == b,
+            (Frame::Boolean(a), Frame::Boolean(b)) => a == b,
+            (Frame::Bulk(a), Frame::Bulk(b)) => a == b,
+            (Frame::BulkError(a), Frame::BulkError(b)) => a == b,
+            (Frame::Double(a), Frame::Double(b)) => {
+                a.to_bits() == b.to_bits() || (a.is_nan() && b.is_nan())
+            }
+            (Frame::Error(a), Frame::Error(b)) => a == b,
+            (Frame::Integer(a), Frame::Integer(b)) => a == b,
+            (Frame::Map(a), Frame::Map(b)) => a == b,
+            (Frame::Null, Frame::Null) => true,
+            (Frame::Push(a), Frame::Push(b)) => a == b,
+            (Frame::Set(a), Frame::Set(b)) => a == b,
+            (Frame::Simple(a), Frame::Simple(b)) => a == b,
+            (
+                Frame::Verbatim { format, data },
+                Frame::Verbatim {
+                    format: other_format,
+                    data: other_data,
+                },
+            ) => format == other_format && data == other_data,
+            (
+                Frame::WithAttributes { attributes, value },
+                Frame::WithAttributes {
+                    attributes: other_attributes,
+                    value: other_value,
+                },
+            ) => attributes == other_attributes && value == other_value,
+            _ => false,
+        }
+    }
 }
 
 impl Eq for Frame {}
@@ -56,7 +160,8 @@ const BIGNUMBER_PREFIX: u8 = b'(';
 const BOOLEAN_PREFIX: u8 = b'#';
 const BULK_PREFIX: u8 = b'$';
 const BULKERROR_PREFIX: u8 = b'!';
-const DOUBLE_PREFIX: u8 = b'.';
+// RESP3 uses ',' for doubles, which keeps '.' free as the streamed aggregate terminator
+const DOUBLE_PREFIX: u8 = b',';
 const ERROR_PREFIX: u8 = b'-';
 const INTEGER_PREFIX: u8 = b':';
 const MAP_PREFIX: u8 = b'%';
@@ -66,12 +171,14 @@ const SET_PREFIX: u8 = b'~';
 const SIMPLE_PREFIX: u8 = b'+';
 const VERBATIM_PREFIX: u8 = b'=';
 const NEWLINE: [u8; 2] = [b'\r', b'\n'];
+const STREAMED_SIZE: &[u8] = b"?";
+const STREAMED_END: &[u8] = b".\r\n";
+const CHUNK_PREFIX: u8 = b';';
 
 impl Frame {
     pub fn prefix(&self) -> u8 {
         match self {
             Frame::Array(_) => ARRAY_PREFIX,
-            Frame::Attribute(_) => ATTRIBUTE_PREFIX,
             Frame::BigNumber(_) => BIGNUMBER_PREFIX,
             Frame::Boolean(_) => BOOLEAN_PREFIX,
             Frame::Bulk(_) => BULK_PREFIX,
@@ -84,9 +191,157 @@ impl Frame {
             Frame::Push(_) => PUSH_PREFIX,
             Frame::Set(_) => SET_PREFIX,
             Frame::Simple(_) => SIMPLE_PREFIX,
-            Frame::Verbatim(_, _) => VERBATIM_PREFIX,
+            Frame::Verbatim { .. } => VERBATIM_PREFIX,
+            Frame::WithAttributes { .. } => ATTRIBUTE_PREFIX,
+        }
+    }
+
+    // Serialize the frame in the wire format of the given protocol version.
+    // Types missing from RESP2 are downgraded to their closest RESP2 equivalent.
+    pub fn encode(&self, version: RespVersion) -> Bytes {
+        let mut buf = Vec::new();
+        serialize_frame(&mut buf, self, version);
+        Bytes::from(buf)
+    }
+
+    pub fn encode_to(&self, buf: &mut BytesMut, version: RespVersion) {
+        buf.extend_from_slice(&self.encode(version));
+    }
+
+    pub fn parse_with_limits(
+        buf: &mut Cursor<&[u8]>,
+        limits: &ParseLimits,
+    ) -> Result<Frame, FrameParsingError> {
+        let ctx = ParseContext {
+            limits: *limits,
+            source: None,
+            inline: false,
+        };
+        parse_with_context(buf, &ctx)
+    }
+
+    // Parse a request sent by a client, which can also be an inline command
+    // (space separated arguments on a single line, as typed in telnet)
+    pub fn parse_request(
+        buf: &mut Cursor<&[u8]>,
+        limits: &ParseLimits,
+    ) -> Result<Frame, FrameParsingError> {
+        let ctx = ParseContext {
+            limits: *limits,
+            source: None,
+            inline: true,
+        };
+        parse_with_context(buf, &ctx)
+    }
+
+    // Parse the `*<len>` header of an array, leaving the cursor on its first
+    // element, so the elements can be parsed one at a time with `parse_element`.
+    // The null array has no length, and no elements.
+    pub fn parse_array_len(buf: &mut Cursor<&[u8]>) -> Result<Option<usize>, FrameParsingError> {
+        let start = buf.position();
+        let result = read_u8(buf).and_then(|prefix| match prefix {
+            ARRAY_PREFIX => match read_line(buf)? {
+                STREAMED_SIZE => Err("streamed arrays have no declared length".into()),
+                line => match parse_line::<i64>(line)? {
+                    size if size >= 0 => Ok(Some(size as usize)),
+                    -1 => Ok(None),
+                    _ => Err("invalid array size".into()),
+                },
+            },
+            prefix => {
+                Err(format!("expected an array, found frame type '{}'", prefix as char).into())
+            }
+        });
+        if result.is_err() {
+            buf.set_position(start);
+        }
+        result
+    }
+
+    // Parse a single element of an array whose header was already parsed:
+    // the array counts towards the depth limit and inline commands aren't allowed
+    pub fn parse_element(
+        buf: &mut Cursor<&[u8]>,
+        limits: &ParseLimits,
+    ) -> Result<Frame, FrameParsingError> {
+        let ctx = ParseContext {
+            limits: *limits,
+            source: None,
+            inline: false,
+        };
+        parse_with_context(buf, &ctx.nested()?)
+    }
+
+    // Parse a frame out of a shared buffer: bulk payloads are slices of the
+    // buffer itself instead of copies
+    pub fn parse_bytes(buf: &mut Cursor<Bytes>) -> Result<Frame, FrameParsingError> {
+        let source = buf.get_ref().clone();
+        let mut cursor = Cursor::new(&source[..]);
+        cursor.set_position(buf.position());
+        let ctx = ParseContext {
+            limits: ParseLimits::default(),
+            source: Some(&source),
+            inline: false,
+        };
+        let result = parse_with_context(&mut cursor, &ctx);
+        buf.set_position(cursor.position());
+        result
+    }
+
+    // The `+OK` reply
+    pub fn ok() -> Frame {
+        Frame::simple("OK")
+    }
+
+    pub fn simple(s: &str) -> Frame {
+        Frame::Simple(s.to_string())
+    }
+
+    pub fn as_bulk_str(&self) -> Option<&str> {
+        match self {
+            Frame::Bulk(bytes) => str::from_utf8(bytes).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Frame::Integer(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_double(&self) -> Option<f64> {
+        match self {
+            Frame::Double(n) => Some(*n),
+            _ => None,
         }
     }
+
+    pub fn as_array(&self) -> Option<&[Frame]> {
+        match self {
+            Frame::Array(frames) => Some(frames),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Frame::Null)
+    }
+
+    fn unexpected(&self, expected: &'static str) -> FrameConversionError {
+        FrameConversionError::UnexpectedType {
+            expected,
+            found: self.prefix(),
+        }
+    }
+
+    // Parse a frame, also returning how many bytes of the buffer it took
+    pub fn parse_with_len(buf: &mut Cursor<&[u8]>) -> Result<(Frame, usize), FrameParsingError> {
+        let start = buf.position();
+        let frame = Self::parse(buf)?;
+        Ok((frame, (buf.position() - start) as usize))
+    }
 }
 
 impl Hash for Frame {
@@ -101,67 +356,208 @@ impl Hash for Frame {
             Frame::Double(data) => data.to_string().hash(state),
             Frame::Error(data) => data.hash(state),
             Frame::Integer(data) => data.hash(state),
+            Frame::Map(data) => data.hash(state),
             Frame::Null => "_\r\n".hash(state),
             Frame::Push(data) => data.hash(state),
+            Frame::Set(data) => data.hash(state),
             Frame::Simple(data) => data.hash(state),
-            Frame::Verbatim(encoding, data) => {
-                encoding.hash(state);
+            Frame::Verbatim { format, data } => {
+                format.hash(state);
                 data.hash(state);
             }
-            _ => panic!("Invalid frame type to be hashed"),
+            Frame::WithAttributes { attributes, value } => {
+                attributes.hash(state);
+                value.hash(state);
+            }
         }
     }
 }
 
-impl Message<Frame, FrameParsingError> for Frame {
-    fn parse(buf: &mut Cursor<&[u8]>) -> Result<Frame, FrameParsingError> {
-        match read_u8(buf)? {
-            SIMPLE_PREFIX => Ok(Frame::Simple(read_line_simple(buf)?)),
-            ERROR_PREFIX => Ok(Frame::Error(read_line_simple(buf)?)),
-            INTEGER_PREFIX => Ok(Frame::Integer(read_from_line(buf)?)),
-            DOUBLE_PREFIX => Ok(Frame::Double(read_from_line(buf)?)),
-            BULK_PREFIX => {
-                let size = read_from_line::<i32>(buf)?;
-                match size {
-                    num if num >= 0 => {
-                        let data = read_bytes(buf, size as usize)?;
-                        Ok(Frame::Bulk(data))
-                    }
-                    -1 => Ok(Frame::Null),
-                    _ => Err("invalid bulk string size".into()),
-                }
+impl TryFrom<Frame> for String {
+    type Error = FrameConversionError;
+
+    fn try_from(frame: Frame) -> Result<Self, Self::Error> {
+        match frame {
+            Frame::Simple(s) => Ok(s),
+            Frame::Bulk(bytes) => Ok(String::from_utf8(bytes.to_vec())?),
+            frame => Err(frame.unexpected("a string")),
+        }
+    }
+}
+
+impl TryFrom<Frame> for i64 {
+    type Error = FrameConversionError;
+
+    fn try_from(frame: Frame) -> Result<Self, Self::Error> {
+        match frame {
+            Frame::Integer(n) => Ok(n),
+            frame => Err(frame.unexpected("an integer")),
+        }
+    }
+}
+
+impl TryFrom<Frame> for f64 {
+    type Error = FrameConversionError;
+
+    fn try_from(frame: Frame) -> Result<Self, Self::Error> {
+        match frame {
+            Frame::Double(n) => Ok(n),
+            frame => Err(frame.unexpected("a double")),
+        }
+    }
+}
+
+impl TryFrom<Frame> for bool {
+    type Error = FrameConversionError;
+
+    fn try_from(frame: Frame) -> Result<Self, Self::Error> {
+        match frame {
+            Frame::Boolean(b) => Ok(b),
+            frame => Err(frame.unexpected("a boolean")),
+        }
+    }
+}
+
+impl TryFrom<Frame> for Bytes {
+    type Error = FrameConversionError;
+
+    fn try_from(frame: Frame) -> Result<Self, Self::Error> {
+        match frame {
+            Frame::Bulk(bytes) => Ok(bytes),
+            frame => Err(frame.unexpected("a bulk string")),
+        }
+    }
+}
+
+impl From<&str> for Frame {
+    fn from(value: &str) -> Self {
+        Frame::Bulk(Bytes::copy_from_slice(value.as_bytes()))
+    }
+}
+
+impl From<String> for Frame {
+    fn from(value: String) -> Self {
+        Frame::Bulk(Bytes::from(value))
+    }
+}
+
+impl From<i64> for Frame {
+    fn from(value: i64) -> Self {
+        Frame::Integer(value)
+    }
+}
+
+impl From<f64> for Frame {
+    fn from(value: f64) -> Self {
+        Frame::Double(value)
+    }
+}
+
+impl From<bool> for Frame {
+    fn from(value: bool) -> Self {
+        Frame::Boolean(value)
+    }
+}
+
+impl From<Vec<Frame>> for Frame {
+    fn from(value: Vec<Frame>) -> Self {
+        Frame::Array(value)
+    }
+}
+
+// Human readable rendering, following the way redis-cli prints replies
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Frame::Array(frames) | Frame::Push(frames) => {
+                render_list(f, frames.iter().map(|v| (v, None)), ")", "(empty array)")
             }
-            NULL_PREFIX => Ok(Frame::Null),
-            ARRAY_PREFIX => Ok(Frame::Array(read_array(buf)?)),
-            BOOLEAN_PREFIX => match read_u8(buf) {
-                Ok(b't') => Ok(Frame::Boolean(true)),
-                Ok(b'f') => Ok(Frame::Boolean(false)),
-                Ok(_) => Err("invalid character for boolean".into()),
-                Err(_) => Err(FrameParsingError::Incomplete),
+            Frame::BigNumber(n) => write!(f, "(big number) {}", n),
+            Frame::Boolean(b) => write!(f, "({})", b),
+            Frame::Bulk(bytes) => write!(f, "{}", quote(bytes)),
+            Frame::BulkError(bytes) => write!(f, "(error) {}", String::from_utf8_lossy(bytes)),
+            Frame::Double(n) => match *n {
+                f64::INFINITY => write!(f, "(double) inf"),
+                f64::NEG_INFINITY => write!(f, "(double) -inf"),
+                n => write!(f, "(double) {}", n),
             },
-            BIGNUMBER_PREFIX => Ok(Frame::BigNumber(read_line_simple(buf)?)),
-            BULKERROR_PREFIX => {
-                let size = read_from_line::<u32>(buf)?;
-                let data = read_bytes(buf, size as usize)?;
-                Ok(Frame::BulkError(String::from_utf8(data.to_vec())?))
+            Frame::Error(message) => write!(f, "(error) {}", message),
+            Frame::Integer(n) => write!(f, "(integer) {}", n),
+            Frame::Map(pairs) => render_list(
+                f,
+                pairs.iter().map(|(k, v)| (k, Some(v))),
+                "#",
+                "(empty hash)",
+            ),
+            Frame::Null => write!(f, "(nil)"),
+            Frame::Set(frames) => {
+                render_list(f, frames.iter().map(|v| (v, None)), "~", "(empty set)")
             }
-            VERBATIM_PREFIX => {
-                let size = read_from_line::<u32>(buf)?;
-                let data = read_bytes(buf, (size + 4) as usize)?;
-                if data[3] != b':' {
-                    return Err("Missing ':' character as 4th byte".into());
-                }
-                let encoding: VerbatimEncoding = data[..3].try_into()?;
-                let content = str::from_utf8(&data[4..])?.to_owned();
-                Ok(Frame::Verbatim(encoding, content))
+            Frame::Simple(s) => write!(f, "{}", s),
+            Frame::Verbatim { data, .. } => write!(f, "{}", String::from_utf8_lossy(data)),
+            // redis-cli doesn't show attributes, only the reply they describe
+            Frame::WithAttributes { value, .. } => value.fmt(f),
+        }
+    }
+}
+
+// Numbered list of elements (or key/value pairs), right-aligning the indexes
+// and indenting the continuation lines of nested aggregates under their element
+fn render_list<'a>(
+    f: &mut fmt::Formatter<'_>,
+    items: impl ExactSizeIterator<Item = (&'a Frame, Option<&'a Frame>)>,
+    marker: &str,
+    empty: &str,
+) -> fmt::Result {
+    let len = items.len();
+    if len == 0 {
+        return write!(f, "{}", empty);
+    }
+    let width = len.to_string().len();
+    for (i, (item, value)) in items.enumerate() {
+        if i > 0 {
+            writeln!(f)?;
+        }
+        let prefix = format!("{:>width$}{} ", i + 1, marker);
+        let mut rendered = item.to_string();
+        if let Some(value) = value {
+            rendered = format!("{} => {}", rendered, value);
+        }
+        let indent = " ".repeat(prefix.len());
+        for (n, line) in rendered.lines().enumerate() {
+            if n == 0 {
+                write!(f, "{}{}", prefix, line)?;
+            } else {
+                write!(f, "\n{}{}", indent, line)?;
             }
-            MAP_PREFIX => Ok(Frame::Map(read_map(buf)?)),
-            ATTRIBUTE_PREFIX => Ok(Frame::Attribute(read_map(buf)?)),
-            SET_PREFIX => Ok(Frame::Set(HashSet::from_iter(read_array(buf)?))),
-            PUSH_PREFIX => Ok(Frame::Push(read_array(buf)?)),
-            _ => todo!("Implement error handling"),
         }
     }
+    Ok(())
+}
+
+// Double quoted string, escaping quotes and non printable bytes
+fn quote(bytes: &[u8]) -> String {
+    let mut quoted = String::with_capacity(bytes.len() + 2);
+    quoted.push('"');
+    for &b in bytes {
+        match b {
+            b'\\' => quoted.push_str("\\\\"),
+            b'"' => quoted.push_str("\\\""),
+            b'\n' => quoted.push_str("\\n"),
+            b'\r' => quoted.push_str("\\r"),
+            b'\t' => quoted.push_str("\\t"),
+            b' '..=b'~' => quoted.push(b as char),
+            b => quoted.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+impl Message<Frame, FrameParsingError> for Frame {
+    fn parse(buf: &mut Cursor<&[u8]>) -> Result<Frame, FrameParsingError> {
+        Self::parse_with_limits(buf, &ParseLimits::default())
+    }
 
     fn check(cursor: &mut Cursor<&[u8]>) -> bool {
         // TODO improve length check method
@@ -174,85 +570,271 @@ impl Message<Frame, FrameParsingError> for Frame {
 
     fn serialize(&self) -> Vec<u8> {
         let mut buf = Vec::new();
-        match self {
-            Frame::Array(frames) => {
-                serialize_array(&mut buf, ARRAY_PREFIX, frames);
-            }
-            Frame::Attribute(hash_map) => serialize_map(&mut buf, ATTRIBUTE_PREFIX, hash_map),
-            Frame::BigNumber(n) => serialize_simple_string(&mut buf, BIGNUMBER_PREFIX, n),
-            Frame::Boolean(v) => {
-                let value = if *v { "t" } else { "f" };
-                serialize_simple_string(&mut buf, BOOLEAN_PREFIX, value)
-            }
-            Frame::Bulk(bytes) => {
-                buf.push(BULK_PREFIX);
-                buf.extend_from_slice(bytes.len().to_string().as_bytes());
-                buf.extend_from_slice(&NEWLINE);
-                buf.extend_from_slice(bytes);
-                buf.extend_from_slice(&NEWLINE);
+        serialize_frame(&mut buf, self, RespVersion::V3);
+        buf
+    }
+}
+
+// State carried along while parsing a frame and its children
+#[derive(Clone, Copy)]
+struct ParseContext<'a> {
+    limits: ParseLimits,
+    // Buffer being parsed, when bulk payloads can be sliced out of it
+    source: Option<&'a Bytes>,
+    // Whether an unknown type byte starts an inline command, which is only
+    // the case for top level frames
+    inline: bool,
+}
+
+impl ParseContext<'_> {
+    fn bytes(&self, data: &[u8]) -> Bytes {
+        match self.source {
+            Some(source) => source.slice_ref(data),
+            None => Bytes::copy_from_slice(data),
+        }
+    }
+
+    fn nested(&self) -> Result<Self, FrameParsingError> {
+        Ok(Self {
+            limits: self.limits.nested()?,
+            inline: false,
+            ..*self
+        })
+    }
+}
+
+// On Incomplete the cursor is rewound to where the frame started, so parsing
+// can be retried from the same position once more data is available.
+// Other errors are tagged with the offset of the innermost frame that failed.
+fn parse_with_context(
+    buf: &mut Cursor<&[u8]>,
+    ctx: &ParseContext,
+) -> Result<Frame, FrameParsingError> {
+    let start = buf.position();
+    parse_frame(buf, ctx).map_err(|error| match error {
+        FrameParsingError::Incomplete => {
+            buf.set_position(start);
+            error
+        }
+        FrameParsingError::At { .. } => error,
+        error => FrameParsingError::At {
+            offset: start as usize,
+            source: Box::new(error),
+        },
+    })
+}
+
+// Parse a single frame, leaving the cursor wherever parsing stopped
+fn parse_frame(buf: &mut Cursor<&[u8]>, ctx: &ParseContext) -> Result<Frame, FrameParsingError> {
+    match read_u8(buf)? {
+        SIMPLE_PREFIX => Ok(Frame::Simple(read_line_simple(buf)?)),
+        ERROR_PREFIX => Ok(Frame::Error(read_line_simple(buf)?)),
+        INTEGER_PREFIX => Ok(Frame::Integer(read_from_line(buf)?)),
+        DOUBLE_PREFIX => Ok(Frame::Double(read_double(buf)?)),
+        BULK_PREFIX => match read_line(buf)? {
+            STREAMED_SIZE => Ok(Frame::Bulk(read_streamed_bulk(buf, ctx)?)),
+            line => match parse_line::<i32>(line)? {
+                size if size >= 0 => {
+                    let data = read_bytes(buf, ctx.limits.check_bulk_len(size as usize)?)?;
+                    Ok(Frame::Bulk(ctx.bytes(data)))
+                }
+                -1 => Ok(Frame::Null),
+                _ => Err("invalid bulk string size".into()),
+            },
+        },
+        NULL_PREFIX => match read_line(buf)? {
+            b"" => Ok(Frame::Null),
+            _ => Err("invalid null".into()),
+        },
+        ARRAY_PREFIX => match read_line(buf)? {
+            STREAMED_SIZE => Ok(Frame::Array(read_streamed_elements(buf, ctx)?)),
+            line => match parse_line::<i64>(line)? {
+                size if size >= 0 => Ok(Frame::Array(read_elements(buf, size as usize, ctx)?)),
+                -1 => Ok(Frame::Null),
+                _ => Err("invalid array size".into()),
+            },
+        },
+        BOOLEAN_PREFIX => match read_line(buf)? {
+            b"t" => Ok(Frame::Boolean(true)),
+            b"f" => Ok(Frame::Boolean(false)),
+            _ => Err("invalid character for boolean".into()),
+        },
+        BIGNUMBER_PREFIX => Ok(Frame::BigNumber(read_from_line(buf)?)),
+        BULKERROR_PREFIX => {
+            let size = read_from_line::<i32>(buf)?;
+            if size < 0 {
+                return Err("invalid bulk error size".into());
             }
-            Frame::BulkError(s) => {
-                let bytes = s.as_bytes();
-                buf.push(BULKERROR_PREFIX);
-                buf.extend_from_slice(bytes.len().to_string().as_bytes());
-                buf.extend_from_slice(&NEWLINE);
-                buf.extend_from_slice(bytes);
-                buf.extend_from_slice(&NEWLINE);
+            let data = read_bytes(buf, ctx.limits.check_bulk_len(size as usize)?)?;
+            Ok(Frame::BulkError(ctx.bytes(data)))
+        }
+        VERBATIM_PREFIX => {
+            let size = read_from_line::<u32>(buf)?;
+            let data = read_bytes(buf, ctx.limits.check_bulk_len(size as usize)?)?;
+            if data.len() < 4 {
+                return Err("verbatim string must contain the format prefix".into());
             }
-            Frame::Double(n) => {
-                serialize_simple_string(&mut buf, DOUBLE_PREFIX, &format!("{:+}", n))
+            if data[3] != b':' {
+                return Err("Missing ':' character as 4th byte".into());
             }
-            Frame::Error(s) => serialize_simple_string(&mut buf, ERROR_PREFIX, s),
-            Frame::Integer(n) => {
-                serialize_simple_string(&mut buf, INTEGER_PREFIX, &format!("{:+}", n))
+            Ok(Frame::Verbatim {
+                format: data[..3].try_into()?,
+                data: ctx.bytes(&data[4..]),
+            })
+        }
+        MAP_PREFIX => Ok(Frame::Map(read_map(buf, ctx)?)),
+        ATTRIBUTE_PREFIX => {
+            let attributes = read_map(buf, ctx)?;
+            // The value counts as nested, or a chain of attributes could
+            // recurse without bound
+            let value = Box::new(parse_with_context(buf, &ctx.nested()?)?);
+            Ok(Frame::WithAttributes { attributes, value })
+        }
+        SET_PREFIX => Ok(Frame::Set(read_array(buf, ctx)?)),
+        PUSH_PREFIX => Ok(Frame::Push(read_array(buf, ctx)?)),
+        _ if ctx.inline => {
+            buf.set_position(buf.position() - 1);
+            Ok(Frame::Array(read_inline(buf, &ctx.limits)?))
+        }
+        prefix => Err(FrameParsingError::UnknownType(prefix)),
+    }
+}
+
+// Inline command, as typed in telnet: space separated arguments on a single
+// line. Arguments can be double quoted to contain spaces, with `\"`, `\\`,
+// `\n`, `\r` and `\t` escapes inside the quotes.
+// The line is bounded by the bulk length limit, so that a client can't make
+// the server buffer an endless line
+fn read_inline(
+    buf: &mut Cursor<&[u8]>,
+    limits: &ParseLimits,
+) -> Result<Vec<Frame>, FrameParsingError> {
+    let start = buf.position() as usize;
+    let line = match read_line_ending(buf, LineEnding::Lenient) {
+        Err(FrameParsingError::Incomplete) => {
+            limits.check_bulk_len(buf.get_ref().len() - start)?;
+            return Err(FrameParsingError::Incomplete);
+        }
+        result => result?,
+    };
+    limits.check_bulk_len(line.len())?;
+    let mut args = Vec::new();
+    let mut chars = line.iter().copied().peekable();
+    loop {
+        while chars.next_if(u8::is_ascii_whitespace).is_some() {}
+        let Some(first) = chars.next() else {
+            return Ok(args);
+        };
+        let mut arg = Vec::new();
+        if first == b'"' {
+            loop {
+                match chars.next() {
+                    Some(b'"') => break,
+                    Some(b'\\') => match chars.next() {
+                        Some(b'n') => arg.push(b'\n'),
+                        Some(b'r') => arg.push(b'\r'),
+                        Some(b't') => arg.push(b'\t'),
+                        Some(c) => arg.push(c),
+                        None => return Err("unbalanced quotes in inline command".into()),
+                    },
+                    Some(c) => arg.push(c),
+                    None => return Err("unbalanced quotes in inline command".into()),
+                }
             }
-            Frame::Map(hash_map) => serialize_map(&mut buf, MAP_PREFIX, hash_map),
-            Frame::Null => {
-                buf.push(NULL_PREFIX);
-                buf.extend_from_slice(&NEWLINE);
+            if chars.peek().is_some_and(|c| !c.is_ascii_whitespace()) {
+                return Err("closing quote must be followed by a space".into());
             }
-            Frame::Push(frames) => serialize_array(&mut buf, PUSH_PREFIX, frames),
-            Frame::Set(hash_set) => serialize_set(&mut buf, SET_PREFIX, hash_set),
-            Frame::Simple(s) => serialize_simple_string(&mut buf, SIMPLE_PREFIX, s),
-            Frame::Verbatim(enc, s) => {
-                let bytes = s.as_bytes();
-                let enc = match enc {
-                    VerbatimEncoding::Text => b"txt",
-                    VerbatimEncoding::Markdown => b"mkd",
-                    VerbatimEncoding::Other(value) => value,
-                };
-                buf.push(VERBATIM_PREFIX);
-                buf.extend_from_slice(bytes.len().to_string().as_bytes());
-                buf.extend_from_slice(&NEWLINE);
-                buf.extend_from_slice(enc);
-                buf.push(b':');
-                buf.extend_from_slice(bytes);
-                buf.extend_from_slice(&NEWLINE);
+        } else {
+            arg.push(first);
+            while let Some(c) = chars.next_if(|c| !c.is_ascii_whitespace()) {
+                arg.push(c);
             }
         }
-        buf
+        args.push(Frame::Bulk(Bytes::from(arg)));
     }
 }
 
-fn read_array(buf: &mut Cursor<&[u8]>) -> Result<Vec<Frame>, FrameParsingError> {
-    let size = read_from_line::<u32>(buf)? as usize;
-    let mut array = Vec::with_capacity(size);
+fn read_array(
+    buf: &mut Cursor<&[u8]>,
+    ctx: &ParseContext,
+) -> Result<Vec<Frame>, FrameParsingError> {
+    match read_line(buf)? {
+        STREAMED_SIZE => read_streamed_elements(buf, ctx),
+        line => read_elements(buf, parse_line::<u32>(line)? as usize, ctx),
+    }
+}
+
+fn read_streamed_elements(
+    buf: &mut Cursor<&[u8]>,
+    ctx: &ParseContext,
+) -> Result<Vec<Frame>, FrameParsingError> {
+    let ctx = &ctx.nested()?;
+    let mut array = Vec::new();
+    while !read_streamed_end(buf)? {
+        array.push(parse_with_context(buf, ctx)?);
+    }
+    Ok(array)
+}
+
+fn read_elements(
+    buf: &mut Cursor<&[u8]>,
+    size: usize,
+    ctx: &ParseContext,
+) -> Result<Vec<Frame>, FrameParsingError> {
+    let ctx = &ctx.nested()?;
+    let mut array = Vec::with_capacity(capacity(buf, size));
     for _ in 0..size {
-        let frame = Frame::parse(buf)?;
+        let frame = parse_with_context(buf, ctx)?;
         array.push(frame);
     }
     Ok(array)
 }
 
-fn read_map(buf: &mut Cursor<&[u8]>) -> Result<HashMap<Frame, Frame>, FrameParsingError> {
-    let size = read_from_line(buf)?;
-    let mut array = HashMap::with_capacity(size);
+fn read_map(
+    buf: &mut Cursor<&[u8]>,
+    ctx: &ParseContext,
+) -> Result<Vec<(Frame, Frame)>, FrameParsingError> {
+    let ctx = &ctx.nested()?;
+    let size = match read_line(buf)? {
+        STREAMED_SIZE => {
+            let mut pairs = Vec::new();
+            while !read_streamed_end(buf)? {
+                let key = parse_with_context(buf, ctx)?;
+                let value = parse_with_context(buf, ctx)?;
+                pairs.push((key, value));
+            }
+            return Ok(pairs);
+        }
+        line => parse_line::<usize>(line)?,
+    };
+    let mut pairs = Vec::with_capacity(capacity(buf, size));
     for _ in 0..size {
-        let key = Frame::parse(buf)?;
-        let value = Frame::parse(buf)?;
-        array.insert(key, value);
+        let key = parse_with_context(buf, ctx)?;
+        let value = parse_with_context(buf, ctx)?;
+        pairs.push((key, value));
     }
-    Ok(array)
+    Ok(pairs)
+}
+
+// Sizes come from the peer, so don't reserve room for more elements than
+// the rest of the buffer could hold: the shortest frame (`_\r\n`) takes 3 bytes
+fn capacity(buf: &Cursor<&[u8]>, size: usize) -> usize {
+    let remaining = buf.get_ref().len().saturating_sub(buf.position() as usize);
+    size.min(remaining / 3)
+}
+
+// Consumes the `.` terminator of a streamed aggregate, if it's the next element.
+fn read_streamed_end(buf: &mut Cursor<&[u8]>) -> Result<bool, FrameParsingError> {
+    let start = buf.position() as usize;
+    let rest = &buf.get_ref()[start..];
+    if rest.starts_with(STREAMED_END) {
+        buf.set_position((start + STREAMED_END.len()) as u64);
+        return Ok(true);
+    }
+    if STREAMED_END.starts_with(rest) {
+        return Err(FrameParsingError::Incomplete);
+    }
+    Ok(false)
 }
 
 fn read_u8(buf: &mut Cursor<&[u8]>) -> Result<u8, FrameParsingError> {
@@ -263,46 +845,203 @@ fn read_u8(buf: &mut Cursor<&[u8]>) -> Result<u8, FrameParsingError> {
     }
 }
 
+// How a line has to be terminated: RESP frames always end lines with \r\n,
+// while inline commands typed by hand may end with a bare \n
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum LineEnding {
+    Strict,
+    Lenient,
+}
+
 // Read the first line (ending with \r\n) from the buffer
 fn read_line<'a>(buf: &mut Cursor<&'a [u8]>) -> Result<&'a [u8], FrameParsingError> {
-    let start = buf.position() as usize;
-    let end = buf.get_ref().len() - 1;
+    read_line_ending(buf, LineEnding::Strict)
+}
 
-    for i in start..end {
-        if buf.get_ref()[i] == b'\r' && buf.get_ref()[i + 1] == b'\n' {
-            // "Consumes the line"
-            buf.set_position((i + 2) as u64);
-            return Ok(&buf.get_ref()[start..i]);
+fn read_line_ending<'a>(
+    buf: &mut Cursor<&'a [u8]>,
+    ending: LineEnding,
+) -> Result<&'a [u8], FrameParsingError> {
+    let start = buf.position() as usize;
+    let data: &'a [u8] = buf.get_ref();
+    let remaining = data.get(start..).unwrap_or_default();
+    let Some(i) = memchr(b'\n', remaining) else {
+        return Err(FrameParsingError::Incomplete);
+    };
+    let line = match remaining[..i].strip_suffix(b"\r") {
+        Some(line) => line,
+        None if ending == LineEnding::Lenient => &remaining[..i],
+        None => {
+            return Err(FrameParsingError::Protocol(format!(
+                "line ending at byte {} is a bare '\\n' instead of '\\r\\n'",
+                start + i
+            )))
         }
-    }
-    Err(FrameParsingError::Incomplete)
+    };
+    // "Consumes the line"
+    buf.set_position((start + i + 1) as u64);
+    Ok(line)
 }
 
 fn read_line_simple(buf: &mut Cursor<&[u8]>) -> Result<String, FrameParsingError> {
-    let line = read_line(buf)?.to_vec();
-    Ok(String::from_utf8(line)?)
+    Ok(str::from_utf8(read_line(buf)?)?.to_owned())
 }
 
 fn read_from_line<T>(buf: &mut Cursor<&[u8]>) -> Result<T, FrameParsingError>
 where
     T: FromStr,
-    T::Err: Into<FrameParsingError>,
+    T::Err: InvalidToken,
 {
-    let line = read_line(buf)?.to_vec();
-    let value = String::from_utf8(line)?.parse().map_err(Into::into)?;
-    Ok(value)
+    parse_line(read_line(buf)?)
 }
 
-fn read_bytes(buf: &mut Cursor<&[u8]>, size: usize) -> Result<Bytes, FrameParsingError> {
+fn parse_line<T>(line: &[u8]) -> Result<T, FrameParsingError>
+where
+    T: FromStr,
+    T::Err: InvalidToken,
+{
+    let line = str::from_utf8(line)?;
+    line.parse().map_err(|_| T::Err::invalid_token(line))
+}
+
+// Read the `;<len>` chunks of a streamed bulk string, up to the `;0` terminator
+fn read_streamed_bulk(
+    buf: &mut Cursor<&[u8]>,
+    ctx: &ParseContext,
+) -> Result<Bytes, FrameParsingError> {
+    let mut data = BytesMut::new();
+    loop {
+        if read_u8(buf)? != CHUNK_PREFIX {
+            return Err("invalid streamed bulk string chunk".into());
+        }
+        let size = read_from_line::<u32>(buf)? as usize;
+        if size == 0 {
+            return Ok(data.freeze());
+        }
+        ctx.limits.check_bulk_len(data.len() + size)?;
+        data.extend_from_slice(read_bytes(buf, size)?);
+    }
+}
+
+// Only the RESP3 spellings of the special values are accepted, not the ones Rust's
+// f64 parser also understands (e.g. "infinity" or "NaN")
+fn read_double(buf: &mut Cursor<&[u8]>) -> Result<f64, FrameParsingError> {
+    match read_line(buf)? {
+        b"inf" => Ok(f64::INFINITY),
+        b"-inf" => Ok(f64::NEG_INFINITY),
+        b"nan" => Ok(f64::NAN),
+        line => match parse_line::<f64>(line)? {
+            value if value.is_finite() => Ok(value),
+            _ => Err(FrameParsingError::InvalidDouble(
+                String::from_utf8_lossy(line).into_owned(),
+            )),
+        },
+    }
+}
+
+// Read a payload of `size` bytes followed by "\r\n". A terminator which is
+// already known to be something else is an error, without waiting for the
+// rest of it.
+fn read_bytes<'a>(buf: &mut Cursor<&'a [u8]>, size: usize) -> Result<&'a [u8], FrameParsingError> {
     let start = buf.position() as usize;
     let end = start + size;
-    let len = buf.get_ref().len();
-    if len < end + 2 {
+    let data: &'a [u8] = buf.get_ref();
+    let terminator = data.get(end..).unwrap_or_default();
+    if !NEWLINE.starts_with(&terminator[..terminator.len().min(2)]) {
+        return Err(FrameParsingError::Protocol(format!(
+            "payload ending at byte {} isn't followed by '\\r\\n'",
+            end
+        )));
+    }
+    if terminator.len() < 2 {
         return Err(FrameParsingError::Incomplete);
     }
-    let data = Bytes::copy_from_slice(&buf.get_ref()[start..(start + size)]);
     buf.set_position((end + 2) as u64);
-    Ok(data)
+    Ok(&data[start..end])
+}
+
+fn serialize_frame(buf: &mut Vec<u8>, frame: &Frame, version: RespVersion) {
+    match (frame, version) {
+        (Frame::Array(frames), _) => serialize_array(buf, ARRAY_PREFIX, frames, version),
+        (Frame::BigNumber(n), RespVersion::V2) => {
+            serialize_bulk(buf, BULK_PREFIX, n.to_string().as_bytes())
+        }
+        (Frame::BigNumber(n), RespVersion::V3) => {
+            serialize_simple_string(buf, BIGNUMBER_PREFIX, &n.to_string())
+        }
+        (Frame::Boolean(v), RespVersion::V2) => {
+            let value = if *v { "1" } else { "0" };
+            serialize_simple_string(buf, INTEGER_PREFIX, value)
+        }
+        (Frame::Boolean(v), RespVersion::V3) => {
+            let value = if *v { "t" } else { "f" };
+            serialize_simple_string(buf, BOOLEAN_PREFIX, value)
+        }
+        (Frame::Bulk(bytes), _) => serialize_bulk(buf, BULK_PREFIX, bytes),
+        (Frame::BulkError(bytes), RespVersion::V2) => {
+            serialize_line(buf, ERROR_PREFIX, &String::from_utf8_lossy(bytes))
+        }
+        (Frame::BulkError(bytes), RespVersion::V3) => serialize_bulk(buf, BULKERROR_PREFIX, bytes),
+        (Frame::Double(n), _) => {
+            let value = match *n {
+                f64::INFINITY => "inf".to_string(),
+                f64::NEG_INFINITY => "-inf".to_string(),
+                n if n.is_nan() => "nan".to_string(),
+                n => n.to_string(),
+            };
+            match version {
+                RespVersion::V2 => serialize_bulk(buf, BULK_PREFIX, value.as_bytes()),
+                RespVersion::V3 => serialize_simple_string(buf, DOUBLE_PREFIX, &value),
+            }
+        }
+        (Frame::Error(s), _) => serialize_line(buf, ERROR_PREFIX, s),
+        (Frame::Integer(n), _) => serialize_simple_string(buf, INTEGER_PREFIX, &n.to_string()),
+        (Frame::Map(pairs), RespVersion::V2) => {
+            serialize_length(buf, ARRAY_PREFIX, pairs.len() * 2);
+            for (k, v) in pairs {
+                serialize_frame(buf, k, version);
+                serialize_frame(buf, v, version);
+            }
+        }
+        (Frame::Map(pairs), RespVersion::V3) => serialize_map(buf, MAP_PREFIX, pairs, version),
+        (Frame::Null, RespVersion::V2) => buf.extend_from_slice(b"$-1\r\n"),
+        (Frame::Null, RespVersion::V3) => {
+            buf.push(NULL_PREFIX);
+            buf.extend_from_slice(&NEWLINE);
+        }
+        (Frame::Push(frames), RespVersion::V2) => {
+            serialize_array(buf, ARRAY_PREFIX, frames, version)
+        }
+        (Frame::Push(frames), RespVersion::V3) => {
+            serialize_array(buf, PUSH_PREFIX, frames, version)
+        }
+        (Frame::Set(frames), RespVersion::V2) => {
+            serialize_array(buf, ARRAY_PREFIX, frames, version)
+        }
+        (Frame::Set(frames), RespVersion::V3) => serialize_array(buf, SET_PREFIX, frames, version),
+        (Frame::Simple(s), _) => serialize_line(buf, SIMPLE_PREFIX, s),
+        (Frame::Verbatim { data, .. }, RespVersion::V2) => serialize_bulk(buf, BULK_PREFIX, data),
+        (Frame::Verbatim { format, data }, RespVersion::V3) => {
+            let enc = match format {
+                VerbatimEncoding::Text => b"txt",
+                VerbatimEncoding::Markdown => b"mkd",
+                VerbatimEncoding::Other(value) => value,
+            };
+            serialize_length(buf, VERBATIM_PREFIX, data.len() + 4);
+            buf.extend_from_slice(enc);
+            buf.push(b':');
+            buf.extend_from_slice(data);
+            buf.extend_from_slice(&NEWLINE);
+        }
+        // RESP2 has no attributes, so they're dropped and only the value is sent
+        (Frame::WithAttributes { value, .. }, RespVersion::V2) => {
+            serialize_frame(buf, value, version)
+        }
+        (Frame::WithAttributes { attributes, value }, RespVersion::V3) => {
+            serialize_map(buf, ATTRIBUTE_PREFIX, attributes, version);
+            serialize_frame(buf, value, version);
+        }
+    }
 }
 
 fn serialize_simple_string(buf: &mut Vec<u8>, prefix: u8, content: &str) {
@@ -311,45 +1050,52 @@ fn serialize_simple_string(buf: &mut Vec<u8>, prefix: u8, content: &str) {
     buf.extend_from_slice(&NEWLINE);
 }
 
-fn serialize_array(buf: &mut Vec<u8>, prefix: u8, frames: &Vec<Frame>) {
-    buf.push(prefix);
-    buf.extend_from_slice(frames.len().to_string().as_bytes());
-    buf.extend_from_slice(&NEWLINE);
-    for v in frames {
-        buf.extend_from_slice(&v.serialize());
+// Simple strings and errors can't span multiple lines, and text which comes
+// from the client mustn't end the line early and inject a reply of its own
+fn serialize_line(buf: &mut Vec<u8>, prefix: u8, content: &str) {
+    if content.contains(['\r', '\n']) {
+        serialize_simple_string(buf, prefix, &content.replace(['\r', '\n'], " "));
+    } else {
+        serialize_simple_string(buf, prefix, content);
     }
 }
 
-fn serialize_set(buf: &mut Vec<u8>, prefix: u8, frames: &HashSet<Frame>) {
+fn serialize_length(buf: &mut Vec<u8>, prefix: u8, len: usize) {
     buf.push(prefix);
-    buf.extend_from_slice(frames.len().to_string().as_bytes());
+    buf.extend_from_slice(len.to_string().as_bytes());
     buf.extend_from_slice(&NEWLINE);
+}
+
+fn serialize_bulk(buf: &mut Vec<u8>, prefix: u8, bytes: &[u8]) {
+    serialize_length(buf, prefix, bytes.len());
+    buf.extend_from_slice(bytes);
+    buf.extend_from_slice(&NEWLINE);
+}
+
+fn serialize_array(buf: &mut Vec<u8>, prefix: u8, frames: &[Frame], version: RespVersion) {
+    serialize_length(buf, prefix, frames.len());
     for v in frames {
-        buf.extend_from_slice(&v.serialize());
+        serialize_frame(buf, v, version);
     }
 }
 
-fn serialize_map(buf: &mut Vec<u8>, prefix: u8, hash_map: &HashMap<Frame, Frame>) {
-    buf.push(prefix);
-    buf.extend_from_slice(hash_map.len().to_string().as_bytes());
-    buf.extend_from_slice(&NEWLINE);
-    for (k, v) in hash_map {
-        buf.extend_from_slice(&k.serialize());
-        buf.extend_from_slice(&v.serialize());
+fn serialize_map(buf: &mut Vec<u8>, prefix: u8, pairs: &[(Frame, Frame)], version: RespVersion) {
+    serialize_length(buf, prefix, pairs.len());
+    for (k, v) in pairs {
+        serialize_frame(buf, k, version);
+        serialize_frame(buf, v, version);
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Frame;
+    use super::{read_line, read_line_ending, Frame, LineEnding, ParseLimits, RespVersion};
     use crate::resp::connection::Message;
-    use crate::resp::error::FrameParsingError;
+    use crate::resp::error::{FrameConversionError, FrameParsingError};
     use crate::resp::types::VerbatimEncoding;
+    use bytes::{Bytes, BytesMut};
     use rstest::rstest;
-    use std::{
-        collections::{HashMap, HashSet},
-        io::Cursor,
-    };
+    use std::io::Cursor;
 
     #[rstest]
     #[case("+OK\r\n", Frame::Simple("OK".to_string()))]
@@ -363,15 +1109,43 @@ mod tests {
     #[case("_\r\n", Frame::Null)]
     #[case("$-1\r\n", Frame::Null)]
     #[case("$0\r\n\r\n", Frame::Bulk("".into()))]
+    #[case("$?\r\n;4\r\nHell\r\n;6\r\no worl\r\n;1\r\nd\r\n;0\r\n", Frame::Bulk("Hello world".into()))]
+    #[case("$?\r\n;0\r\n", Frame::Bulk("".into()))]
     #[case("*0\r\n", Frame::Array(vec![]))]
     #[case("*2\r\n$5\r\nhello\r\n$5\r\nworld\r\n", Frame::Array(vec![Frame::Bulk("hello".into()), Frame::Bulk("world".into())]))]
     #[case("*3\r\n:1\r\n:2\r\n:3\r\n", Frame::Array(vec![Frame::Integer(1), Frame::Integer(2), Frame::Integer(3)]))]
+    #[case("*2\r\n$3\r\nfoo\r\n$3\r\nbar\r\n", Frame::Array(vec![Frame::Bulk("foo".into()), Frame::Bulk("bar".into())]))]
+    #[case("*2\r\n*1\r\n:1\r\n*0\r\n", Frame::Array(vec![Frame::Array(vec![Frame::Integer(1)]), Frame::Array(vec![])]))]
+    #[case("*-1\r\n", Frame::Null)]
+    #[case("*2\r\n*-1\r\n*0\r\n", Frame::Array(vec![Frame::Null, Frame::Array(vec![])]))]
+    #[case("*2\r\n_\r\n:1\r\n", Frame::Array(vec![Frame::Null, Frame::Integer(1)]))]
+    #[case("#t\r\n", Frame::Boolean(true))]
     #[case("#f\r\n", Frame::Boolean(false))]
     #[case("!30\r\nERROR This is an error message\r\n", Frame::BulkError("ERROR This is an error message".into()))]
-    #[case("=19\r\ntxt:Hello from verbatim\r\n", Frame::Verbatim(VerbatimEncoding::Text, "Hello from verbatim".into()))]
-    #[case("%2\r\n+first\r\n:1\r\n+second\r\n:2\r\n", Frame::Map(HashMap::from([(Frame::Simple("first".into()), Frame::Integer(1)),(Frame::Simple("second".into()), Frame::Integer(2))])))]
-    #[case("|1\r\n+third\r\n:3\r\n", Frame::Attribute(HashMap::from([(Frame::Simple("third".into()), Frame::Integer(3))])))]
-    #[case("~3\r\n:1\r\n:2\r\n:3\r\n", Frame::Set(HashSet::from([Frame::Integer(1), Frame::Integer(2), Frame::Integer(3)])))]
+    #[case("!21\r\nSYNTAX invalid syntax\r\n", Frame::BulkError("SYNTAX invalid syntax".into()))]
+    #[case("!11\r\nmulti\r\nline\r\n", Frame::BulkError("multi\r\nline".into()))]
+    #[case("=23\r\ntxt:Hello from verbatim\r\n", Frame::Verbatim { format: VerbatimEncoding::Text, data: "Hello from verbatim".into() })]
+    #[case("=15\r\ntxt:Some string\r\n", Frame::Verbatim { format: VerbatimEncoding::Text, data: "Some string".into() })]
+    #[case("=11\r\nmkd:# Title\r\n", Frame::Verbatim { format: VerbatimEncoding::Markdown, data: "# Title".into() })]
+    #[case("=4\r\nabc:\r\n", Frame::Verbatim { format: VerbatimEncoding::Other(*b"abc"), data: "".into() })]
+    #[case("%2\r\n+first\r\n:1\r\n+second\r\n:2\r\n", Frame::Map(vec![(Frame::Simple("first".into()), Frame::Integer(1)),(Frame::Simple("second".into()), Frame::Integer(2))]))]
+    #[case("%2\r\n+k\r\n:1\r\n+k\r\n:2\r\n", Frame::Map(vec![(Frame::Simple("k".into()), Frame::Integer(1)),(Frame::Simple("k".into()), Frame::Integer(2))]))]
+    #[case("%1\r\n+nested\r\n%1\r\n:1\r\n*1\r\n#t\r\n", Frame::Map(vec![(Frame::Simple("nested".into()), Frame::Map(vec![(Frame::Integer(1), Frame::Array(vec![Frame::Boolean(true)]))]))]))]
+    #[case("%0\r\n", Frame::Map(vec![]))]
+    #[case(">4\r\n$7\r\nmessage\r\n$3\r\nfoo\r\n$3\r\nbar\r\n$5\r\nworld\r\n", Frame::Push(vec![Frame::Bulk("message".into()), Frame::Bulk("foo".into()), Frame::Bulk("bar".into()), Frame::Bulk("world".into())]))]
+    #[case(">2\r\n+pubsub\r\n*1\r\n:1\r\n", Frame::Push(vec![Frame::Simple("pubsub".into()), Frame::Array(vec![Frame::Integer(1)])]))]
+    #[case(">0\r\n", Frame::Push(vec![]))]
+    #[case("*?\r\n:1\r\n:2\r\n:3\r\n.\r\n", Frame::Array(vec![Frame::Integer(1), Frame::Integer(2), Frame::Integer(3)]))]
+    #[case("*?\r\n.\r\n", Frame::Array(vec![]))]
+    #[case("*?\r\n,1.5\r\n*?\r\n:1\r\n.\r\n.\r\n", Frame::Array(vec![Frame::Double(1.5), Frame::Array(vec![Frame::Integer(1)])]))]
+    #[case("~?\r\n:1\r\n:2\r\n.\r\n", Frame::Set(vec![Frame::Integer(1), Frame::Integer(2)]))]
+    #[case(">?\r\n+message\r\n.\r\n", Frame::Push(vec![Frame::Simple("message".into())]))]
+    #[case("%?\r\n+a\r\n:1\r\n+b\r\n:2\r\n.\r\n", Frame::Map(vec![(Frame::Simple("a".into()), Frame::Integer(1)), (Frame::Simple("b".into()), Frame::Integer(2))]))]
+    #[case("|1\r\n+third\r\n:3\r\n*2\r\n:1\r\n:2\r\n", Frame::WithAttributes { attributes: vec![(Frame::Simple("third".into()), Frame::Integer(3))], value: Box::new(Frame::Array(vec![Frame::Integer(1), Frame::Integer(2)])) })]
+    #[case("|1\r\n+key-popularity\r\n%1\r\n$1\r\na\r\n,0.1923\r\n+OK\r\n", Frame::WithAttributes { attributes: vec![(Frame::Simple("key-popularity".into()), Frame::Map(vec![(Frame::Bulk("a".into()), Frame::Double(0.1923))]))], value: Box::new(Frame::Simple("OK".into())) })]
+    #[case("~3\r\n:1\r\n:2\r\n:3\r\n", Frame::Set(vec![Frame::Integer(1), Frame::Integer(2), Frame::Integer(3)]))]
+    #[case("~2\r\n:1\r\n:1\r\n", Frame::Set(vec![Frame::Integer(1), Frame::Integer(1)]))]
+    #[case("~0\r\n", Frame::Set(vec![]))]
     fn test_parse_success(#[case] input: &str, #[case] expected: Frame) {
         let mut cursor = Cursor::new(input.as_bytes());
         let result = Frame::parse(&mut cursor);
@@ -379,29 +1153,358 @@ mod tests {
     }
 
     #[rstest]
-    #[case(".1032.34\r\n", 1032.34)]
-    #[case(".+834.234\r\n", 834.234)]
-    #[case(".-20.12\r\n",  -20.12)]
-    #[case(".1e-1\r\n", 0.1)]
+    #[case(",1032.34\r\n", 1032.34)]
+    #[case(",+834.234\r\n", 834.234)]
+    #[case(",-20.12\r\n",  -20.12)]
+    #[case(",1e-1\r\n", 0.1)]
+    #[case(",10\r\n", 10.0)]
     fn test_parse_double_success(#[case] input: &str, #[case] expected: f64) {
         let mut cursor = Cursor::new(input.as_bytes());
         let result = Frame::parse(&mut cursor);
         assert!(matches!(result, Ok(Frame::Double(x)) if (x - expected).abs() < f64::EPSILON));
     }
 
+    #[rstest]
+    #[case("OK\r\n", 0, "OK")]
+    #[case("\r\n", 0, "")]
+    #[case("+OK\r\n", 1, "OK")]
+    #[case("+OK\r\n+\r\n", 6, "")]
+    #[case("a\rb\r\n", 0, "a\rb")]
+    #[case("\r\r\n", 0, "\r")]
+    #[case("\r\r\r\n", 0, "\r\r")]
+    fn test_read_line_crlf_at_end(#[case] input: &str, #[case] start: u64, #[case] expected: &str) {
+        let mut cursor = Cursor::new(input.as_bytes());
+        cursor.set_position(start);
+        let line = read_line(&mut cursor).unwrap();
+        assert_eq!(expected.as_bytes(), line);
+        assert_eq!(input.len() as u64, cursor.position());
+    }
+
+    #[rstest]
+    #[case("$10\r\nnotenough", "!\r\n", Frame::Bulk("notenough!".into()))]
+    #[case("*2\r\n$3\r\nfoo\r\n$3\r\nba", "r\r\n", Frame::Array(vec![Frame::Bulk("foo".into()), Frame::Bulk("bar".into())]))]
+    #[case("%1\r\n+key\r\n", ":1\r\n", Frame::Map(vec![(Frame::Simple("key".into()), Frame::Integer(1))]))]
+    fn test_parse_incomplete_is_retryable(
+        #[case] first: &str,
+        #[case] rest: &str,
+        #[case] expected: Frame,
+    ) {
+        let mut data = b"+OK\r\n".to_vec();
+        data.extend_from_slice(first.as_bytes());
+        let mut cursor = Cursor::new(data.as_slice());
+        assert_eq!(
+            Frame::Simple("OK".into()),
+            Frame::parse(&mut cursor).unwrap()
+        );
+
+        let result = Frame::parse(&mut cursor);
+        assert!(matches!(result, Err(FrameParsingError::Incomplete)));
+        assert_eq!(5, cursor.position());
+
+        let position = cursor.position();
+        data.extend_from_slice(rest.as_bytes());
+        let mut cursor = Cursor::new(data.as_slice());
+        cursor.set_position(position);
+        assert_eq!(expected, Frame::parse(&mut cursor).unwrap());
+        assert_eq!(data.len() as u64, cursor.position());
+    }
+
+    #[test]
+    fn test_parse_with_len_pipelined_frames() {
+        let input = b"$5\r\nhello\r\n:42\r\n";
+        let mut buf = &input[..];
+
+        let (first, len) = Frame::parse_with_len(&mut Cursor::new(buf)).unwrap();
+        assert_eq!(Frame::Bulk("hello".into()), first);
+        assert_eq!(11, len);
+        buf = &buf[len..];
+
+        let (second, len) = Frame::parse_with_len(&mut Cursor::new(buf)).unwrap();
+        assert_eq!(Frame::Integer(42), second);
+        assert_eq!(5, len);
+        assert!(buf[len..].is_empty());
+    }
+
+    #[rstest]
+    #[case("$1000000000\r\n")]
+    #[case("$17\r\nsomewhat too long\r\n")]
+    #[case("!17\r\nsomewhat too long\r\n")]
+    #[case("=21\r\ntxt:somewhat too long\r\n")]
+    #[case("$?\r\n;8\r\nsomewhat\r\n;9\r\ntoo long\r\n;0\r\n")]
+    #[case("*2\r\n$1\r\na\r\n$17\r\nsomewhat too long\r\n")]
+    fn test_parse_length_limit_exceeded(#[case] input: &str) {
+        let limits = ParseLimits {
+            max_bulk_len: 16,
+            ..Default::default()
+        };
+        let mut cursor = Cursor::new(input.as_bytes());
+        let result = Frame::parse_with_limits(&mut cursor, &limits);
+        assert!(matches!(
+            result.as_ref().map_err(FrameParsingError::cause),
+            Err(FrameParsingError::LengthLimitExceeded(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_within_length_limit() {
+        let limits = ParseLimits {
+            max_bulk_len: 16,
+            ..Default::default()
+        };
+        let mut cursor = Cursor::new("$16\r\nexactly sixteen!\r\n".as_bytes());
+        let result = Frame::parse_with_limits(&mut cursor, &limits);
+        assert_eq!(Frame::Bulk("exactly sixteen!".into()), result.unwrap());
+    }
+
+    #[test]
+    fn test_parse_depth_limit_exceeded() {
+        let input = "*1\r\n".repeat(10_000) + ":1\r\n";
+        let mut cursor = Cursor::new(input.as_bytes());
+        let result = Frame::parse(&mut cursor);
+        assert!(matches!(
+            result.as_ref().map_err(FrameParsingError::cause),
+            Err(FrameParsingError::DepthLimitExceeded)
+        ));
+    }
+
+    #[test]
+    fn test_parse_attribute_chain_depth_limit_exceeded() {
+        let input = "|0\r\n".repeat(200_000) + ":1\r\n";
+        let mut cursor = Cursor::new(input.as_bytes());
+        let result = Frame::parse(&mut cursor);
+        assert!(matches!(
+            result.as_ref().map_err(FrameParsingError::cause),
+            Err(FrameParsingError::DepthLimitExceeded)
+        ));
+    }
+
+    #[rstest]
+    #[case("*1\r\n*1\r\n:1\r\n", true)]
+    #[case("*1\r\n*1\r\n*1\r\n:1\r\n", false)]
+    #[case("%1\r\n:1\r\n*1\r\n:1\r\n", true)]
+    #[case("%1\r\n:1\r\n%1\r\n:1\r\n*0\r\n", false)]
+    #[case("~?\r\n>1\r\n:1\r\n.\r\n", true)]
+    #[case("~?\r\n>1\r\n*?\r\n.\r\n.\r\n", false)]
+    #[case("|0\r\n|0\r\n:1\r\n", true)]
+    #[case("|0\r\n|0\r\n|0\r\n:1\r\n", false)]
+    fn test_parse_depth_limit(#[case] input: &str, #[case] allowed: bool) {
+        let limits = ParseLimits {
+            max_depth: 2,
+            ..Default::default()
+        };
+        let mut cursor = Cursor::new(input.as_bytes());
+        let result = Frame::parse_with_limits(&mut cursor, &limits);
+        match allowed {
+            true => assert!(result.is_ok()),
+            false => assert!(matches!(
+                result.as_ref().map_err(FrameParsingError::cause),
+                Err(FrameParsingError::DepthLimitExceeded)
+            )),
+        }
+    }
+
+    #[test]
+    fn test_parse_bytes_shares_allocation() {
+        let input = Bytes::from_static(b"*2\r\n$5\r\nhello\r\n=9\r\ntxt:world\r\n+OK\r\n");
+        let mut cursor = Cursor::new(input.clone());
+
+        let frame = Frame::parse_bytes(&mut cursor).unwrap();
+        let Frame::Array(elements) = frame else {
+            panic!("expected an array, got {:?}", frame);
+        };
+        let [Frame::Bulk(hello), Frame::Verbatim { data: world, .. }] = &elements[..] else {
+            panic!("unexpected elements {:?}", elements);
+        };
+        assert_eq!(hello, "hello");
+        assert_eq!(hello.as_ptr(), input[8..].as_ptr());
+        assert_eq!(world, "world");
+        assert_eq!(world.as_ptr(), input[23..].as_ptr());
+
+        assert_eq!(
+            Frame::Simple("OK".into()),
+            Frame::parse_bytes(&mut cursor).unwrap()
+        );
+        assert_eq!(input.len() as u64, cursor.position());
+    }
+
+    #[test]
+    fn test_parse_bytes_incomplete_keeps_position() {
+        let mut cursor = Cursor::new(Bytes::from_static(b"+OK\r\n$5\r\nhel"));
+        assert_eq!(
+            Frame::Simple("OK".into()),
+            Frame::parse_bytes(&mut cursor).unwrap()
+        );
+        let result = Frame::parse_bytes(&mut cursor);
+        assert!(matches!(result, Err(FrameParsingError::Incomplete)));
+        assert_eq!(5, cursor.position());
+    }
+
+    #[rstest]
+    #[case("a\r")]
+    #[case("a\rb")]
+    #[case("a\rb\r")]
+    #[case("\r")]
+    #[case("")]
+    #[case("a")]
+    fn test_read_line_incomplete(#[case] input: &str) {
+        let mut cursor = Cursor::new(input.as_bytes());
+        assert!(matches!(
+            read_line(&mut cursor),
+            Err(FrameParsingError::Incomplete)
+        ));
+        assert_eq!(0, cursor.position());
+    }
+
+    #[rstest]
+    #[case("+OK\r\n", 5)]
+    #[case("+OK\r\n", 9)]
+    fn test_read_line_incomplete_at_end(#[case] input: &str, #[case] start: u64) {
+        let mut cursor = Cursor::new(input.as_bytes());
+        cursor.set_position(start);
+        assert!(matches!(
+            read_line(&mut cursor),
+            Err(FrameParsingError::Incomplete)
+        ));
+        assert_eq!(start, cursor.position());
+    }
+
+    #[rstest]
+    #[case("+OK\n", LineEnding::Strict, None)]
+    #[case("+OK\n", LineEnding::Lenient, Some("+OK"))]
+    #[case("\n", LineEnding::Strict, None)]
+    #[case("\n", LineEnding::Lenient, Some(""))]
+    fn test_read_line_bare_lf(
+        #[case] input: &str,
+        #[case] ending: LineEnding,
+        #[case] expected: Option<&str>,
+    ) {
+        let mut cursor = Cursor::new(input.as_bytes());
+        match (read_line_ending(&mut cursor, ending), expected) {
+            (Ok(line), Some(expected)) => {
+                assert_eq!(expected.as_bytes(), line);
+                assert_eq!(input.len() as u64, cursor.position());
+            }
+            (Err(FrameParsingError::Protocol(message)), None) => {
+                let byte = format!("byte {}", input.len() - 1);
+                assert!(message.contains(&byte), "{message}");
+                assert_eq!(0, cursor.position());
+            }
+            (result, _) => panic!("unexpected result {result:?}"),
+        }
+    }
+
+    #[rstest]
+    #[case("+OK\n")]
+    #[case("%1\r\n+key\n:1\r\n")]
+    #[case("*1\r\n:1\n")]
+    #[case("*1\r\n$4\r\nPING\n")]
+    #[case("$4\r\nPINGxx")]
+    #[case("$10\r\nnotenough\r\n")]
+    #[case("$4\r\nPING\rx")]
+    #[case("!3\r\nERR\n")]
+    #[case("$?\r\n;4\r\nPING\n")]
+    fn test_parse_bare_lf(#[case] input: &str) {
+        let mut cursor = Cursor::new(input.as_bytes());
+        assert!(matches!(
+            Frame::parse(&mut cursor)
+                .as_ref()
+                .map_err(FrameParsingError::cause),
+            Err(FrameParsingError::Protocol(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_boolean_consumes_line() {
+        let mut cursor = Cursor::new("#t\r\n#f\r\n".as_bytes());
+        assert_eq!(Frame::Boolean(true), Frame::parse(&mut cursor).unwrap());
+        assert_eq!(Frame::Boolean(false), Frame::parse(&mut cursor).unwrap());
+    }
+
+    #[rstest]
+    #[case(
+        "(3492890328409238509324850943850943825024385\r\n",
+        "3492890328409238509324850943850943825024385"
+    )]
+    #[case(
+        "(-3492890328409238509324850943850943825024385\r\n",
+        "-3492890328409238509324850943850943825024385"
+    )]
+    #[case("(9223372036854775808\r\n", "9223372036854775808")]
+    #[case("(42\r\n", "42")]
+    fn test_parse_big_number_success(#[case] input: &str, #[case] expected: &str) {
+        let mut cursor = Cursor::new(input.as_bytes());
+        let result = Frame::parse(&mut cursor).unwrap();
+        assert_eq!(Frame::BigNumber(expected.parse().unwrap()), result);
+    }
+
+    #[test]
+    fn test_parse_double_special_values() {
+        let parse = |input: &str| Frame::parse(&mut Cursor::new(input.as_bytes())).unwrap();
+        assert_eq!(Frame::Double(f64::INFINITY), parse(",inf\r\n"));
+        assert_eq!(Frame::Double(f64::NEG_INFINITY), parse(",-inf\r\n"));
+        assert_eq!(Frame::Double(f64::NAN), parse(",nan\r\n"));
+    }
+
+    #[rstest]
+    #[case(Frame::Double(f64::NAN), Frame::Double(f64::NAN), true)]
+    #[case(Frame::Double(1.5), Frame::Double(1.5), true)]
+    #[case(Frame::Double(0.0), Frame::Double(-0.0), false)]
+    #[case(Frame::Double(1.5), Frame::Double(f64::NAN), false)]
+    #[case(Frame::Double(f64::NAN), Frame::Integer(0), false)]
+    #[case(Frame::Array(vec![Frame::Double(f64::NAN)]), Frame::Array(vec![Frame::Double(f64::NAN)]), true)]
+    #[case(Frame::Array(vec![Frame::Integer(1)]), Frame::Push(vec![Frame::Integer(1)]), false)]
+    fn test_frame_equality(#[case] a: Frame, #[case] b: Frame, #[case] expected: bool) {
+        assert_eq!(expected, a == b);
+    }
+
+    #[rstest]
+    #[case(Frame::Double(f64::INFINITY), ",inf\r\n")]
+    #[case(Frame::Double(f64::NEG_INFINITY), ",-inf\r\n")]
+    #[case(Frame::Double(f64::NAN), ",nan\r\n")]
+    #[case(Frame::Double(1.5), ",1.5\r\n")]
+    #[case(Frame::Double(-2.5), ",-2.5\r\n")]
+    fn test_serialize_double(#[case] frame: Frame, #[case] expected: &str) {
+        assert_eq!(expected.as_bytes(), frame.serialize());
+    }
+
     #[rstest]
     #[case("")]
     #[case("+OK\r")]
     #[case("+Test")]
     #[case("-Err")]
     #[case(":129")]
-    #[case(".123.34\r")]
-    #[case("$10\r\nnotenough\r\n")]
+    #[case(",123.34\r")]
+    #[case("$10\r\nnotenough")]
+    #[case("$?\r\n")]
+    #[case("$?\r\n;4\r\nHell\r\n;6\r\no wo")]
+    #[case("$?\r\n;4\r\nHell\r\n")]
+    #[case("*?\r\n")]
+    #[case("*?\r\n:1\r\n")]
+    #[case("*?\r\n:1\r\n.")]
+    #[case("*?\r\n:1\r\n.\r")]
+    #[case("%?\r\n+a\r\n:1\r\n")]
+    #[case("(123456789")]
     #[case("!50\r\nERROR not enough text\r\n")]
+    #[case("!21\r\nSYNTAX invalid syntax")]
+    #[case("!21")]
     #[case("=40\r\ntxt:Hello from verbatim\r\n")]
     #[case("%2\r\n+first\r\n:1\r\n")]
     #[case("%2\r\n+first\r\n")]
+    #[case("%1\r\n+nested\r\n%1\r\n:1\r\n")]
+    #[case("|1\r\n+third\r\n:3\r\n")]
+    #[case("|1\r\n+third\r\n:3\r\n*2\r\n:1\r\n")]
+    #[case(">4\r\n$7\r\nmessage\r\n$3\r\nfoo\r\n")]
+    #[case(">2\r\n+pubsub\r\n*1\r\n")]
     #[case("~3\r\n:1\r\n:2\r\n")]
+    #[case("*2\r\n*1\r\n:1\r\n")]
+    #[case("#")]
+    #[case("#t")]
+    #[case("#t\r")]
+    #[case("_")]
+    #[case("*9223372036854775807\r\n")]
+    #[case("%9223372036854775807\r\n")]
+    #[case("$4\r\nPING")]
+    #[case("$4\r\nPING\r")]
     fn test_parse_incomplete(#[case] input: &str) {
         let mut cursor = Cursor::new(input.as_bytes());
         let enough = Frame::check(&mut cursor);
@@ -414,18 +1517,216 @@ mod tests {
     }
 
     #[rstest]
-    #[case(":13472.2348\r\n")]
-    #[case(":pasdufgskldfg\r\n")]
-    #[case(".str\r\n")]
-    #[case(".*234950.45&\r\n")]
+    #[case(":13472.2348\r\n", "13472.2348")]
+    #[case(":pasdufgskldfg\r\n", "pasdufgskldfg")]
+    #[case("$abc\r\n", "abc")]
+    #[case("$?\r\n;-4\r\nHell\r\n;0\r\n", "-4")]
+    #[case("(12a34\r\n", "12a34")]
+    #[case("(\r\n", "")]
+    #[case("~-34\r\n", "-34")]
+    #[case("~a\r\n", "a")]
+    #[case(">-1\r\n", "-1")]
+    fn test_parse_invalid_integer(#[case] input: &str, #[case] token: &str) {
+        let mut cursor = Cursor::new(input.as_bytes());
+        let result = Frame::parse(&mut cursor);
+        assert!(
+            matches!(result.as_ref().map_err(FrameParsingError::cause), Err(FrameParsingError::InvalidInteger(t)) if t == token)
+        );
+    }
+
+    #[rstest]
+    #[case(",str\r\n", "str")]
+    #[case(",*234950.45&\r\n", "*234950.45&")]
+    #[case(",infinity\r\n", "infinity")]
+    #[case(",+inf\r\n", "+inf")]
+    #[case(",NaN\r\n", "NaN")]
+    #[case(",1e400\r\n", "1e400")]
+    #[case(",\r\n", "")]
+    fn test_parse_invalid_double(#[case] input: &str, #[case] token: &str) {
+        let mut cursor = Cursor::new(input.as_bytes());
+        let result = Frame::parse(&mut cursor);
+        assert!(
+            matches!(result.as_ref().map_err(FrameParsingError::cause), Err(FrameParsingError::InvalidDouble(t)) if t == token)
+        );
+    }
+
+    #[test]
+    fn test_invalid_token_in_error_message() {
+        let mut cursor = Cursor::new(":12x\r\n".as_bytes());
+        let error = Frame::parse(&mut cursor).unwrap_err();
+        assert_eq!("invalid integer: \"12x\" at byte 0", error.to_string());
+    }
+
+    #[rstest]
+    #[case(b"+O\xffK\r\n", 1)]
+    #[case(b"-ERR \xc3\x28\r\n", 4)]
+    #[case(b"*1\r\n+\xe2\x82\r\n", 0)]
+    fn test_parse_invalid_utf8(#[case] input: &[u8], #[case] valid_up_to: usize) {
+        let mut cursor = Cursor::new(input);
+        let result = Frame::parse(&mut cursor);
+        assert!(matches!(
+            result.as_ref().map_err(FrameParsingError::cause),
+            Err(FrameParsingError::InvalidUtf8(e)) if e.valid_up_to() == valid_up_to
+        ));
+    }
+
+    #[rstest]
+    #[case("+OK\r\n@bad\r\n", 5)]
+    #[case(":1\r\n:x\r\n", 4)]
+    #[case("+OK\r\n*2\r\n:1\r\n,nope\r\n", 13)]
+    #[case("+OK\r\n*1\r\n%1\r\n+key\r\n#x\r\n", 19)]
+    fn test_parse_error_offset(#[case] input: &str, #[case] offset: usize) {
+        let mut cursor = Cursor::new(input.as_bytes());
+        let _ = Frame::parse(&mut cursor).unwrap();
+        let error = Frame::parse(&mut cursor).unwrap_err();
+        assert_eq!(Some(offset), error.offset());
+    }
+
+    #[rstest]
     #[case("#c\r\n")]
-    #[case("=19\r\ntxtH:ello from verbatim\r\n")]
-    #[case("~-34\r\n")]
-    #[case("~a\r\n")]
+    #[case("#tf\r\n")]
+    #[case("#\r\n")]
+    #[case("=23\r\ntxtH:ello from verbatim\r\n")]
+    #[case("=3\r\ntxt\r\n")]
+    #[case("!-1\r\n")]
+    #[case("$?\r\n:4\r\nHell\r\n;0\r\n")]
+    #[case("*-2\r\n")]
     fn test_parse_invalid(#[case] input: &str) {
         let mut cursor = Cursor::new(input.as_bytes());
         let result = Frame::parse(&mut cursor);
-        assert!(matches!(result, Err(FrameParsingError::Other(_))));
+        assert!(matches!(
+            result.as_ref().map_err(FrameParsingError::cause),
+            Err(FrameParsingError::Other(_))
+        ));
+    }
+
+    #[rstest]
+    #[case("@garbage\r\n", b'@')]
+    #[case("garbage", b'g')]
+    #[case("*2\r\n:1\r\n@garbage\r\n", b'@')]
+    #[case("%1\r\n+key\r\nvalue\r\n", b'v')]
+    #[case("|1\r\n+key\r\n+value\r\nPING\r\n", b'P')]
+    fn test_parse_unknown_type(#[case] input: &str, #[case] expected: u8) {
+        let mut cursor = Cursor::new(input.as_bytes());
+        let result = Frame::parse(&mut cursor);
+        assert!(
+            matches!(result.as_ref().map_err(FrameParsingError::cause), Err(FrameParsingError::UnknownType(b)) if *b == expected)
+        );
+    }
+
+    #[rstest]
+    #[case("*-1\r\n", None)]
+    #[case("*0\r\n", Some(0))]
+    #[case("*3\r\n:1\r\n", Some(3))]
+    fn test_parse_array_len(#[case] input: &str, #[case] expected: Option<usize>) {
+        let mut cursor = Cursor::new(input.as_bytes());
+        assert_eq!(expected, Frame::parse_array_len(&mut cursor).unwrap());
+        assert_eq!(input.find('\n').unwrap() as u64 + 1, cursor.position());
+    }
+
+    #[rstest]
+    #[case(Frame::Simple("OK".into()), "+OK\r\n")]
+    #[case(Frame::Error("ERR bad".into()), "-ERR bad\r\n")]
+    #[case(Frame::Integer(-12), ":-12\r\n")]
+    #[case(Frame::Double(0.5), ",0.5\r\n")]
+    #[case(Frame::Bulk("hello".into()), "$5\r\nhello\r\n")]
+    #[case(Frame::Bulk("".into()), "$0\r\n\r\n")]
+    #[case(Frame::Null, "_\r\n")]
+    #[case(Frame::Boolean(true), "#t\r\n")]
+    fn test_encode(#[case] frame: Frame, #[case] expected: &str) {
+        assert_eq!(expected.as_bytes(), &frame.encode(RespVersion::V3)[..]);
+
+        let mut buf = BytesMut::from("+prefix\r\n");
+        frame.encode_to(&mut buf, RespVersion::V3);
+        assert_eq!(format!("+prefix\r\n{}", expected).as_bytes(), &buf[..]);
+
+        let encoded = frame.encode(RespVersion::V3);
+        let mut cursor = Cursor::new(&encoded[..]);
+        assert_eq!(frame, Frame::parse(&mut cursor).unwrap());
+    }
+
+    #[rstest]
+    #[case(Frame::Null, "$-1\r\n", "_\r\n")]
+    #[case(Frame::Boolean(true), ":1\r\n", "#t\r\n")]
+    #[case(Frame::Boolean(false), ":0\r\n", "#f\r\n")]
+    #[case(Frame::Double(1.5), "$3\r\n1.5\r\n", ",1.5\r\n")]
+    #[case(Frame::Double(f64::INFINITY), "$3\r\ninf\r\n", ",inf\r\n")]
+    #[case(Frame::BigNumber("-12345678901234567890".parse().unwrap()), "$21\r\n-12345678901234567890\r\n", "(-12345678901234567890\r\n")]
+    #[case(Frame::BulkError("ERR multi\nline".into()), "-ERR multi line\r\n", "!14\r\nERR multi\nline\r\n")]
+    #[case(Frame::Verbatim { format: VerbatimEncoding::Text, data: "hi".into() }, "$2\r\nhi\r\n", "=6\r\ntxt:hi\r\n")]
+    #[case(Frame::Set(vec![Frame::Integer(1)]), "*1\r\n:1\r\n", "~1\r\n:1\r\n")]
+    #[case(Frame::Push(vec![Frame::Integer(1)]), "*1\r\n:1\r\n", ">1\r\n:1\r\n")]
+    #[case(Frame::Map(vec![(Frame::Bulk("k".into()), Frame::Null)]), "*2\r\n$1\r\nk\r\n$-1\r\n", "%1\r\n$1\r\nk\r\n_\r\n")]
+    #[case(
+        Frame::WithAttributes { attributes: vec![(Frame::Simple("a".into()), Frame::Integer(1))], value: Box::new(Frame::Boolean(true)) },
+        ":1\r\n",
+        "|1\r\n+a\r\n:1\r\n#t\r\n"
+    )]
+    #[case(Frame::Array(vec![Frame::Null, Frame::Boolean(true)]), "*2\r\n$-1\r\n:1\r\n", "*2\r\n_\r\n#t\r\n")]
+    #[case(Frame::Simple("a\r\n+INJ".into()), "+a  +INJ\r\n", "+a  +INJ\r\n")]
+    #[case(Frame::Error("ERR unknown command 'X\r\n+INJ'".into()), "-ERR unknown command 'X  +INJ'\r\n", "-ERR unknown command 'X  +INJ'\r\n")]
+    fn test_encode_versions(#[case] frame: Frame, #[case] v2: &str, #[case] v3: &str) {
+        assert_eq!(v2.as_bytes(), &frame.encode(RespVersion::V2)[..]);
+        assert_eq!(v3.as_bytes(), &frame.encode(RespVersion::V3)[..]);
+    }
+
+    #[test]
+    fn test_encode_nested_aggregates() {
+        let frame = Frame::Map(vec![(
+            Frame::Simple("list".into()),
+            Frame::Array(vec![
+                Frame::Integer(1),
+                Frame::Set(vec![Frame::Bulk("a".into())]),
+                Frame::Push(vec![]),
+            ]),
+        )]);
+        assert_eq!(
+            "%1\r\n+list\r\n*3\r\n:1\r\n~1\r\n$1\r\na\r\n>0\r\n".as_bytes(),
+            &frame.encode(RespVersion::V3)[..]
+        );
+    }
+
+    #[test]
+    fn test_encode_parse_roundtrip_nested() {
+        // Builds aggregates of increasing width and depth, mixing all the aggregate kinds
+        fn build(depth: usize, width: usize) -> Frame {
+            if depth == 0 {
+                return Frame::Bulk(format!("leaf{}", width).into());
+            }
+            let children: Vec<Frame> = (0..width).map(|w| build(depth - 1, w)).collect();
+            match depth % 4 {
+                0 => Frame::Array(children),
+                1 => Frame::Set(children),
+                2 => Frame::Push(children),
+                _ => Frame::Map(
+                    children
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, c)| (Frame::Integer(i as i64), c))
+                        .collect(),
+                ),
+            }
+        }
+
+        for depth in 0..6 {
+            for width in 0..4 {
+                let frame = build(depth, width);
+                let encoded = frame.encode(RespVersion::V3);
+                let mut cursor = Cursor::new(&encoded[..]);
+                assert_eq!(frame, Frame::parse(&mut cursor).unwrap());
+                assert_eq!(encoded.len() as u64, cursor.position());
+            }
+        }
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_encode_parse_roundtrip_arbitrary(frame in crate::resp::strategy::frame()) {
+            let encoded = frame.encode(RespVersion::V3);
+            let mut cursor = Cursor::new(&encoded[..]);
+            proptest::prop_assert_eq!(&frame, &Frame::parse(&mut cursor).unwrap());
+            proptest::prop_assert_eq!(encoded.len() as u64, cursor.position());
+        }
     }
 
     #[test]
@@ -435,23 +1736,30 @@ mod tests {
             Frame::Error("Error".into()),
             Frame::Integer(123),
             Frame::Double(123.456),
+            Frame::Double(f64::NAN),
+            Frame::Double(f64::NEG_INFINITY),
             Frame::Bulk("hello".into()),
             Frame::BulkError("error".into()),
             Frame::Null,
             Frame::Array(vec![Frame::Integer(1), Frame::Integer(2)]),
             Frame::Boolean(true),
             Frame::Boolean(false),
-            Frame::BigNumber("12345678901234567890".into()),
-            Frame::Verbatim(VerbatimEncoding::Text, "hello".into()),
-            Frame::Map(HashMap::from([(
-                Frame::Simple("k".into()),
-                Frame::Integer(1),
-            )])),
-            Frame::Attribute(HashMap::from([(
-                Frame::Simple("k".into()),
-                Frame::Integer(1),
-            )])),
-            Frame::Set(HashSet::from([Frame::Integer(1), Frame::Integer(2)])),
+            Frame::BigNumber("12345678901234567890".parse().unwrap()),
+            Frame::BigNumber("-12345678901234567890".parse().unwrap()),
+            Frame::Verbatim {
+                format: VerbatimEncoding::Text,
+                data: "hello".into(),
+            },
+            Frame::Map(vec![
+                (Frame::Simple("k".into()), Frame::Integer(1)),
+                (Frame::Simple("j".into()), Frame::Integer(2)),
+            ]),
+            Frame::WithAttributes {
+                attributes: vec![(Frame::Simple("k".into()), Frame::Integer(1))],
+                value: Box::new(Frame::Array(vec![Frame::Integer(1)])),
+            },
+            Frame::Set(vec![Frame::Integer(1), Frame::Integer(2)]),
+            Frame::Set(vec![]),
             Frame::Push(vec![Frame::Integer(1), Frame::Integer(2)]),
         ];
 
@@ -462,4 +1770,255 @@ mod tests {
             assert_eq!(frame, parsed);
         }
     }
+
+    #[rstest]
+    #[case(Frame::Simple("OK".to_string()), "OK")]
+    #[case(Frame::Error("ERR wrong".to_string()), "(error) ERR wrong")]
+    #[case(Frame::Integer(-3), "(integer) -3")]
+    #[case(Frame::Null, "(nil)")]
+    #[case(
+        Frame::Bulk(Bytes::from("say \"hi\"\r\n\x01")),
+        "\"say \\\"hi\\\"\\r\\n\\x01\""
+    )]
+    #[case(Frame::Boolean(true), "(true)")]
+    #[case(Frame::Double(1.5), "(double) 1.5")]
+    #[case(Frame::Array(vec![]), "(empty array)")]
+    #[case(Frame::Map(vec![]), "(empty hash)")]
+    fn test_display(#[case] frame: Frame, #[case] expected: &str) {
+        assert_eq!(expected, frame.to_string());
+    }
+
+    #[test]
+    fn test_display_nested_array() {
+        let frame = Frame::Array(vec![
+            Frame::Bulk(Bytes::from("a")),
+            Frame::Array(vec![Frame::Integer(1), Frame::Array(vec![Frame::Null])]),
+            Frame::Map(vec![(
+                Frame::Simple("key".to_string()),
+                Frame::Bulk(Bytes::from("v")),
+            )]),
+        ]);
+        let expected = "\
+1) \"a\"
+2) 1) (integer) 1
+   2) 1) (nil)
+3) 1# key => \"v\"";
+        assert_eq!(expected, frame.to_string());
+    }
+
+    #[test]
+    fn test_display_aligns_indexes() {
+        let frame = Frame::Array((0..10).map(Frame::Integer).collect());
+        let rendered = frame.to_string();
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(" 1) (integer) 0", lines[0]);
+        assert_eq!("10) (integer) 9", lines[9]);
+    }
+
+    #[test]
+    fn test_try_from_string() {
+        assert_eq!(
+            "OK",
+            String::try_from(Frame::Simple("OK".to_string())).unwrap()
+        );
+        assert_eq!(
+            "bulk",
+            String::try_from(Frame::Bulk(Bytes::from("bulk"))).unwrap()
+        );
+        assert!(matches!(
+            String::try_from(Frame::Bulk(Bytes::from_static(b"\xff"))),
+            Err(FrameConversionError::InvalidUtf8(_))
+        ));
+        assert!(matches!(
+            String::try_from(Frame::Integer(1)),
+            Err(FrameConversionError::UnexpectedType { found: b':', .. })
+        ));
+    }
+
+    #[test]
+    fn test_try_from_scalars() {
+        assert_eq!(42, i64::try_from(Frame::Integer(42)).unwrap());
+        assert_eq!(1.5, f64::try_from(Frame::Double(1.5)).unwrap());
+        assert!(bool::try_from(Frame::Boolean(true)).unwrap());
+        assert_eq!(
+            Bytes::from("data"),
+            Bytes::try_from(Frame::Bulk(Bytes::from("data"))).unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case(i64::try_from(Frame::Simple("42".to_string())).map(|_| ()), b'+')]
+    #[case(f64::try_from(Frame::Integer(1)).map(|_| ()), b':')]
+    #[case(bool::try_from(Frame::Null).map(|_| ()), b'_')]
+    #[case(Bytes::try_from(Frame::Simple("OK".to_string())).map(|_| ()), b'+')]
+    fn test_try_from_mismatch(
+        #[case] result: Result<(), FrameConversionError>,
+        #[case] expected: u8,
+    ) {
+        assert!(matches!(
+            result,
+            Err(FrameConversionError::UnexpectedType { found, .. }) if found == expected
+        ));
+    }
+
+    #[test]
+    fn test_conversion_error_message() {
+        let error = i64::try_from(Frame::Null).unwrap_err();
+        assert_eq!(
+            "expected an integer, found frame type '_'",
+            error.to_string()
+        );
+    }
+
+    #[rstest]
+    #[case(Frame::from("text"), Frame::Bulk(Bytes::from("text")))]
+    #[case(Frame::from("owned".to_string()), Frame::Bulk(Bytes::from("owned")))]
+    #[case(Frame::from(-7), Frame::Integer(-7))]
+    #[case(Frame::from(2.5), Frame::Double(2.5))]
+    #[case(Frame::from(false), Frame::Boolean(false))]
+    #[case(
+        Frame::from(vec![Frame::from(1), Frame::from("a")]),
+        Frame::Array(vec![Frame::Integer(1), Frame::Bulk(Bytes::from("a"))])
+    )]
+    #[case(Frame::ok(), Frame::Simple("OK".to_string()))]
+    #[case(Frame::simple("QUEUED"), Frame::Simple("QUEUED".to_string()))]
+    fn test_from_constructors(#[case] frame: Frame, #[case] expected: Frame) {
+        assert_eq!(expected, frame);
+    }
+
+    #[test]
+    fn test_accessors_matching() {
+        assert_eq!(Some("GET"), Frame::Bulk(Bytes::from("GET")).as_bulk_str());
+        assert_eq!(Some(3), Frame::Integer(3).as_integer());
+        assert_eq!(Some(0.5), Frame::Double(0.5).as_double());
+        assert_eq!(
+            Some(&[Frame::Null][..]),
+            Frame::Array(vec![Frame::Null]).as_array()
+        );
+        assert!(Frame::Null.is_null());
+    }
+
+    #[rstest]
+    #[case(Frame::Simple("GET".to_string()))]
+    #[case(Frame::Bulk(Bytes::from_static(b"\xff")))]
+    #[case(Frame::Set(vec![Frame::Integer(1)]))]
+    #[case(Frame::Boolean(false))]
+    fn test_accessors_not_matching(#[case] frame: Frame) {
+        assert_eq!(None, frame.as_bulk_str());
+        assert_eq!(None, frame.as_integer());
+        assert_eq!(None, frame.as_double());
+        assert_eq!(None, frame.as_array());
+        assert!(!frame.is_null());
+    }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    #[case(Frame::Array(vec![Frame::Integer(1), Frame::Null]))]
+    #[case(Frame::BigNumber("-3492890328409238509324850943850943825024385".parse().unwrap()))]
+    #[case(Frame::Boolean(true))]
+    #[case(Frame::Bulk(Bytes::from_static(b"bin\x00\xff")))]
+    #[case(Frame::BulkError(Bytes::from("SYNTAX invalid")))]
+    #[case(Frame::Double(-1.25))]
+    #[case(Frame::Error("ERR".to_string()))]
+    #[case(Frame::Integer(i64::MIN))]
+    #[case(Frame::Map(vec![(Frame::Simple("k".to_string()), Frame::Set(vec![]))]))]
+    #[case(Frame::Null)]
+    #[case(Frame::Push(vec![Frame::Bulk(Bytes::from("message"))]))]
+    #[case(Frame::Set(vec![Frame::Boolean(false)]))]
+    #[case(Frame::Simple("OK".to_string()))]
+    #[case(Frame::Verbatim { format: VerbatimEncoding::Other(*b"abc"), data: Bytes::from("x") })]
+    #[case(Frame::WithAttributes {
+        attributes: vec![(Frame::Simple("ttl".to_string()), Frame::Integer(3))],
+        value: Box::new(Frame::Integer(1)),
+    })]
+    fn test_serde_roundtrip(#[case] frame: Frame) {
+        let json = serde_json::to_string(&frame).unwrap();
+        let decoded: Frame = serde_json::from_str(&json).unwrap();
+        assert_eq!(frame, decoded);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_tagged_representation() {
+        let json = serde_json::to_value(Frame::Bulk(Bytes::from("hi"))).unwrap();
+        assert_eq!(
+            serde_json::json!({"type": "Bulk", "value": [104, 105]}),
+            json
+        );
+    }
+
+    #[rstest]
+    #[case("PING\r\n", vec!["PING"])]
+    #[case("SET foo \"hello world\"\r\n", vec!["SET", "foo", "hello world"])]
+    #[case("  get\t key  \r\n", vec!["get", "key"])]
+    #[case("ECHO \"say \\\"hi\\\"\\n\" \"\"\r\n", vec!["ECHO", "say \"hi\"\n", ""])]
+    #[case("   \r\n", vec![])]
+    #[case("PING\n", vec!["PING"])]
+    #[case("\n", vec![])]
+    fn test_parse_inline(#[case] input: &str, #[case] expected: Vec<&str>) {
+        let mut cursor = Cursor::new(input.as_bytes());
+        let expected = expected
+            .into_iter()
+            .map(|arg| Frame::Bulk(Bytes::copy_from_slice(arg.as_bytes())))
+            .collect();
+        assert_eq!(
+            Frame::Array(expected),
+            Frame::parse_request(&mut cursor, &ParseLimits::default()).unwrap()
+        );
+        assert_eq!(input.len() as u64, cursor.position());
+    }
+
+    #[rstest]
+    #[case("SET foo \"bar\r\n")]
+    #[case("SET foo \"bar\"baz\r\n")]
+    fn test_parse_inline_invalid(#[case] input: &str) {
+        let mut cursor = Cursor::new(input.as_bytes());
+        assert!(matches!(
+            Frame::parse_request(&mut cursor, &ParseLimits::default())
+                .as_ref()
+                .map_err(FrameParsingError::cause),
+            Err(FrameParsingError::Other(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_inline_incomplete() {
+        let mut cursor = Cursor::new("SET foo".as_bytes());
+        assert!(matches!(
+            Frame::parse_request(&mut cursor, &ParseLimits::default()),
+            Err(FrameParsingError::Incomplete)
+        ));
+        assert_eq!(0, cursor.position());
+    }
+
+    #[test]
+    fn test_parse_inline_length_limit() {
+        let limits = ParseLimits {
+            max_bulk_len: 8,
+            ..ParseLimits::default()
+        };
+        for input in ["GET 0123456789\r\n", "GET 0123456789"] {
+            let mut cursor = Cursor::new(input.as_bytes());
+            assert!(matches!(
+                Frame::parse_request(&mut cursor, &limits)
+                    .as_ref()
+                    .map_err(FrameParsingError::cause),
+                Err(FrameParsingError::LengthLimitExceeded(_))
+            ));
+        }
+
+        let mut cursor = Cursor::new("GET key\r\n".as_bytes());
+        assert!(Frame::parse_request(&mut cursor, &limits).is_ok());
+    }
+
+    #[test]
+    fn test_parse_inline_not_by_default() {
+        let mut cursor = Cursor::new("PING\r\n".as_bytes());
+        assert!(matches!(
+            Frame::parse(&mut cursor)
+                .as_ref()
+                .map_err(FrameParsingError::cause),
+            Err(FrameParsingError::UnknownType(b'P'))
+        ));
+    }
 }
@@ -1,25 +1,89 @@
-use std::{collections::HashMap, sync::atomic::AtomicU64};
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    io,
+    path::PathBuf,
+    sync::atomic::AtomicU64,
+    time::{Duration, Instant},
+};
 
+use bytes::Bytes;
 use thiserror::Error;
-use tokio::{select, sync::mpsc};
+use tokio::{select, sync::mpsc, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
 
 use crate::{
-    command::{echo, ping},
+    aof::{self, Aof},
+    blocking::Blocked,
+    cmd,
+    command::{
+        append, auth, bitmap, commands, config, copy, dbsize, debug, del, discard, echo, exec,
+        exists, expire, flush, get, getdel, getex, getrange, hdel, hello, hget, hgetall, hincrby,
+        hset, incr, info, keys, keytype, llen, lrange, mget, mset, multi, object, persist, ping,
+        pop, psubscribe, pttl, publish, push, push::End, quit, rename, reset, sadd, save, scan,
+        select, set, setnx, setop, setop::SetOp, setrange, sismember, slowlog, smembers, srem,
+        strlen, subscribe, ttl, unsubscribe, wait, watch, zadd, zrange, zscore, Command,
+        CommandError,
+    },
+    config::ServerConfig,
     messages::{
         ConnectionMessage::{self},
         Request, ServerMessage,
     },
-    resp::types::Frame,
+    pubsub::PubSub,
+    resp::types::{Frame, RespVersion},
+    slowlog::SlowLog,
+    snapshot::Snapshot,
+    store::{evict, spawn_expiration_task, Db},
 };
 
+// Commands queued after MULTI
+#[derive(Debug, Default, PartialEq)]
+pub struct Transaction {
+    pub queued: Vec<(Frame, Command)>,
+    // Set when a command failed to be queued, so EXEC must abort
+    pub failed: bool,
+}
+
+// State kept for each connection, which the commands it sends depend upon
+#[derive(Debug, PartialEq)]
+pub struct ConnectionState {
+    // Protocol negotiated with HELLO, connections start with RESP2
+    pub protocol: RespVersion,
+    // Logical database the connection works on
+    pub db: usize,
+    pub authenticated: bool,
+    pub transaction: Option<Transaction>,
+    // Keys watched for the next transaction: their database, and the version
+    // they were at when watched
+    pub watched: Vec<(usize, Bytes, u64)>,
+}
+
+impl Default for ConnectionState {
+    fn default() -> Self {
+        Self {
+            protocol: RespVersion::V2,
+            db: 0,
+            authenticated: false,
+            transaction: None,
+            watched: Vec::new(),
+        }
+    }
+}
+
 pub struct Client {
     pub id: u64,
     pub sender: mpsc::Sender<ServerMessage>,
+    pub state: ConnectionState,
 }
 
+// How long connections get to write their last replies when shutting down
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub struct ServerInfo {
     pub host: String,
     pub port: u16,
+    pub started_at: Instant,
 }
 
 impl ServerInfo {
@@ -33,96 +97,827 @@ pub struct Server {
     pub receiver: mpsc::Receiver<ConnectionMessage>,
     pub sender: mpsc::Sender<ConnectionMessage>,
     pub clients: HashMap<u64, Client>,
+    // Logical databases, selected by each connection with SELECT
+    pub dbs: Vec<Db>,
+    pub pubsub: PubSub,
+    // Clients waiting in BLPOP or DEBUG SLEEP
+    pub blocked: Blocked,
+    pub slowlog: SlowLog,
+    pub config: ServerConfig,
+    expiration_task: Option<JoinHandle<()>>,
+    // Snapshot being saved by BGSAVE
+    save_task: Option<JoinHandle<()>>,
+    // Log of the write commands, when appendonly is set
+    pub aof: Option<Aof>,
+    // Cancelled to shut the server down, along with its listeners
+    pub shutdown: CancellationToken,
     client_id: AtomicU64,
 }
 
 #[derive(Error, Debug, PartialEq)]
 pub enum ServerError {
-    #[error("Invalid command syntax: {0}")]
-    CommandInvalidSyntax(String),
-    #[error("Command \"{0}\" not available")]
-    CommandNotAvailable(String),
+    #[error(transparent)]
+    Command(#[from] CommandError),
     #[error("Generic IO error")]
     ServerIoError,
+    #[error("{0}")]
+    InvalidValue(String),
+    #[error("NOPROTO sorry, this protocol version is not supported")]
+    UnsupportedProtocol,
+    #[error("MULTI calls can not be nested")]
+    NestedMulti,
+    #[error("WATCH inside MULTI is not allowed")]
+    WatchInMulti,
+    #[error("EXEC without MULTI")]
+    ExecWithoutMulti,
+    #[error("DISCARD without MULTI")]
+    DiscardWithoutMulti,
+    #[error("EXECABORT Transaction discarded because of previous errors.")]
+    ExecAbort,
+    #[error("NOAUTH Authentication required.")]
+    NoAuth,
+    #[error("WRONGPASS invalid username-password pair or user is disabled.")]
+    WrongPass,
+    #[error("OOM command not allowed when used memory > 'maxmemory'.")]
+    OutOfMemory,
+    #[error("DEBUG command not allowed. Set enable-debug-command to allow it.")]
+    DebugDisabled,
+}
+
+impl ServerError {
+    // Error reply sent to the client, starting with the error code
+    pub fn to_frame(&self) -> Frame {
+        match self {
+            ServerError::UnsupportedProtocol
+            | ServerError::ExecAbort
+            | ServerError::NoAuth
+            | ServerError::WrongPass
+            | ServerError::OutOfMemory => Frame::Error(self.to_string()),
+            ServerError::Command(error) => error.to_frame(),
+            error => Frame::Error(format!("ERR {}", error)),
+        }
+    }
 }
 
 impl Server {
     pub fn new(host: String, port: u16) -> Self {
+        Self::with_config(host, port, ServerConfig::default())
+    }
+
+    pub fn with_config(host: String, port: u16, config: ServerConfig) -> Self {
         let (sender, recv) = mpsc::channel::<ConnectionMessage>(10);
 
         Server {
-            info: ServerInfo { host, port },
+            info: ServerInfo {
+                host,
+                port,
+                started_at: Instant::now(),
+            },
             receiver: recv,
             sender,
             clients: HashMap::new(),
+            dbs: (0..config.databases).map(|_| Db::new()).collect(),
+            pubsub: PubSub::new(),
+            blocked: Blocked::new(),
+            slowlog: SlowLog::new(),
+            config,
+            expiration_task: None,
+            save_task: None,
+            aof: None,
+            shutdown: CancellationToken::new(),
             client_id: AtomicU64::new(0),
         }
     }
 
+    // Serve the clients until shut down, see `shut_down`
     pub async fn run(&mut self) {
+        self.restart_expiration_task();
         loop {
             select! {
-                Some(command) = self.receiver.recv() => {
-                    match command {
-                        ConnectionMessage::NewClient(sender) => {
-                            let new_id = self.client_id.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                            let client = Client {
-                                id: new_id,
-                                sender: sender.clone()
-                            };
-                            if let Err(e) = client.sender.send(ServerMessage::ClientInitialized(new_id)).await {
-                                eprintln!("Error sending new client id back to client: {}", e);
-                            }
-                            self.clients.insert(new_id, client);
-                        },
-                        ConnectionMessage::ClientRequest(request) => {
-                            if let Err(e) = self.handle_message(&request).await {
-                                eprintln!("Error handling message : {}", e);
-                            };
-                        },
+                Some(message) = self.receiver.recv() => self.receive(message).await,
+                _ = self.shutdown.cancelled() => break,
+            }
+        }
+        self.shut_down().await;
+    }
+
+    async fn receive(&mut self, message: ConnectionMessage) {
+        match message {
+            ConnectionMessage::NewClient(sender) => {
+                let new_id = self
+                    .client_id
+                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                // Connections made while no password is required
+                // stay authenticated when one gets set
+                let client = Client {
+                    id: new_id,
+                    sender: sender.clone(),
+                    state: ConnectionState {
+                        authenticated: self.config.requirepass.is_empty(),
+                        ..ConnectionState::default()
+                    },
+                };
+                if let Err(e) = client
+                    .sender
+                    .send(ServerMessage::Limits(self.config.limits))
+                    .await
+                {
+                    eprintln!("Error sending limits to client: {}", e);
+                }
+                if let Err(e) = client
+                    .sender
+                    .send(ServerMessage::IdleTimeout(self.config.idle_timeout()))
+                    .await
+                {
+                    eprintln!("Error sending idle timeout to client: {}", e);
+                }
+                if let Err(e) = client
+                    .sender
+                    .send(ServerMessage::ClientInitialized(new_id))
+                    .await
+                {
+                    eprintln!("Error sending new client id back to client: {}", e);
+                }
+                self.clients.insert(new_id, client);
+            }
+            ConnectionMessage::ClientRequest(request) => {
+                self.run_requests(VecDeque::from([request])).await;
+            }
+            ConnectionMessage::ClientClosed(id) => {
+                self.clients.remove(&id);
+                self.pubsub.remove_client(id);
+                self.blocked.remove_client(id);
+            }
+            ConnectionMessage::BlockTimeout { client_id, token } => {
+                self.block_timeout(client_id, token).await;
+            }
+        }
+    }
+
+    // Stop serving clients: the requests already received are run, then every
+    // connection, blocked ones included, is closed once the replies it's due
+    // are written. A last snapshot is saved if configured.
+    async fn shut_down(&mut self) {
+        while let Ok(message) = self.receiver.try_recv() {
+            self.receive(message).await;
+        }
+        for client in self.clients.values() {
+            // The connection may be waiting on the server, so don't wait on it
+            let sender = client.sender.clone();
+            tokio::spawn(async move { sender.send(ServerMessage::Close).await });
+        }
+
+        let drained = tokio::time::timeout(SHUTDOWN_TIMEOUT, async {
+            while !self.clients.is_empty() {
+                match self.receiver.recv().await {
+                    Some(ConnectionMessage::ClientClosed(id)) => {
+                        self.clients.remove(&id);
                     }
+                    // New connections and requests are turned down by
+                    // dropping them
+                    Some(_) => {}
+                    None => break,
                 }
             }
+        })
+        .await;
+        if drained.is_err() {
+            eprintln!("Timed out waiting for connections to close");
+        }
+
+        if let Some(task) = self.expiration_task.take() {
+            task.abort();
+        }
+        if let Some(task) = self.save_task.take() {
+            if let Err(e) = task.await {
+                eprintln!("Error waiting for the background save: {}", e);
+            }
+        }
+        if let Some(aof) = &mut self.aof {
+            if let Err(e) = aof.sync() {
+                eprintln!("Error syncing the append only file: {}", e);
+            }
+        }
+        if self.config.save_on_shutdown {
+            let path = self.config.snapshot_path();
+            if let Err(e) = Snapshot::take(&self.dbs).save(&path) {
+                eprintln!("Error saving snapshot to {}: {}", path.display(), e);
+            }
         }
     }
 
-    async fn handle_message(self: &Self, request: &Request) -> Result<(), ServerError> {
-        let elements = match &request.frame {
-            Frame::Array(frames) => frames,
-            _ => {
-                return Err(ServerError::CommandInvalidSyntax(
-                    "shold be RESP array of bulk strings".to_string(),
-                ))
+    // Handle the requests in order, along with the ones of the clients they
+    // unblock. Requests of blocked clients wait until they're unblocked.
+    pub(crate) async fn run_requests(&mut self, mut requests: VecDeque<Request>) {
+        while let Some(request) = requests.pop_front() {
+            let Some(request) = self.blocked.defer(request) else {
+                continue;
+            };
+            if let Err(e) = self.handle_message(&request).await {
+                request.error(e).await;
             }
-        };
+            if !self.blocked.is_empty() {
+                requests.extend(self.serve_blocked().await);
+            }
+        }
+    }
 
-        let mut command = Vec::new();
-        for elem in elements {
-            match elem {
-                Frame::Bulk(s) => command.push(String::from_utf8(s.to_vec()).map_err(|_| {
-                    ServerError::CommandInvalidSyntax("must be utf8 string?".to_string())
-                })?),
-                _ => {
-                    return Err(ServerError::CommandInvalidSyntax(
-                        "shold be RESP array of bulk strings".to_string(),
-                    ))
-                }
+    // Send the client its timeout reply if it's still blocked in the wait
+    // with this token, and go on with its requests
+    pub(crate) async fn block_timeout(&mut self, client_id: u64, token: u64) {
+        if let Some(client) = self.blocked.unblock(client_id, token) {
+            let _ = client
+                .connection
+                .send(ServerMessage::Data(client.timeout_reply))
+                .await;
+            self.run_requests(client.deferred).await;
+        }
+    }
+
+    // Serve the blocked clients which can pop now, the ones which blocked
+    // first getting the elements first. Returns the requests they sent while
+    // blocked.
+    async fn serve_blocked(&mut self) -> VecDeque<Request> {
+        let mut deferred = VecDeque::new();
+        loop {
+            let mut popped = None;
+            let dbs = &self.dbs;
+            let Some(client) = self.blocked.serve_first(|client| {
+                popped = client.keys.iter().find_map(|key| {
+                    match pop::pop(&dbs[client.db], key.clone(), 1, End::Left) {
+                        Ok(Some(mut values)) => Some((key.clone(), values.remove(0))),
+                        // Keys holding something else than a list are waited on
+                        _ => None,
+                    }
+                });
+                popped.is_some()
+            }) else {
+                return deferred;
+            };
+            let (key, value) = popped.unwrap();
+            self.log_frame(client.db, &cmd!("LPOP", key.clone()));
+            let reply = Frame::Array(vec![Frame::Bulk(key), Frame::Bulk(value)]);
+            let _ = client.connection.send(ServerMessage::Data(reply)).await;
+            deferred.extend(client.deferred);
+        }
+    }
+
+    // Whether the client sending the request may run commands, either because
+    // no password is required or because it sent the right one
+    fn authenticated(&self, request: &Request) -> bool {
+        self.config.requirepass.is_empty()
+            || self
+                .clients
+                .get(&request.client_id)
+                .is_some_and(|client| client.state.authenticated)
+    }
+
+    // Database selected by the client sending the request
+    pub fn db(&self, request: &Request) -> &Db {
+        let index = self
+            .clients
+            .get(&request.client_id)
+            .map_or(0, |client| client.state.db);
+        &self.dbs[index]
+    }
+
+    // (Re)start the task deleting expired keys, with the current configuration
+    pub(crate) fn restart_expiration_task(&mut self) {
+        if let Some(task) = self.expiration_task.take() {
+            task.abort();
+        }
+        self.expiration_task = Some(spawn_expiration_task(
+            self.dbs.clone(),
+            self.config.expiration,
+        ));
+    }
+
+    // Rebuild the keyspace on startup: by replaying the append only file when
+    // appendonly is set, which is then kept up to date, or else from the
+    // snapshot. Returns the file data was loaded from, if there was one.
+    pub async fn load(&mut self) -> io::Result<Option<PathBuf>> {
+        if !self.config.appendonly {
+            let path = self.config.snapshot_path();
+            let Some(snapshot) = Snapshot::load(&path)? else {
+                return Ok(None);
+            };
+            snapshot.restore(&self.dbs)?;
+            return Ok(Some(path));
+        }
+
+        let path = self.config.aof_path();
+        let commands = aof::read_commands(&path, self.config.limits)?;
+        let loaded = commands.is_some();
+        if let Some(commands) = commands {
+            self.replay(commands).await;
+        }
+        self.aof = Some(Aof::open(&path, self.config.appendfsync)?);
+        Ok(loaded.then_some(path))
+    }
+
+    // Run the logged commands as a client of their own, whose replies are
+    // dropped
+    async fn replay(&mut self, commands: Vec<Frame>) {
+        let (sender, mut receiver) = mpsc::channel(32);
+        let id = u64::MAX;
+        self.clients.insert(
+            id,
+            Client {
+                id,
+                sender: sender.clone(),
+                state: ConnectionState::default(),
+            },
+        );
+        for frame in commands {
+            let request = Request {
+                client_id: id,
+                frame,
+                connection: sender.clone(),
+            };
+            let result = match Command::from_frame(request.frame.clone()) {
+                Ok(command) => self.execute(&request, command).await,
+                Err(error) => Err(error.into()),
             };
+            if let Err(e) = result {
+                eprintln!("Error replaying command: {}", e);
+            }
+            while receiver.try_recv().is_ok() {}
+        }
+        self.clients.remove(&id);
+    }
+
+    // Save a snapshot of the databases in the background. The keyspace is
+    // copied right away, so later writes don't end up in the file. Returns
+    // false if another save is still running.
+    pub(crate) fn spawn_save(&mut self) -> bool {
+        if self
+            .save_task
+            .as_ref()
+            .is_some_and(|task| !task.is_finished())
+        {
+            return false;
+        }
+        let snapshot = Snapshot::take(&self.dbs);
+        let path = self.config.snapshot_path();
+        self.save_task = Some(tokio::task::spawn_blocking(move || {
+            if let Err(e) = snapshot.save(&path) {
+                eprintln!("Error saving snapshot to {}: {}", path.display(), e);
+            }
+        }));
+        true
+    }
+
+    // Commands sent by a client in a transaction are queued instead of being
+    // run, up to EXEC or DISCARD
+    pub(crate) async fn handle_message(&mut self, request: &Request) -> Result<(), ServerError> {
+        let command = Command::from_frame(request.frame.clone());
+        if let Ok(command) = &command {
+            if !self.authenticated(request)
+                && !matches!(
+                    command,
+                    Command::Auth { .. } | Command::Hello(_) | Command::Quit | Command::Reset
+                )
+            {
+                return Err(ServerError::NoAuth);
+            }
         }
+        let Some(transaction) = self
+            .clients
+            .get_mut(&request.client_id)
+            .and_then(|client| client.state.transaction.as_mut())
+        else {
+            return self.execute(request, command?).await;
+        };
 
-        if command.is_empty() {
-            return Err(ServerError::CommandInvalidSyntax(
-                "missing command name".to_string(),
-            ));
+        match command {
+            Ok(Command::Exec) => exec::command(self, request).await,
+            Ok(Command::Discard) => discard::command(self, request).await,
+            Ok(Command::Multi) => return Err(ServerError::NestedMulti),
+            Ok(Command::Watch(_)) => return Err(ServerError::WatchInMulti),
+            // The connection goes away along with the transaction
+            Ok(Command::Quit) => quit::command(self, request).await,
+            Ok(Command::Reset) => reset::command(self, request).await,
+            Ok(command) => {
+                transaction.queued.push((request.frame.clone(), command));
+                request.data(Frame::simple("QUEUED")).await;
+            }
+            // The transaction can't run if one of its commands is invalid
+            Err(error) => {
+                transaction.failed = true;
+                return Err(error.into());
+            }
         }
+        Ok(())
+    }
 
-        let command_name = command[0].to_lowercase();
+    // Run the command, replying to the request
+    pub(crate) async fn execute(
+        &mut self,
+        request: &Request,
+        command: Command,
+    ) -> Result<(), ServerError> {
+        let started = tokio::time::Instant::now();
+        let result = traced(request, self.dispatch(request, command)).await;
+        self.log_if_slow(request, started.elapsed());
+        result
+    }
 
-        match command_name.as_str() {
-            "echo" => echo::command(self, &request, &command).await,
-            "ping" => ping::command(self, &request, &command).await,
-            _ => return Err(ServerError::CommandNotAvailable(command_name)),
+    // Add the request to the slow log if it took at least
+    // slowlog-log-slower-than
+    fn log_if_slow(&mut self, request: &Request, duration: Duration) {
+        let Ok(threshold) = u128::try_from(self.config.slowlog_log_slower_than) else {
+            return;
         };
+        if duration.as_micros() < threshold {
+            return;
+        }
+        let Frame::Array(frames) = &request.frame else {
+            return;
+        };
+        let args: Vec<Bytes> = frames
+            .iter()
+            .filter_map(|frame| match frame {
+                Frame::Bulk(arg) => Some(arg.clone()),
+                _ => None,
+            })
+            .collect();
+        let max_len = usize::try_from(self.config.slowlog_max_len).unwrap_or(usize::MAX);
+        self.slowlog.record(&args, duration, max_len);
+    }
+
+    async fn dispatch(&mut self, request: &Request, command: Command) -> Result<(), ServerError> {
+        if command.denied_when_oom() && !self.make_room() {
+            return Err(ServerError::OutOfMemory);
+        }
+        // Along with the number of writes before the command, to only log it
+        // if it changed something
+        let logged = (self.aof.is_some() && command.is_write())
+            .then(|| aof::logged_frame(&command, &request.frame))
+            .flatten()
+            .map(|frame| (frame, self.writes()));
+        match command {
+            Command::Append { key, value } => append::command(self, request, key, value).await,
+            Command::Auth { username, password } => {
+                auth::command(self, request, username, password).await
+            }
+            Command::BgSave => save::background(self, request).await,
+            Command::BLPop { keys, timeout } => pop::blocking(self, request, keys, timeout).await,
+            Command::BitCount { key, range } => bitmap::count(self, request, key, range).await,
+            Command::CommandCount => commands::count(self, request).await,
+            Command::CommandList => commands::list(self, request).await,
+            Command::ConfigGet(pattern) => config::get(self, request, pattern).await,
+            Command::ConfigSet { parameter, value } => {
+                config::set(self, request, parameter, value).await
+            }
+            Command::Copy {
+                from,
+                to,
+                db,
+                replace,
+            } => copy::command(self, request, from, to, db, replace).await,
+            Command::DbSize => dbsize::command(self, request).await,
+            Command::DebugObject(key) => debug::object(self, request, key).await,
+            Command::DebugSleep(duration) => debug::sleep(self, request, duration).await,
+            Command::Decr(key) => incr::command(self, request, key, -1).await,
+            Command::Del(keys) => del::command(self, request, keys).await,
+            Command::Discard => return Err(ServerError::DiscardWithoutMulti),
+            Command::Echo(message) => echo::command(self, request, message).await,
+            Command::Exec => return Err(ServerError::ExecWithoutMulti),
+            Command::Exists(keys) => exists::command(self, request, keys).await,
+            Command::Expire { key, seconds } => expire::command(self, request, key, seconds).await,
+            Command::ExpireAt { key, unix_millis } => {
+                expire::at(self, request, key, unix_millis).await
+            }
+            Command::FlushAll => flush::all(self, request).await,
+            Command::FlushDb => flush::db(self, request).await,
+            Command::Get(key) => get::command(self, request, key).await,
+            Command::GetBit { key, offset } => bitmap::get(self, request, key, offset).await,
+            Command::GetDel(key) => getdel::command(self, request, key).await,
+            Command::GetEx { key, expire } => getex::command(self, request, key, expire).await,
+            Command::GetRange { key, start, end } => {
+                getrange::command(self, request, key, start, end).await
+            }
+            Command::HDel { key, fields } => hdel::command(self, request, key, fields).await,
+            Command::Hello(protover) => hello::command(self, request, protover).await,
+            Command::HGet { key, field } => hget::command(self, request, key, field).await,
+            Command::HGetAll(key) => hgetall::command(self, request, key).await,
+            Command::HIncrBy { key, field, delta } => {
+                hincrby::command(self, request, key, field, delta).await
+            }
+            Command::HIncrByFloat { key, field, delta } => {
+                hincrby::float(self, request, key, field, delta).await
+            }
+            Command::HSet { key, fields } => hset::command(self, request, key, fields).await,
+            Command::Incr(key) => incr::command(self, request, key, 1).await,
+            Command::IncrBy { key, delta } => incr::command(self, request, key, delta).await,
+            Command::Info(section) => info::command(self, request, section).await,
+            Command::Keys(pattern) => keys::command(self, request, pattern).await,
+            Command::LLen(key) => llen::command(self, request, key).await,
+            Command::LPop { key, count } => {
+                pop::command(self, request, key, count, End::Left).await
+            }
+            Command::LPush { key, values } => {
+                push::command(self, request, key, values, End::Left).await
+            }
+            Command::LRange { key, start, stop } => {
+                lrange::command(self, request, key, start, stop).await
+            }
+            Command::MGet(keys) => mget::command(self, request, keys).await,
+            Command::MSet(pairs) => mset::command(self, request, pairs).await,
+            Command::Multi => multi::command(self, request).await,
+            Command::ObjectEncoding(key) => object::encoding(self, request, key).await,
+            Command::Persist(key) => persist::command(self, request, key).await,
+            Command::Ping(message) => ping::command(self, request, message).await,
+            Command::PSubscribe(patterns) => psubscribe::command(self, request, patterns).await,
+            Command::Pttl(key) => pttl::command(self, request, key).await,
+            Command::Publish { channel, message } => {
+                publish::command(self, request, channel, message).await
+            }
+            Command::Quit => quit::command(self, request).await,
+            Command::Reset => reset::command(self, request).await,
+            Command::Rename { from, to } => rename::command(self, request, from, to).await,
+            Command::RenameNx { from, to } => rename::nx(self, request, from, to).await,
+            Command::RPop { key, count } => {
+                pop::command(self, request, key, count, End::Right).await
+            }
+            Command::RPush { key, values } => {
+                push::command(self, request, key, values, End::Right).await
+            }
+            Command::SAdd { key, members } => sadd::command(self, request, key, members).await,
+            Command::Save => save::command(self, request).await,
+            Command::Scan {
+                cursor,
+                pattern,
+                count,
+            } => scan::command(self, request, cursor, pattern, count).await,
+            Command::SDiff(keys) => setop::command(self, request, SetOp::Diff, keys).await,
+            Command::SDiffStore { destination, keys } => {
+                setop::store(self, request, SetOp::Diff, destination, keys).await
+            }
+            Command::Select(index) => select::command(self, request, index).await,
+            Command::Set {
+                key,
+                value,
+                options,
+            } => set::command(self, request, key, value, options).await,
+            Command::SetBit { key, offset, value } => {
+                bitmap::set(self, request, key, offset, value).await
+            }
+            Command::SetNx { key, value } => setnx::command(self, request, key, value).await,
+            Command::SetRange { key, offset, value } => {
+                setrange::command(self, request, key, offset, value).await
+            }
+            Command::SInter(keys) => setop::command(self, request, SetOp::Inter, keys).await,
+            Command::SInterStore { destination, keys } => {
+                setop::store(self, request, SetOp::Inter, destination, keys).await
+            }
+            Command::SIsMember { key, member } => {
+                sismember::command(self, request, key, member).await
+            }
+            Command::SlowLogGet(count) => slowlog::get(self, request, count).await,
+            Command::SlowLogLen => slowlog::len(self, request).await,
+            Command::SlowLogReset => slowlog::reset(self, request).await,
+            Command::SMembers(key) => smembers::command(self, request, key).await,
+            Command::SRem { key, members } => srem::command(self, request, key, members).await,
+            Command::StrLen(key) => strlen::command(self, request, key).await,
+            Command::Subscribe(channels) => subscribe::command(self, request, channels).await,
+            Command::SUnion(keys) => setop::command(self, request, SetOp::Union, keys).await,
+            Command::SUnionStore { destination, keys } => {
+                setop::store(self, request, SetOp::Union, destination, keys).await
+            }
+            Command::Ttl(key) => ttl::command(self, request, key).await,
+            Command::Type(key) => keytype::command(self, request, key).await,
+            Command::Unsubscribe(channels) => unsubscribe::command(self, request, channels).await,
+            Command::Unwatch => watch::unwatch(self, request).await,
+            Command::Wait => wait::command(self, request).await,
+            Command::Watch(keys) => watch::command(self, request, keys).await,
+            Command::ZAdd { key, members } => zadd::command(self, request, key, members).await,
+            Command::ZRange {
+                key,
+                start,
+                stop,
+                with_scores,
+            } => zrange::command(self, request, key, start, stop, with_scores).await,
+            Command::ZScore { key, member } => zscore::command(self, request, key, member).await,
+        };
+        if let Some((frame, writes)) = logged {
+            if self.writes() != writes {
+                self.log_write(request, &frame);
+            }
+        }
         Ok(())
     }
+
+    // Number of writes made to every database so far
+    fn writes(&self) -> u64 {
+        self.dbs.iter().map(Db::writes).sum()
+    }
+
+    // Evict keys until the keyspace fits in maxmemory again. Returns false if
+    // the policy doesn't allow evicting enough of them. Evicted keys are
+    // logged as deleted, so that replaying the log gives the same keyspace.
+    fn make_room(&mut self) -> bool {
+        let limit = self.config.maxmemory as usize;
+        if limit == 0 {
+            return true;
+        }
+        while self.dbs.iter().map(Db::used_memory).sum::<usize>() > limit {
+            let Some((db, key)) = evict(&self.dbs, self.config.maxmemory_policy) else {
+                return false;
+            };
+            self.log_frame(db, &cmd!("DEL", key));
+        }
+        true
+    }
+
+    // Log the write made by the request, as the given command, in the
+    // client's database
+    pub(crate) fn log_write(&mut self, request: &Request, frame: &Frame) {
+        let db = self
+            .clients
+            .get(&request.client_id)
+            .map_or(0, |client| client.state.db);
+        self.log_frame(db, frame);
+    }
+
+    fn log_frame(&mut self, db: usize, frame: &Frame) {
+        let Some(aof) = self.aof.as_mut() else {
+            return;
+        };
+        if let Err(e) = aof.append(db, frame) {
+            eprintln!("Error writing to the append only file: {}", e);
+        }
+    }
+}
+
+// Run the command in a span of its own, recording how long it took
+#[cfg(feature = "tracing")]
+async fn traced<T>(request: &Request, execution: impl Future<Output = T>) -> T {
+    use tracing::Instrument;
+
+    let span = tracing::info_span!(
+        "command",
+        client_id = request.client_id,
+        name = %command_name(&request.frame),
+    );
+    let started = Instant::now();
+    let result = execution.instrument(span.clone()).await;
+    span.in_scope(|| {
+        tracing::info!(
+            latency_us = started.elapsed().as_micros() as u64,
+            "command executed"
+        )
+    });
+    result
+}
+
+#[cfg(not(feature = "tracing"))]
+async fn traced<T>(_request: &Request, execution: impl Future<Output = T>) -> T {
+    execution.await
+}
+
+#[cfg(feature = "tracing")]
+fn command_name(frame: &Frame) -> String {
+    match frame {
+        Frame::Array(frames) => match frames.first() {
+            Some(Frame::Bulk(name)) => String::from_utf8_lossy(name).to_uppercase(),
+            _ => String::new(),
+        },
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        cmd,
+        command::{send_command, setup_command_test, temporary_dir},
+        config::MaxMemoryPolicy,
+        messages::ServerMessage,
+        resp::types::Frame,
+        server::{Server, ServerError},
+        snapshot::Snapshot,
+        store::{Db, Value},
+    };
+
+    fn used_memory(dbs: &[Db]) -> usize {
+        dbs.iter().map(Db::used_memory).sum()
+    }
+
+    #[tokio::test]
+    async fn test_maxmemory_evicts_lru() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("set"));
+        server.config.maxmemory = 2000;
+        server.config.maxmemory_policy = MaxMemoryPolicy::AllKeysLru;
+
+        for i in 0..100 {
+            let set = cmd!("SET", format!("key:{}", i), "value");
+            assert_eq!(
+                ServerMessage::Data(Frame::ok()),
+                send_command(&mut server, &mut receiver, &request, set).await
+            );
+        }
+
+        let keys = server.db(&request).len();
+        assert!(keys > 0 && keys < 100, "{} keys left", keys);
+        // Eviction happens before each write, which may go past the limit
+        assert!(used_memory(&server.dbs) <= 2000 + 100);
+        assert!(server.db(&request).get(b"key:99").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_evictions_are_logged() {
+        let dir = temporary_dir("evictions");
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("set"));
+        server.config.appendonly = true;
+        server.config.dir = dir.to_string_lossy().into_owned();
+        server.config.maxmemory = 2000;
+        server.config.maxmemory_policy = MaxMemoryPolicy::AllKeysRandom;
+        server.load().await.unwrap();
+
+        for i in 0..100 {
+            let set = cmd!("SET", format!("key:{}", i), "value");
+            send_command(&mut server, &mut receiver, &request, set).await;
+        }
+        let mut expected = server.dbs[0].keys(b"*");
+        assert!(expected.len() < 100);
+
+        let mut restarted = Server::with_config("0.0.0.0".into(), 0, server.config.clone());
+        drop(server);
+        restarted.load().await.unwrap();
+
+        let mut keys = restarted.dbs[0].keys(b"*");
+        expected.sort();
+        keys.sort();
+        assert_eq!(expected, keys);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_maxmemory_noeviction_fails_writes() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("set"));
+        server.config.maxmemory = 2000;
+
+        let mut replies = Vec::new();
+        for i in 0..100 {
+            let set = cmd!("SET", format!("key:{}", i), "value");
+            replies.push(send_command(&mut server, &mut receiver, &request, set).await);
+        }
+
+        let stored = server.db(&request).len();
+        assert!(stored < 100);
+        assert!(replies[..stored]
+            .iter()
+            .all(|reply| *reply == ServerMessage::Data(Frame::ok())));
+        assert!(replies[stored..]
+            .iter()
+            .all(|reply| *reply == ServerMessage::Error(ServerError::OutOfMemory)));
+        // Reads and deletions still go through
+        assert_eq!(
+            ServerMessage::Data(Frame::Bulk(Bytes::from("value"))),
+            send_command(&mut server, &mut receiver, &request, cmd!("GET", "key:0")).await
+        );
+        assert_eq!(
+            ServerMessage::Data(Frame::Integer(1)),
+            send_command(&mut server, &mut receiver, &request, cmd!("DEL", "key:0")).await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_saves_snapshot() {
+        let dir = temporary_dir("shutdown");
+        let mut server = Server::new("127.0.0.1".into(), 0);
+        server.config.dir = dir.to_string_lossy().into_owned();
+        server.config.save_on_shutdown = true;
+        server.dbs[0].set(Bytes::from("key"), Value::String(Bytes::from("value")));
+
+        server.shutdown.cancel();
+        server.run().await;
+
+        let dbs = vec![Db::new()];
+        Snapshot::load(&server.config.snapshot_path())
+            .unwrap()
+            .expect("a snapshot should be saved")
+            .restore(&dbs)
+            .unwrap();
+        assert_eq!(
+            Some(Value::String(Bytes::from("value"))),
+            dbs[0].get(b"key")
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_command_traced() {
+        let (mut server, mut receiver, request) = setup_command_test(cmd!("get"));
+
+        send_command(&mut server, &mut receiver, &request, cmd!("get", "key")).await;
+
+        assert!(logs_contain("command{client_id=0 name=GET}"));
+        assert!(logs_contain("command executed latency_us="));
+    }
 }
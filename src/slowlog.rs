@@ -0,0 +1,145 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+
+// Arguments kept for each command, and bytes kept for each argument, the
+// rest being summed up in the last one like Redis does
+const MAX_ARGS: usize = 32;
+const MAX_ARG_LEN: usize = 128;
+
+// A command which took longer than slowlog-log-slower-than
+#[derive(Clone, Debug, PartialEq)]
+pub struct SlowLogEntry {
+    pub id: u64,
+    // Unix time the command was logged at, in seconds
+    pub timestamp: u64,
+    pub duration: Duration,
+    pub args: Vec<Bytes>,
+}
+
+// The slowest recent commands, newest first, up to slowlog-max-len of them
+#[derive(Debug, Default)]
+pub struct SlowLog {
+    entries: VecDeque<SlowLogEntry>,
+    next_id: u64,
+}
+
+impl SlowLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Log the command, dropping the oldest entries beyond `max_len`
+    pub fn record(&mut self, args: &[Bytes], duration: Duration, max_len: usize) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs());
+        self.entries.push_front(SlowLogEntry {
+            id: self.next_id,
+            timestamp,
+            duration,
+            args: truncate_args(args),
+        });
+        self.next_id += 1;
+        self.entries.truncate(max_len);
+    }
+
+    // The `count` newest entries
+    pub fn newest(&self, count: usize) -> impl Iterator<Item = &SlowLogEntry> {
+        self.entries.iter().take(count)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Drop the entries, ids keep counting up
+    pub fn reset(&mut self) {
+        self.entries.clear();
+    }
+}
+
+fn truncate_args(args: &[Bytes]) -> Vec<Bytes> {
+    let kept = if args.len() > MAX_ARGS {
+        MAX_ARGS - 1
+    } else {
+        args.len()
+    };
+    let mut truncated: Vec<Bytes> = args[..kept]
+        .iter()
+        .map(|arg| match arg.len() {
+            len if len > MAX_ARG_LEN => {
+                let mut shortened = arg[..MAX_ARG_LEN].to_vec();
+                shortened.extend_from_slice(
+                    format!("... ({} more bytes)", len - MAX_ARG_LEN).as_bytes(),
+                );
+                Bytes::from(shortened)
+            }
+            _ => arg.clone(),
+        })
+        .collect();
+    if kept < args.len() {
+        truncated.push(Bytes::from(format!(
+            "... ({} more arguments)",
+            args.len() - kept
+        )));
+    }
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use super::SlowLog;
+
+    #[test]
+    fn test_record() {
+        let mut slowlog = SlowLog::new();
+        for name in ["a", "b", "c"] {
+            slowlog.record(&[Bytes::from(name)], Duration::from_millis(20), 2);
+        }
+
+        // The oldest entry went beyond the maximum length
+        let entries: Vec<_> = slowlog.newest(10).collect();
+        assert_eq!(2, entries.len());
+        assert_eq!(
+            (2, vec![Bytes::from("c")]),
+            (entries[0].id, entries[0].args.clone())
+        );
+        assert_eq!(1, entries[1].id);
+        assert_eq!(Duration::from_millis(20), entries[1].duration);
+
+        slowlog.reset();
+        assert!(slowlog.is_empty());
+        slowlog.record(&[Bytes::from("d")], Duration::ZERO, 2);
+        assert_eq!(3, slowlog.newest(1).next().unwrap().id);
+    }
+
+    #[test]
+    fn test_record_truncates_args() {
+        let mut slowlog = SlowLog::new();
+        let mut args = vec![Bytes::from(vec![b'x'; 130])];
+        args.extend((0..39).map(|i| Bytes::from(i.to_string())));
+
+        slowlog.record(&args, Duration::ZERO, 1);
+
+        let args = &slowlog.newest(1).next().unwrap().args;
+        assert_eq!(32, args.len());
+        assert_eq!(
+            Bytes::from(format!("{}... (2 more bytes)", "x".repeat(128))),
+            args[0]
+        );
+        assert_eq!(Bytes::from("29"), args[30]);
+        assert_eq!(Bytes::from("... (9 more arguments)"), args[31]);
+    }
+}
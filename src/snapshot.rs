@@ -0,0 +1,424 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+
+use crate::store::{sorted_set::SortedSet, Db, Value};
+
+// Snapshot files start with the magic string followed by the format version.
+// Then, for each database holding keys, its index and its entries follow,
+// up to the end marker. An entry is its optional expiration (a deadline in
+// milliseconds since the epoch, so time spent offline counts), the value
+// type, the key and the value. Lengths and numbers are big endian.
+const MAGIC: &[u8] = b"YARRS";
+const VERSION: u8 = 1;
+
+const SELECT_DB: u8 = 0xfe;
+const EXPIRE_AT: u8 = 0xfd;
+const END: u8 = 0xff;
+
+const STRING: u8 = 0;
+const LIST: u8 = 1;
+const HASH: u8 = 2;
+const SET: u8 = 3;
+const SORTED_SET: u8 = 4;
+
+// A key as stored in the snapshot, with its expiration deadline in
+// milliseconds since the epoch
+type Entry = (Bytes, Value, Option<u64>);
+
+// Content of every database, taken at one point in time so it can be written
+// out while the server keeps changing the keyspace
+#[derive(Debug, Default, PartialEq)]
+pub struct Snapshot {
+    // Entries of each database, by index
+    dbs: Vec<Vec<Entry>>,
+}
+
+impl Snapshot {
+    pub fn take(dbs: &[Db]) -> Self {
+        let now = unix_millis(SystemTime::now());
+        let dbs = dbs
+            .iter()
+            .map(|db| {
+                db.entries()
+                    .into_iter()
+                    .map(|(key, value, ttl)| {
+                        let deadline = ttl.map(|ttl| now.saturating_add(ttl.as_millis() as u64));
+                        (key, value, deadline)
+                    })
+                    .collect()
+            })
+            .collect();
+        Self { dbs }
+    }
+
+    // Write the snapshot to a file next to the destination, then move it in
+    // place, so a failed save doesn't leave a broken file behind
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        let mut writer = BufWriter::new(File::create(&temporary)?);
+        self.encode(&mut writer)?;
+        writer.into_inner()?.sync_all()?;
+        std::fs::rename(&temporary, path)
+    }
+
+    // Read the snapshot at path, None when there is no such file
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        match File::open(path) {
+            Ok(file) => Self::decode(&mut BufReader::new(file)).map(Some),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Fill the databases with the entries of the snapshot. Keys whose
+    // deadline passed in the meantime are left out.
+    pub fn restore(self, dbs: &[Db]) -> io::Result<()> {
+        if self.dbs.len() > dbs.len() && self.dbs[dbs.len()..].iter().any(|db| !db.is_empty()) {
+            return Err(invalid_data("snapshot has more databases than configured"));
+        }
+        let now = unix_millis(SystemTime::now());
+        for (db, entries) in dbs.iter().zip(self.dbs) {
+            for (key, value, deadline) in entries {
+                match deadline {
+                    None => {
+                        db.set(key, value);
+                    }
+                    Some(deadline) if deadline > now => {
+                        db.set(key.clone(), value);
+                        db.expire(&key, Duration::from_millis(deadline - now));
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn encode(&self, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(MAGIC)?;
+        out.write_all(&[VERSION])?;
+        for (index, entries) in self.dbs.iter().enumerate() {
+            if entries.is_empty() {
+                continue;
+            }
+            out.write_all(&[SELECT_DB])?;
+            write_len(out, index)?;
+            for (key, value, deadline) in entries {
+                if let Some(deadline) = deadline {
+                    out.write_all(&[EXPIRE_AT])?;
+                    out.write_all(&deadline.to_be_bytes())?;
+                }
+                write_value(out, key, value)?;
+            }
+        }
+        out.write_all(&[END])
+    }
+
+    fn decode(input: &mut impl Read) -> io::Result<Self> {
+        let mut magic = [0; MAGIC.len()];
+        input.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(invalid_data("not a snapshot file"));
+        }
+        if read_u8(input)? != VERSION {
+            return Err(invalid_data("unsupported snapshot version"));
+        }
+
+        let mut snapshot = Snapshot::default();
+        let mut db = None;
+        loop {
+            let mut opcode = read_u8(input)?;
+            match opcode {
+                END => return Ok(snapshot),
+                SELECT_DB => {
+                    let index = read_len(input)?;
+                    if snapshot.dbs.len() <= index {
+                        snapshot.dbs.resize_with(index + 1, Vec::new);
+                    }
+                    db = Some(index);
+                    continue;
+                }
+                _ => {}
+            }
+            let Some(index) = db else {
+                return Err(invalid_data("entry outside of a database"));
+            };
+            let mut deadline = None;
+            if opcode == EXPIRE_AT {
+                let mut bytes = [0; 8];
+                input.read_exact(&mut bytes)?;
+                deadline = Some(u64::from_be_bytes(bytes));
+                opcode = read_u8(input)?;
+            }
+            let key = read_bytes(input)?;
+            let value = read_value(input, opcode)?;
+            snapshot.dbs[index].push((key, value, deadline));
+        }
+    }
+}
+
+fn write_value(out: &mut impl Write, key: &[u8], value: &Value) -> io::Result<()> {
+    let kind = match value {
+        Value::String(_) => STRING,
+        Value::List(_) => LIST,
+        Value::Hash(_) => HASH,
+        Value::Set(_) => SET,
+        Value::SortedSet(_) => SORTED_SET,
+    };
+    out.write_all(&[kind])?;
+    write_bytes(out, key)?;
+    match value {
+        Value::String(bytes) => write_bytes(out, bytes),
+        Value::List(items) => {
+            write_len(out, items.len())?;
+            items.iter().try_for_each(|item| write_bytes(out, item))
+        }
+        Value::Hash(fields) => {
+            write_len(out, fields.len())?;
+            fields.iter().try_for_each(|(field, value)| {
+                write_bytes(out, field)?;
+                write_bytes(out, value)
+            })
+        }
+        Value::Set(members) => {
+            write_len(out, members.len())?;
+            members
+                .iter()
+                .try_for_each(|member| write_bytes(out, member))
+        }
+        Value::SortedSet(set) => {
+            write_len(out, set.len())?;
+            set.iter().try_for_each(|(member, score)| {
+                out.write_all(&score.to_bits().to_be_bytes())?;
+                write_bytes(out, member)
+            })
+        }
+    }
+}
+
+fn read_value(input: &mut impl Read, kind: u8) -> io::Result<Value> {
+    let value = match kind {
+        STRING => Value::String(read_bytes(input)?),
+        LIST => {
+            let len = read_len(input)?;
+            let items = (0..len)
+                .map(|_| read_bytes(input))
+                .collect::<io::Result<VecDeque<_>>>()?;
+            Value::List(items)
+        }
+        HASH => {
+            let len = read_len(input)?;
+            let fields = (0..len)
+                .map(|_| Ok((read_bytes(input)?, read_bytes(input)?)))
+                .collect::<io::Result<HashMap<_, _>>>()?;
+            Value::Hash(fields)
+        }
+        SET => {
+            let len = read_len(input)?;
+            let members = (0..len)
+                .map(|_| read_bytes(input))
+                .collect::<io::Result<HashSet<_>>>()?;
+            Value::Set(members)
+        }
+        SORTED_SET => {
+            let len = read_len(input)?;
+            let mut set = SortedSet::new();
+            for _ in 0..len {
+                let mut score = [0; 8];
+                input.read_exact(&mut score)?;
+                set.insert(
+                    read_bytes(input)?,
+                    f64::from_bits(u64::from_be_bytes(score)),
+                );
+            }
+            Value::SortedSet(set)
+        }
+        _ => return Err(invalid_data("unknown value type")),
+    };
+    Ok(value)
+}
+
+fn write_len(out: &mut impl Write, len: usize) -> io::Result<()> {
+    let len = u32::try_from(len).map_err(|_| invalid_data("length too large"))?;
+    out.write_all(&len.to_be_bytes())
+}
+
+fn write_bytes(out: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    write_len(out, bytes.len())?;
+    out.write_all(bytes)
+}
+
+fn read_u8(input: &mut impl Read) -> io::Result<u8> {
+    let mut byte = [0];
+    input.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn read_len(input: &mut impl Read) -> io::Result<usize> {
+    let mut len = [0; 4];
+    input.read_exact(&mut len)?;
+    Ok(u32::from_be_bytes(len) as usize)
+}
+
+// Lengths come from the file, so the buffer grows as the bytes are actually
+// read rather than being allocated upfront
+fn read_bytes(input: &mut impl Read) -> io::Result<Bytes> {
+    let len = read_len(input)?;
+    let mut bytes = Vec::new();
+    input.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(Bytes::from(bytes))
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as u64)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{HashMap, HashSet, VecDeque},
+        io,
+        path::PathBuf,
+        time::Duration,
+    };
+
+    use bytes::Bytes;
+
+    use super::Snapshot;
+    use crate::{
+        command::temporary_dir,
+        store::{sorted_set::SortedSet, Db, Value},
+    };
+
+    fn temporary_path(name: &str) -> PathBuf {
+        temporary_dir(name).join("dump.rdb")
+    }
+
+    fn sorted(db: &Db) -> Vec<(Bytes, Value, Option<Duration>)> {
+        let mut entries = db.entries();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries
+    }
+
+    #[test]
+    fn test_save_load_every_type() {
+        let dbs = vec![Db::new(), Db::new(), Db::new()];
+        let mut scores = SortedSet::new();
+        scores.insert(Bytes::from("a"), 1.5);
+        scores.insert(Bytes::from("b"), f64::NEG_INFINITY);
+        dbs[0].set(
+            Bytes::from("string"),
+            Value::String(Bytes::from_static(b"\x00\xff")),
+        );
+        dbs[0].set(
+            Bytes::from("list"),
+            Value::List(VecDeque::from([Bytes::from("a"), Bytes::from("b")])),
+        );
+        dbs[0].set(
+            Bytes::from("hash"),
+            Value::Hash(HashMap::from([(
+                Bytes::from("field"),
+                Bytes::from("value"),
+            )])),
+        );
+        dbs[0].set(
+            Bytes::from("set"),
+            Value::Set(HashSet::from([Bytes::from("a"), Bytes::from("")])),
+        );
+        dbs[0].set(Bytes::from("zset"), Value::SortedSet(scores));
+        dbs[2].set(Bytes::from("other"), Value::String(Bytes::from("db")));
+        let path = temporary_path("every-type");
+
+        Snapshot::take(&dbs).save(&path).unwrap();
+        let loaded = vec![Db::new(), Db::new(), Db::new()];
+        Snapshot::load(&path)
+            .unwrap()
+            .unwrap()
+            .restore(&loaded)
+            .unwrap();
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        for (db, loaded) in dbs.iter().zip(loaded.iter()) {
+            assert_eq!(sorted(db), sorted(loaded));
+        }
+    }
+
+    #[test]
+    fn test_save_load_expirations() {
+        let dbs = vec![Db::new()];
+        dbs[0].set(Bytes::from("expiring"), Value::String(Bytes::from("a")));
+        dbs[0].expire(b"expiring", Duration::from_secs(100));
+        dbs[0].set(Bytes::from("persistent"), Value::String(Bytes::from("b")));
+        let path = temporary_path("expirations");
+
+        Snapshot::take(&dbs).save(&path).unwrap();
+        let loaded = vec![Db::new()];
+        Snapshot::load(&path)
+            .unwrap()
+            .unwrap()
+            .restore(&loaded)
+            .unwrap();
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        let ttl = loaded[0].ttl(b"expiring").unwrap().unwrap();
+        assert!(ttl <= Duration::from_secs(100) && ttl > Duration::from_secs(90));
+        assert_eq!(Some(None), loaded[0].ttl(b"persistent"));
+    }
+
+    #[test]
+    fn test_restore_skips_passed_deadlines() {
+        let snapshot = Snapshot {
+            dbs: vec![vec![(
+                Bytes::from("key"),
+                Value::String(Bytes::from("a")),
+                Some(1),
+            )]],
+        };
+        let db = Db::new();
+
+        snapshot.restore(std::slice::from_ref(&db)).unwrap();
+
+        assert!(db.is_empty());
+    }
+
+    #[test]
+    fn test_load_missing() {
+        let path = temporary_path("missing");
+        assert_eq!(None, Snapshot::load(&path).unwrap());
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        let mut valid = Vec::new();
+        let db = Db::new();
+        db.set(Bytes::from("key"), Value::String(Bytes::from("value")));
+        Snapshot::take(std::slice::from_ref(&db))
+            .encode(&mut valid)
+            .unwrap();
+
+        for invalid in [&b"REDIS"[..], &valid[..valid.len() - 3], b"YARRS\x01\x00"] {
+            let error = Snapshot::decode(&mut &invalid[..]).unwrap_err();
+            assert!(matches!(
+                error.kind(),
+                io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof
+            ));
+        }
+    }
+}
@@ -0,0 +1,1198 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    hash::{BuildHasher, DefaultHasher, Hash, Hasher, RandomState},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use tokio::{task::JoinHandle, time::Instant};
+
+use crate::{config::MaxMemoryPolicy, glob};
+
+pub mod sorted_set;
+
+use sorted_set::SortedSet;
+
+// Value stored under a key, one variant for each type
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    String(Bytes),
+    List(VecDeque<Bytes>),
+    Hash(HashMap<Bytes, Bytes>),
+    Set(HashSet<Bytes>),
+    SortedSet(SortedSet),
+}
+
+impl Value {
+    // Rough number of bytes the value takes in memory. Collections are
+    // estimated out of a few of their elements, so it takes the same time
+    // whatever their size.
+    pub fn memory_usage(&self) -> usize {
+        // Bookkeeping for each element of a collection
+        const OVERHEAD: usize = 16;
+        match self {
+            Value::String(bytes) => bytes.len(),
+            Value::List(list) => {
+                estimate(list.len(), list.iter().map(|item| item.len() + OVERHEAD))
+            }
+            Value::Hash(hash) => estimate(
+                hash.len(),
+                hash.iter()
+                    .map(|(field, value)| field.len() + value.len() + OVERHEAD),
+            ),
+            Value::Set(set) => {
+                estimate(set.len(), set.iter().map(|member| member.len() + OVERHEAD))
+            }
+            Value::SortedSet(set) => estimate(
+                set.len(),
+                set.iter()
+                    .map(|(member, _)| 2 * member.len() + 8 + OVERHEAD),
+            ),
+        }
+    }
+
+    // How Redis would store the value, as replied by OBJECT ENCODING: small
+    // collections are packed, larger ones use their own structure
+    pub fn encoding(&self) -> &'static str {
+        // Like the default set-max-intset-entries
+        const INTSET_ENTRIES: usize = 512;
+        match self {
+            Value::String(bytes) if is_integer(bytes) => "int",
+            // Short strings are allocated along with their object
+            Value::String(bytes) if bytes.len() <= 44 => "embstr",
+            Value::String(_) => "raw",
+            Value::List(list) if is_packed(list.len(), list.iter().map(Bytes::len)) => "listpack",
+            Value::List(_) => "quicklist",
+            Value::Hash(hash)
+                if is_packed(
+                    hash.len(),
+                    hash.iter().flat_map(|(f, v)| [f.len(), v.len()]),
+                ) =>
+            {
+                "listpack"
+            }
+            Value::Set(set)
+                if set.len() <= INTSET_ENTRIES && set.iter().all(|member| is_integer(member)) =>
+            {
+                "intset"
+            }
+            Value::Set(set) if is_packed(set.len(), set.iter().map(Bytes::len)) => "listpack",
+            Value::Hash(_) | Value::Set(_) => "hashtable",
+            Value::SortedSet(set)
+                if is_packed(set.len(), set.iter().map(|(member, _)| member.len())) =>
+            {
+                "listpack"
+            }
+            Value::SortedSet(_) => "skiplist",
+        }
+    }
+
+    // Name of the type, as replied by TYPE
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::List(_) => "list",
+            Value::Hash(_) => "hash",
+            Value::Set(_) => "set",
+            Value::SortedSet(_) => "zset",
+        }
+    }
+}
+
+// Whether the bytes are an integer written the way Redis would write it back,
+// so "12" is one but "012" and "+12" aren't
+fn is_integer(bytes: &[u8]) -> bool {
+    str::from_utf8(bytes)
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok().map(|n| n.to_string() == s))
+        .unwrap_or(false)
+}
+
+// Whether a collection would fit a listpack, with the default
+// *-max-listpack-entries and *-max-listpack-value
+fn is_packed(len: usize, mut sizes: impl Iterator<Item = usize>) -> bool {
+    len <= 128 && sizes.all(|size| size <= 64)
+}
+
+// Size of a collection of `len` elements, extrapolated from the size of its
+// first elements
+fn estimate(len: usize, sizes: impl Iterator<Item = usize>) -> usize {
+    const SAMPLES: usize = 8;
+    let sampled: usize = sizes.take(SAMPLES).sum();
+    if len <= SAMPLES {
+        sampled
+    } else {
+        sampled / SAMPLES * len
+    }
+}
+
+struct Entry {
+    value: Value,
+    expires_at: Option<Instant>,
+    // Last time the key was read or written, for LRU eviction
+    accessed_at: Instant,
+    // Bumped on every write, so WATCH can tell the key changed
+    version: u64,
+}
+
+impl Entry {
+    fn new(value: Value, expires_at: Option<Instant>) -> Self {
+        Self {
+            value,
+            expires_at,
+            accessed_at: Instant::now(),
+            version: 0,
+        }
+    }
+
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+}
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<Bytes, Entry>,
+    // Keys with an expiration, ordered by when they expire
+    expirations: BTreeSet<(Instant, Bytes)>,
+    // Every key, ordered by its hash, which SCAN cursors point into
+    scan_order: BTreeSet<(u64, Bytes)>,
+    // Estimated bytes taken by the keys and their values
+    memory: usize,
+    // Number of writes so far, deletions included. Also the version of the
+    // last written key.
+    writes: u64,
+}
+
+// Position of a key in the SCAN order. The hasher has fixed keys, so it
+// doesn't change while the server runs.
+fn scan_hash(key: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Memory accounted for a key and its value
+fn entry_size(key: &[u8], value: &Value) -> usize {
+    // Bookkeeping for each key
+    const OVERHEAD: usize = 64;
+    key.len() + value.memory_usage() + OVERHEAD
+}
+
+impl State {
+    // The entry for the key, deleting it first if it already expired.
+    // Getting it counts as an access.
+    fn live_entry(&mut self, key: &[u8]) -> Option<&mut Entry> {
+        let now = Instant::now();
+        if self
+            .entries
+            .get(key)
+            .is_some_and(|entry| entry.is_expired(now))
+        {
+            self.remove(key);
+        }
+        let entry = self.entries.get_mut(key)?;
+        entry.accessed_at = now;
+        Some(entry)
+    }
+
+    fn insert(&mut self, key: Bytes, mut entry: Entry) -> Option<Entry> {
+        self.writes += 1;
+        entry.version = self.writes;
+        self.put(key, entry)
+    }
+
+    // Insert the entry as is, keeping its version
+    fn put(&mut self, key: Bytes, entry: Entry) -> Option<Entry> {
+        if let Some(at) = entry.expires_at {
+            self.expirations.insert((at, key.clone()));
+        }
+        self.scan_order.insert((scan_hash(&key), key.clone()));
+        self.memory += entry_size(&key, &entry.value);
+        let previous = self.entries.insert(key.clone(), entry)?;
+        self.memory -= entry_size(&key, &previous.value);
+        if let Some(at) = previous.expires_at {
+            self.expirations.remove(&(at, key));
+        }
+        Some(previous)
+    }
+
+    // Change when the key expires, returning None if it doesn't exist or
+    // when it expired before
+    fn set_expiration(
+        &mut self,
+        key: &[u8],
+        expires_at: Option<Instant>,
+    ) -> Option<Option<Instant>> {
+        let previous = self.live_entry(key)?.expires_at;
+        if previous != expires_at {
+            let key = self.entries.get_key_value(key).unwrap().0.clone();
+            let entry = self.remove(&key).unwrap();
+            self.insert(
+                key,
+                Entry {
+                    expires_at,
+                    ..entry
+                },
+            );
+        }
+        Some(previous)
+    }
+
+    fn remove(&mut self, key: &[u8]) -> Option<Entry> {
+        let (key, entry) = self.entries.remove_entry(key)?;
+        self.memory -= entry_size(&key, &entry.value);
+        self.scan_order.remove(&(scan_hash(&key), key.clone()));
+        if let Some(at) = entry.expires_at {
+            self.expirations.remove(&(at, key));
+        }
+        Some(entry)
+    }
+}
+
+// How often the background task looks for expired keys, and how many it
+// removes at most while holding the lock
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExpirationConfig {
+    pub interval: Duration,
+    pub batch_size: usize,
+}
+
+impl Default for ExpirationConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(100),
+            batch_size: 20,
+        }
+    }
+}
+
+// Handle to the keyspace. Cloning it is cheap and every clone refers to the
+// same data, so it can be handed to any task which needs it.
+// Time is read from tokio's clock, so tests can pause and advance it.
+#[derive(Clone, Default)]
+pub struct Db {
+    state: Arc<Mutex<State>>,
+}
+
+impl Db {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<Value> {
+        let mut state = self.state.lock().unwrap();
+        state.live_entry(key).map(|entry| entry.value.clone())
+    }
+
+    // Store the value, returning the one it replaced. Any expiration set on
+    // the key is cleared.
+    pub fn set(&self, key: Bytes, value: Value) -> Option<Value> {
+        let mut state = self.state.lock().unwrap();
+        let previous = state.insert(key, Entry::new(value, None))?;
+        (!previous.is_expired(Instant::now())).then_some(previous.value)
+    }
+
+    // Store all the values while holding the lock, clearing their expiration
+    pub fn set_many(&self, pairs: impl IntoIterator<Item = (Bytes, Value)>) {
+        let mut state = self.state.lock().unwrap();
+        for (key, value) in pairs {
+            state.insert(key, Entry::new(value, None));
+        }
+    }
+
+    // Store the value, expiring after `expire` if given, but only if `check`
+    // accepts the current one (None if missing). Returns whether it was
+    // stored.
+    pub fn set_if<E>(
+        &self,
+        key: Bytes,
+        value: Value,
+        expire: Option<Duration>,
+        check: impl FnOnce(Option<&Value>) -> Result<bool, E>,
+    ) -> Result<bool, E> {
+        let mut state = self.state.lock().unwrap();
+        let current = state.live_entry(&key).map(|entry| &entry.value);
+        if !check(current)? {
+            return Ok(false);
+        }
+        let expires_at = expire.map(|after| Instant::now() + after);
+        state.insert(key, Entry::new(value, expires_at));
+        Ok(true)
+    }
+
+    // Run `f` on the value stored under the key (None if missing) without
+    // copying it out, for reads which only need part of it
+    pub fn view<T>(&self, key: &[u8], f: impl FnOnce(Option<&Value>) -> T) -> T {
+        let mut state = self.state.lock().unwrap();
+        f(state.live_entry(key).map(|entry| &entry.value))
+    }
+
+    // Run `f` on the values stored under the keys (None for missing ones),
+    // all read under the same lock
+    pub fn view_many<T>(&self, keys: &[Bytes], f: impl FnOnce(&[Option<&Value>]) -> T) -> T {
+        let mut state = self.state.lock().unwrap();
+        for key in keys {
+            state.live_entry(key);
+        }
+        let values: Vec<_> = keys
+            .iter()
+            .map(|key| state.entries.get(key).map(|entry| &entry.value))
+            .collect();
+        f(&values)
+    }
+
+    // Run `f` on the value stored under the key (None if missing) while
+    // holding the lock. Setting the value to None deletes the key, changing
+    // it keeps the key's expiration. Along with its result, `f` tells whether
+    // it changed the value: the key's version is only bumped if it did. When
+    // `f` fails it must leave the value as it was.
+    pub fn update<T, E>(
+        &self,
+        key: Bytes,
+        f: impl FnOnce(&mut Option<Value>) -> Result<(T, bool), E>,
+    ) -> Result<T, E> {
+        let mut state = self.state.lock().unwrap();
+        state.live_entry(&key);
+        let previous = state.remove(&key);
+        let expires_at = previous.as_ref().and_then(|entry| entry.expires_at);
+        let version = previous.as_ref().map(|entry| entry.version);
+        let mut value = previous.map(|entry| entry.value);
+        let result = f(&mut value);
+        if value.is_none() && version.is_some() {
+            state.writes += 1;
+        }
+        if let Some(value) = value {
+            let mut entry = Entry::new(value, expires_at);
+            match (&result, version) {
+                (Ok((_, true)), _) | (_, None) => {
+                    state.insert(key, entry);
+                }
+                (_, Some(version)) => {
+                    entry.version = version;
+                    state.put(key, entry);
+                }
+            }
+        }
+        result.map(|(result, _)| result)
+    }
+
+    // Remove the key, returning whether it existed
+    pub fn remove(&self, key: &[u8]) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let removed = state
+            .remove(key)
+            .is_some_and(|entry| !entry.is_expired(now));
+        if removed {
+            state.writes += 1;
+        }
+        removed
+    }
+
+    // Move the value stored at `from`, with its expiration, to `to`. Returns
+    // None if `from` doesn't exist, or whether it was moved: an existing `to`
+    // is only overwritten with `replace`.
+    pub fn rename(&self, from: &[u8], to: Bytes, replace: bool) -> Option<bool> {
+        let mut state = self.state.lock().unwrap();
+        state.live_entry(from)?;
+        if from == to.as_ref() {
+            return Some(replace);
+        }
+        if !replace && state.live_entry(&to).is_some() {
+            return Some(false);
+        }
+        let entry = state.remove(from).unwrap();
+        state.insert(to, entry);
+        Some(true)
+    }
+
+    // Make the key expire after the given time, returning whether it existed
+    pub fn expire(&self, key: &[u8], after: Duration) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state
+            .set_expiration(key, Some(Instant::now() + after))
+            .is_some()
+    }
+
+    // Make the key never expire, returning whether it had an expiration
+    pub fn persist(&self, key: &[u8]) -> bool {
+        let mut state = self.state.lock().unwrap();
+        matches!(state.set_expiration(key, None), Some(Some(_)))
+    }
+
+    // Time left before the key expires: None if the key doesn't exist,
+    // Some(None) if it never expires
+    pub fn ttl(&self, key: &[u8]) -> Option<Option<Duration>> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        state
+            .live_entry(key)
+            .map(|entry| entry.expires_at.map(|at| at - now))
+    }
+
+    // Delete up to `limit` keys which already expired, returning how many
+    // were deleted
+    pub fn remove_expired(&self, limit: usize) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let mut removed = 0;
+        while removed < limit {
+            match state.expirations.first() {
+                Some((at, key)) if *at <= now => {
+                    let key = key.clone();
+                    state.remove(&key);
+                    removed += 1;
+                }
+                _ => break,
+            }
+        }
+        removed
+    }
+
+    // Keys matching the glob pattern, skipping expired ones. Goes through the
+    // whole keyspace while holding the lock.
+    pub fn keys(&self, pattern: &[u8]) -> Vec<Bytes> {
+        let state = self.state.lock().unwrap();
+        let now = Instant::now();
+        state
+            .entries
+            .iter()
+            .filter(|(key, entry)| !entry.is_expired(now) && glob::matches(pattern, key))
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    // Walk the keyspace starting from the cursor (0 to start from the
+    // beginning), visiting about `count` keys and returning those matching the
+    // pattern along with the cursor to continue from, 0 once done.
+    // Keys are walked by hash rather than by insertion or bucket, so the
+    // order never changes: a full scan returns every key which exists
+    // throughout it, whatever gets inserted or deleted in the meantime.
+    pub fn scan(&self, cursor: u64, pattern: Option<&[u8]>, count: usize) -> (u64, Vec<Bytes>) {
+        let state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let mut keys = Vec::new();
+        let mut last = None;
+        let mut next_cursor = 0;
+        for (hash, key) in state.scan_order.range((cursor, Bytes::new())..) {
+            let hash = *hash;
+            // Keys sharing a hash can't be told apart by the cursor, so they
+            // must all end up in the same batch
+            if keys.len() >= count && last != Some(hash) {
+                next_cursor = hash;
+                break;
+            }
+            last = Some(hash);
+            keys.push(key);
+        }
+
+        let keys = keys
+            .into_iter()
+            .filter(|key| !state.entries[*key].is_expired(now))
+            .filter(|key| pattern.is_none_or(|pattern| glob::matches(pattern, key)))
+            .cloned()
+            .collect();
+        (next_cursor, keys)
+    }
+
+    // Copy of every live key, with its value and time to live, taken at once
+    pub fn entries(&self) -> Vec<(Bytes, Value, Option<Duration>)> {
+        let state = self.state.lock().unwrap();
+        let now = Instant::now();
+        state
+            .entries
+            .iter()
+            .filter(|(_, entry)| !entry.is_expired(now))
+            .map(|(key, entry)| {
+                let ttl = entry.expires_at.map(|at| at - now);
+                (key.clone(), entry.value.clone(), ttl)
+            })
+            .collect()
+    }
+
+    // Delete every key, along with their expirations. Versions keep going
+    // up, so recreated keys don't get the version they had before.
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        *state = State {
+            writes: state.writes + 1,
+            ..State::default()
+        };
+    }
+
+    // Version of the key, changing whenever it's written, deleted or expires.
+    // Missing keys are at version 0.
+    pub fn version(&self, key: &[u8]) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        state.live_entry(key).map_or(0, |entry| entry.version)
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    // Number of keys which didn't expire yet, even if not deleted so far
+    pub fn live_len(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let expired = state
+            .expirations
+            .iter()
+            .take_while(|(at, _)| *at <= now)
+            .count();
+        state.entries.len() - expired
+    }
+
+    // Number of keys with an expiration
+    pub fn expires(&self) -> usize {
+        self.state.lock().unwrap().expirations.len()
+    }
+
+    // The least recently used among a few keys sampled at random, along with
+    // when it was last accessed
+    pub fn lru_candidate(&self, samples: usize) -> Option<(Instant, Bytes)> {
+        let state = self.state.lock().unwrap();
+        let start = (random(), Bytes::new());
+        state
+            .scan_order
+            .range(start..)
+            .chain(state.scan_order.iter())
+            .take(samples)
+            .map(|(_, key)| (state.entries[key].accessed_at, key.clone()))
+            .min()
+    }
+
+    // The key expiring the soonest, along with when it expires
+    pub fn ttl_candidate(&self) -> Option<(Instant, Bytes)> {
+        self.state.lock().unwrap().expirations.first().cloned()
+    }
+
+    pub fn random_key(&self) -> Option<Bytes> {
+        let state = self.state.lock().unwrap();
+        let start = (random(), Bytes::new());
+        state
+            .scan_order
+            .range(start..)
+            .chain(state.scan_order.iter())
+            .next()
+            .map(|(_, key)| key.clone())
+    }
+
+    // Number of writes and deletions made so far, not counting keys which
+    // expired
+    pub fn writes(&self) -> u64 {
+        self.state.lock().unwrap().writes
+    }
+
+    // Rough number of bytes taken by the keys and their values
+    pub fn used_memory(&self) -> usize {
+        self.state.lock().unwrap().memory
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// A different number each time, good enough to pick keys at random
+fn random() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+// Delete a key of any database, chosen as the policy says. Approximated LRU
+// picks the least recently used out of a few sampled keys, like Redis does.
+// Returns the index of the database and the key evicted, None if there's no
+// key the policy may evict.
+pub fn evict(dbs: &[Db], policy: MaxMemoryPolicy) -> Option<(usize, Bytes)> {
+    const SAMPLES: usize = 5;
+    let candidate = |pick: &dyn Fn(&Db) -> Option<(Instant, Bytes)>| {
+        dbs.iter()
+            .enumerate()
+            .filter_map(|(index, db)| pick(db).map(|(at, key)| (at, index, key)))
+            .min()
+            .map(|(_, index, key)| (index, key))
+    };
+    let victim = match policy {
+        MaxMemoryPolicy::NoEviction => None,
+        MaxMemoryPolicy::AllKeysLru => candidate(&|db| db.lru_candidate(SAMPLES)),
+        MaxMemoryPolicy::VolatileTtl => candidate(&Db::ttl_candidate),
+        MaxMemoryPolicy::AllKeysRandom => {
+            let non_empty: Vec<usize> = (0..dbs.len()).filter(|&i| !dbs[i].is_empty()).collect();
+            (!non_empty.is_empty())
+                .then(|| non_empty[random() as usize % non_empty.len()])
+                .and_then(|index| dbs[index].random_key().map(|key| (index, key)))
+        }
+    };
+    let (index, key) = victim?;
+    dbs[index].remove(&key);
+    Some((index, key))
+}
+
+// Periodically delete expired keys, so the ones which are never read again
+// don't stay in memory. Expired keys are removed in batches, releasing the
+// lock in between, until a batch comes back not full.
+pub fn spawn_expiration_task(dbs: Vec<Db>, config: ExpirationConfig) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.interval);
+        loop {
+            interval.tick().await;
+            for db in dbs.iter() {
+                while db.remove_expired(config.batch_size) == config.batch_size {
+                    tokio::task::yield_now().await;
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, time::Duration};
+
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use super::{evict, spawn_expiration_task, Db, ExpirationConfig, SortedSet, Value};
+    use crate::config::MaxMemoryPolicy;
+
+    #[test]
+    fn test_get_missing() {
+        let db = Db::new();
+        assert_eq!(None, db.get(b"missing"));
+        assert!(db.is_empty());
+    }
+
+    #[test]
+    fn test_set_get() {
+        let db = Db::new();
+        let value = Value::String(Bytes::from_static(b"\x00binary\xff"));
+
+        assert_eq!(None, db.set(Bytes::from("key"), value.clone()));
+        assert_eq!(Some(value), db.get(b"key"));
+        assert_eq!(1, db.len());
+    }
+
+    #[test]
+    fn test_set_overwrites() {
+        let db = Db::new();
+        db.set(Bytes::from("key"), Value::String(Bytes::from("old")));
+
+        let previous = db.set(Bytes::from("key"), Value::String(Bytes::from("new")));
+
+        assert_eq!(Some(Value::String(Bytes::from("old"))), previous);
+        assert_eq!(Some(Value::String(Bytes::from("new"))), db.get(b"key"));
+        assert_eq!(1, db.len());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_access() {
+        let db = Db::new();
+
+        let tasks: Vec<_> = (0..8)
+            .map(|task| {
+                let db = db.clone();
+                tokio::spawn(async move {
+                    for i in 0..100 {
+                        let key = Bytes::from(format!("{}:{}", task, i));
+                        db.set(key.clone(), Value::String(key));
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(800, db.len());
+        assert_eq!(Some(Value::String(Bytes::from("3:42"))), db.get(b"3:42"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_expire() {
+        let db = Db::new();
+        db.set(Bytes::from("key"), Value::String(Bytes::from("value")));
+        assert_eq!(Some(None), db.ttl(b"key"));
+
+        assert!(db.expire(b"key", Duration::from_secs(10)));
+        assert_eq!(Some(Some(Duration::from_secs(10))), db.ttl(b"key"));
+
+        tokio::time::advance(Duration::from_secs(4)).await;
+        assert_eq!(Some(Some(Duration::from_secs(6))), db.ttl(b"key"));
+        assert!(db.get(b"key").is_some());
+
+        tokio::time::advance(Duration::from_secs(6)).await;
+        assert_eq!(None, db.get(b"key"));
+        assert_eq!(None, db.ttl(b"key"));
+        assert!(db.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_expire_missing() {
+        let db = Db::new();
+        assert!(!db.expire(b"missing", Duration::from_secs(1)));
+        assert_eq!(None, db.ttl(b"missing"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_persist() {
+        let db = Db::new();
+        db.set(Bytes::from("key"), Value::String(Bytes::from("value")));
+        assert!(!db.persist(b"key"));
+        assert!(!db.persist(b"missing"));
+
+        db.expire(b"key", Duration::from_secs(1));
+        assert!(db.persist(b"key"));
+        assert_eq!(Some(None), db.ttl(b"key"));
+        assert_eq!(0, db.expires());
+
+        tokio::time::advance(Duration::from_secs(2)).await;
+        assert!(db.get(b"key").is_some());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_live_len_skips_expired() {
+        let db = Db::new();
+        for key in ["a", "b", "c"] {
+            db.set(Bytes::from(key), Value::String(Bytes::from("value")));
+        }
+        db.expire(b"a", Duration::from_secs(1));
+        db.expire(b"b", Duration::from_secs(10));
+        assert_eq!(3, db.live_len());
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(3, db.len());
+        assert_eq!(2, db.live_len());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_set_clears_expiration() {
+        let db = Db::new();
+        db.set(Bytes::from("key"), Value::String(Bytes::from("old")));
+        db.expire(b"key", Duration::from_secs(1));
+
+        db.set(Bytes::from("key"), Value::String(Bytes::from("new")));
+
+        tokio::time::advance(Duration::from_secs(2)).await;
+        assert_eq!(Some(Value::String(Bytes::from("new"))), db.get(b"key"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_remove() {
+        let db = Db::new();
+        db.set(Bytes::from("key"), Value::String(Bytes::from("value")));
+        db.set(Bytes::from("expired"), Value::String(Bytes::from("value")));
+        db.expire(b"expired", Duration::from_secs(1));
+        tokio::time::advance(Duration::from_secs(1)).await;
+
+        assert!(db.remove(b"key"));
+        assert!(!db.remove(b"key"));
+        assert!(!db.remove(b"expired"));
+    }
+
+    #[test]
+    fn test_clear() {
+        let db = Db::new();
+        db.set(Bytes::from("key"), Value::String(Bytes::from("value")));
+        db.set(Bytes::from("expiring"), Value::String(Bytes::from("value")));
+        db.expire(b"expiring", Duration::from_secs(1));
+
+        db.clear();
+
+        assert!(db.is_empty());
+        assert_eq!(0, db.expires());
+        assert_eq!((0, vec![]), db.scan(0, None, 10));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_remove_expired_batches() {
+        let db = Db::new();
+        for i in 0..10 {
+            let key = Bytes::from(format!("key:{}", i));
+            db.set(key.clone(), Value::String(key.clone()));
+            db.expire(&key, Duration::from_secs(1 + i % 2));
+        }
+        db.set(
+            Bytes::from("persistent"),
+            Value::String(Bytes::from("value")),
+        );
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(3, db.remove_expired(3));
+        assert_eq!(2, db.remove_expired(3));
+        assert_eq!(0, db.remove_expired(3));
+        assert_eq!(6, db.len());
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(5, db.remove_expired(10));
+        assert_eq!(1, db.len());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_expire_again_replaces_deadline() {
+        let db = Db::new();
+        db.set(Bytes::from("key"), Value::String(Bytes::from("value")));
+        db.expire(b"key", Duration::from_secs(1));
+        db.expire(b"key", Duration::from_secs(10));
+
+        tokio::time::advance(Duration::from_secs(2)).await;
+        assert_eq!(0, db.remove_expired(10));
+        assert_eq!(1, db.len());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_expiration_task_reclaims_keys() {
+        let db = Db::new();
+        for i in 0..1000 {
+            let key = Bytes::from(format!("key:{}", i));
+            db.set(key.clone(), Value::String(key.clone()));
+            db.expire(&key, Duration::from_millis(50));
+        }
+        let config = ExpirationConfig {
+            interval: Duration::from_millis(100),
+            batch_size: 20,
+        };
+        let task = spawn_expiration_task(vec![db.clone()], config);
+
+        // Let the first, immediate, tick run while nothing has expired yet
+        tokio::task::yield_now().await;
+        assert_eq!(1000, db.len());
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(db.is_empty());
+        task.abort();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_keys_skips_expired() {
+        let db = Db::new();
+        db.set(Bytes::from("key"), Value::String(Bytes::from("value")));
+        db.set(Bytes::from("expired"), Value::String(Bytes::from("value")));
+        db.expire(b"expired", Duration::from_secs(1));
+        tokio::time::advance(Duration::from_secs(1)).await;
+
+        assert_eq!(vec![Bytes::from("key")], db.keys(b"*"));
+    }
+
+    // Scan the whole keyspace, running `between` after each call
+    fn scan_all(db: &Db, count: usize, mut between: impl FnMut(usize)) -> Vec<Bytes> {
+        let mut cursor = 0;
+        let mut keys = Vec::new();
+        for call in 0.. {
+            let (next, batch) = db.scan(cursor, None, count);
+            keys.extend(batch);
+            between(call);
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        keys
+    }
+
+    #[test]
+    fn test_scan_visits_every_key() {
+        let db = Db::new();
+        for i in 0..1000 {
+            let key = Bytes::from(format!("key:{}", i));
+            db.set(key.clone(), Value::String(key));
+        }
+
+        let mut keys = scan_all(&db, 10, |_| {});
+
+        keys.sort();
+        let mut expected: Vec<_> = (0..1000)
+            .map(|i| Bytes::from(format!("key:{}", i)))
+            .collect();
+        expected.sort();
+        assert_eq!(expected, keys);
+    }
+
+    #[test]
+    fn test_scan_under_concurrent_changes() {
+        let db = Db::new();
+        for i in 0..500 {
+            let key = Bytes::from(format!("stable:{}", i));
+            db.set(key.clone(), Value::String(key));
+        }
+        let writer = db.clone();
+
+        // Between calls, add new keys and delete some of the ones just added
+        let keys = scan_all(&db, 7, |call| {
+            for i in 0..20 {
+                let key = Bytes::from(format!("new:{}:{}", call, i));
+                writer.set(key.clone(), Value::String(key));
+            }
+            for i in 0..10 {
+                writer.remove(format!("new:{}:{}", call, i).as_bytes());
+            }
+        });
+
+        let stable: HashSet<_> = keys
+            .iter()
+            .filter(|key| key.starts_with(b"stable:"))
+            .collect();
+        assert_eq!(500, stable.len());
+        let unique: HashSet<_> = keys.iter().collect();
+        assert_eq!(keys.len(), unique.len());
+    }
+
+    #[test]
+    fn test_scan_match() {
+        let db = Db::new();
+        for key in ["user:1", "user:2", "post:1"] {
+            db.set(Bytes::from(key), Value::String(Bytes::from("value")));
+        }
+
+        let (cursor, mut keys) = db.scan(0, Some(b"user:*"), 10);
+
+        keys.sort();
+        assert_eq!(0, cursor);
+        assert_eq!(vec![Bytes::from("user:1"), Bytes::from("user:2")], keys);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_update() {
+        let db = Db::new();
+        db.set(Bytes::from("key"), Value::String(Bytes::from("old")));
+        db.expire(b"key", Duration::from_secs(10));
+
+        let previous = db.update(Bytes::from("key"), |value| {
+            Ok::<_, ()>((value.replace(Value::String(Bytes::from("new"))), true))
+        });
+
+        assert_eq!(Ok(Some(Value::String(Bytes::from("old")))), previous);
+        assert_eq!(Some(Value::String(Bytes::from("new"))), db.get(b"key"));
+        assert_eq!(Some(Some(Duration::from_secs(10))), db.ttl(b"key"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_set_if() {
+        let db = Db::new();
+        let value = |s: &'static str| Value::String(Bytes::from(s));
+        let missing = |current: Option<&Value>| Ok::<_, ()>(current.is_none());
+
+        assert_eq!(
+            Ok(true),
+            db.set_if(Bytes::from("key"), value("a"), None, missing)
+        );
+        assert_eq!(
+            Ok(false),
+            db.set_if(Bytes::from("key"), value("b"), None, missing)
+        );
+        assert_eq!(Some(value("a")), db.get(b"key"));
+
+        let expire = Some(Duration::from_secs(5));
+        assert_eq!(
+            Ok(true),
+            db.set_if(Bytes::from("key"), value("c"), expire, |_| Ok::<_, ()>(
+                true
+            ))
+        );
+        assert_eq!(Some(Some(Duration::from_secs(5))), db.ttl(b"key"));
+        assert_eq!(
+            Err(()),
+            db.set_if(Bytes::from("key"), value("d"), None, |_| Err(()))
+        );
+        assert_eq!(Some(value("c")), db.get(b"key"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_set_many() {
+        let db = Db::new();
+        db.set(Bytes::from("a"), Value::String(Bytes::from("old")));
+        db.expire(b"a", Duration::from_secs(1));
+
+        db.set_many([
+            (Bytes::from("a"), Value::String(Bytes::from("1"))),
+            (Bytes::from("b"), Value::String(Bytes::from("2"))),
+        ]);
+
+        assert_eq!(Some(None), db.ttl(b"a"));
+        assert_eq!(Some(Value::String(Bytes::from("2"))), db.get(b"b"));
+        assert_eq!(2, db.len());
+    }
+
+    #[test]
+    fn test_used_memory_grows_with_values() {
+        let db = Db::new();
+        assert_eq!(0, db.used_memory());
+
+        db.set(Bytes::from("key"), Value::String(Bytes::from("small")));
+        let small = db.used_memory();
+        db.set(
+            Bytes::from("key"),
+            Value::String(Bytes::from(vec![0; 1000])),
+        );
+
+        assert!(small > 0);
+        assert_eq!(small + 995, db.used_memory());
+    }
+
+    #[test]
+    fn test_used_memory_follows_changes() {
+        let db = Db::new();
+        db.set(Bytes::from("key"), Value::String(Bytes::from("value")));
+        let _ = db.update(Bytes::from("list"), |value| {
+            *value = Some(Value::List(
+                (0..100).map(|i| Bytes::from(i.to_string())).collect(),
+            ));
+            Ok::<_, ()>(((), true))
+        });
+        db.expire(b"key", Duration::from_secs(10));
+        assert!(db.used_memory() > 100 * 16);
+
+        db.remove(b"key");
+        let _ = db.update(Bytes::from("list"), |value| {
+            Ok::<_, ()>((value.take(), true))
+        });
+
+        assert_eq!(0, db.used_memory());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_version() {
+        let db = Db::new();
+        assert_eq!(0, db.version(b"key"));
+
+        db.set(Bytes::from("key"), Value::String(Bytes::from("value")));
+        let written = db.version(b"key");
+        db.get(b"key");
+        assert_eq!(written, db.version(b"key"));
+        let _ = db.update(Bytes::from("key"), |_| Ok::<_, ()>(((), false)));
+        assert_eq!(written, db.version(b"key"));
+        let _ = db.update(Bytes::from("key"), |_| Err::<((), bool), _>(()));
+        assert_eq!(written, db.version(b"key"));
+        let _ = db.update(Bytes::from("key"), |_| Ok::<_, ()>(((), true)));
+        assert!(db.version(b"key") > written);
+
+        let updated = db.version(b"key");
+        db.clear();
+        db.set(Bytes::from("key"), Value::String(Bytes::from("value")));
+        assert!(db.version(b"key") > updated);
+
+        db.expire(b"key", Duration::from_secs(1));
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(0, db.version(b"key"));
+    }
+
+    #[rstest]
+    #[case(Value::String(Bytes::from("-42")), "int")]
+    #[case(Value::String(Bytes::from("042")), "embstr")]
+    #[case(Value::String(Bytes::from(vec![b'x'; 44])), "embstr")]
+    #[case(Value::String(Bytes::from(vec![b'x'; 45])), "raw")]
+    #[case(Value::List([Bytes::from("a")].into()), "listpack")]
+    #[case(Value::List((0..129).map(|i| Bytes::from(i.to_string())).collect()), "quicklist")]
+    #[case(Value::Hash([(Bytes::from("f"), Bytes::from("v"))].into()), "listpack")]
+    #[case(Value::Hash([(Bytes::from("f"), Bytes::from(vec![b'x'; 65]))].into()), "hashtable")]
+    #[case(Value::Set([Bytes::from("1"), Bytes::from("2")].into()), "intset")]
+    #[case(Value::Set([Bytes::from("1"), Bytes::from("a")].into()), "listpack")]
+    #[case(Value::Set((0..600).map(|i| Bytes::from(format!("m{}", i))).collect()), "hashtable")]
+    #[case(Value::SortedSet(SortedSet::new()), "listpack")]
+    fn test_encoding(#[case] value: Value, #[case] expected: &str) {
+        assert_eq!(expected, value.encoding());
+    }
+
+    #[test]
+    fn test_memory_usage_estimate() {
+        let small = Value::Set((0..8).map(|i| Bytes::from(format!("{:04}", i))).collect());
+        let large = Value::Set((0..800).map(|i| Bytes::from(format!("{:04}", i))).collect());
+
+        assert_eq!(8 * (4 + 16), small.memory_usage());
+        assert_eq!(800 * (4 + 16), large.memory_usage());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_evict_lru() {
+        let dbs = vec![Db::new(), Db::new()];
+        for key in ["a", "b"] {
+            dbs[0].set(Bytes::from(key), Value::String(Bytes::from("value")));
+            tokio::time::advance(Duration::from_secs(1)).await;
+        }
+        dbs[1].set(Bytes::from("c"), Value::String(Bytes::from("value")));
+        tokio::time::advance(Duration::from_secs(1)).await;
+        dbs[0].get(b"a");
+
+        assert_eq!(
+            Some((0, Bytes::from("b"))),
+            evict(&dbs, MaxMemoryPolicy::AllKeysLru)
+        );
+        assert_eq!(vec![Bytes::from("a")], dbs[0].keys(b"*"));
+        assert_eq!(
+            Some((1, Bytes::from("c"))),
+            evict(&dbs, MaxMemoryPolicy::AllKeysLru)
+        );
+        assert!(dbs[1].is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_evict_volatile_ttl() {
+        let dbs = vec![Db::new(), Db::new()];
+        for (db, key, ttl) in [(0, "later", 10), (1, "sooner", 5)] {
+            dbs[db].set(Bytes::from(key), Value::String(Bytes::from("value")));
+            dbs[db].expire(key.as_bytes(), Duration::from_secs(ttl));
+        }
+        dbs[0].set(
+            Bytes::from("persistent"),
+            Value::String(Bytes::from("value")),
+        );
+
+        assert!(evict(&dbs, MaxMemoryPolicy::VolatileTtl).is_some());
+        assert!(dbs[1].is_empty());
+        assert!(evict(&dbs, MaxMemoryPolicy::VolatileTtl).is_some());
+        assert_eq!(None, evict(&dbs, MaxMemoryPolicy::VolatileTtl));
+        assert_eq!(vec![Bytes::from("persistent")], dbs[0].keys(b"*"));
+    }
+
+    #[test]
+    fn test_evict_random_and_none() {
+        let dbs = vec![Db::new(), Db::new()];
+        dbs[1].set(Bytes::from("a"), Value::String(Bytes::from("value")));
+        dbs[1].set(Bytes::from("b"), Value::String(Bytes::from("value")));
+
+        assert_eq!(None, evict(&dbs, MaxMemoryPolicy::NoEviction));
+        assert!(evict(&dbs, MaxMemoryPolicy::AllKeysRandom).is_some());
+        assert_eq!(1, dbs[1].len());
+        assert!(evict(&dbs, MaxMemoryPolicy::AllKeysRandom).is_some());
+        assert_eq!(None, evict(&dbs, MaxMemoryPolicy::AllKeysRandom));
+    }
+
+    #[test]
+    fn test_view() {
+        let db = Db::new();
+        db.set(Bytes::from("key"), Value::String(Bytes::from("value")));
+
+        assert_eq!(
+            Some(5),
+            db.view(b"key", |value| match value {
+                Some(Value::String(bytes)) => Some(bytes.len()),
+                _ => None,
+            })
+        );
+        assert!(db.view(b"missing", |value| value.is_none()));
+    }
+
+    #[test]
+    fn test_update_insert_and_delete() {
+        let db = Db::new();
+
+        let _ = db.update(Bytes::from("key"), |value| {
+            assert_eq!(None, *value);
+            *value = Some(Value::String(Bytes::from("new")));
+            Ok::<_, ()>(((), true))
+        });
+        assert_eq!(Some(None), db.ttl(b"key"));
+
+        let writes = db.writes();
+        let _ = db.update(Bytes::from("key"), |value| {
+            Ok::<_, ()>((value.take(), true))
+        });
+        assert!(db.is_empty());
+        // Deletions count as writes, while doing nothing doesn't
+        assert_eq!(writes + 1, db.writes());
+        let _ = db.update(Bytes::from("key"), |_| Ok::<_, ()>(((), false)));
+        assert!(!db.remove(b"key"));
+        assert_eq!(writes + 1, db.writes());
+    }
+}
@@ -0,0 +1,127 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeSet, HashMap},
+};
+
+use bytes::Bytes;
+
+// Members ordered by score, ties broken by comparing the members bytewise
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SortedSet {
+    scores: HashMap<Bytes, f64>,
+    ordered: BTreeSet<(Score, Bytes)>,
+}
+
+// Score with a total order, so it can key the ordered members. NaN scores
+// are rejected before they get here.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Score(f64);
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl SortedSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Add the member or update its score, returning whether it was added
+    pub fn insert(&mut self, member: Bytes, score: f64) -> bool {
+        let previous = self.scores.insert(member.clone(), score);
+        if let Some(previous) = previous {
+            self.ordered.remove(&(Score(previous), member.clone()));
+        }
+        self.ordered.insert((Score(score), member));
+        previous.is_none()
+    }
+
+    // Remove the member, returning whether it was there
+    pub fn remove(&mut self, member: &[u8]) -> bool {
+        match self.scores.remove_entry(member) {
+            Some((member, score)) => {
+                self.ordered.remove(&(Score(score), member));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    // Members along with their score, from the lowest score up
+    pub fn iter(&self) -> impl Iterator<Item = (&Bytes, f64)> {
+        self.ordered.iter().map(|(score, member)| (member, score.0))
+    }
+
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::SortedSet;
+
+    fn members(set: &SortedSet) -> Vec<(&str, f64)> {
+        set.iter()
+            .map(|(member, score)| (std::str::from_utf8(member).unwrap(), score))
+            .collect()
+    }
+
+    #[test]
+    fn test_ordered_by_score_then_member() {
+        let mut set = SortedSet::new();
+        assert!(set.insert(Bytes::from("c"), 1.0));
+        assert!(set.insert(Bytes::from("a"), 2.0));
+        assert!(set.insert(Bytes::from("b"), 1.0));
+        assert!(set.insert(Bytes::from("d"), f64::NEG_INFINITY));
+
+        assert_eq!(
+            vec![("d", f64::NEG_INFINITY), ("b", 1.0), ("c", 1.0), ("a", 2.0)],
+            members(&set)
+        );
+    }
+
+    #[test]
+    fn test_update_score() {
+        let mut set = SortedSet::new();
+        set.insert(Bytes::from("a"), 1.0);
+        set.insert(Bytes::from("b"), 2.0);
+
+        assert!(!set.insert(Bytes::from("a"), 3.0));
+
+        assert_eq!(vec![("b", 2.0), ("a", 3.0)], members(&set));
+        assert_eq!(Some(3.0), set.score(b"a"));
+        assert_eq!(2, set.len());
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut set = SortedSet::new();
+        set.insert(Bytes::from("a"), 1.0);
+
+        assert!(set.remove(b"a"));
+        assert!(!set.remove(b"a"));
+        assert!(set.is_empty());
+        assert!(members(&set).is_empty());
+    }
+}
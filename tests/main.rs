@@ -1,8 +1,19 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 use redis::{aio::MultiplexedConnection, AsyncConnectionConfig, Value};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpStream, UnixStream},
+};
+use tokio_rustls::{
+    rustls::{self, pki_types::ServerName, RootCertStore},
+    TlsConnector,
+};
 use yarrs::{
-    listener::{bind, run_listener},
+    client::Client,
+    cmd,
+    listener::{bind, bind_unix, run_listener, run_tls_listener, run_unix_listener, tls_acceptor},
+    resp::types::Frame,
     server::Server,
 };
 
@@ -15,7 +26,7 @@ async fn test_ping() {
         .await
         .expect("Error sending ping command");
 
-    assert_eq!(result, Value::BulkString("PONG".into()));
+    assert_eq!(result, Value::SimpleString("PONG".into()));
 }
 
 #[tokio::test]
@@ -32,20 +43,434 @@ async fn test_echo() {
     assert_eq!(result, Value::BulkString("test string".into()));
 }
 
-async fn spawn() -> MultiplexedConnection {
+#[tokio::test]
+async fn test_set_get() {
+    let mut connection = spawn().await;
+    let mut set = redis::cmd("SET");
+    set.arg(b"key\x00").arg(b"\xffbinary value");
+
+    let result = connection
+        .send_packed_command(&set)
+        .await
+        .expect("Error sending set command");
+    assert_eq!(result, Value::Okay);
+
+    let mut get = redis::cmd("GET");
+    get.arg(b"key\x00");
+    let result = connection
+        .send_packed_command(&get)
+        .await
+        .expect("Error sending get command");
+    assert_eq!(result, Value::BulkString(b"\xffbinary value".to_vec()));
+
+    let mut get = redis::cmd("GET");
+    get.arg("missing");
+    let result = connection
+        .send_packed_command(&get)
+        .await
+        .expect("Error sending get command");
+    assert_eq!(result, Value::Nil);
+}
+
+#[tokio::test]
+async fn test_client_set_get() {
+    let mut client = Client::connect(start().await).await.unwrap();
+
+    assert_eq!(
+        Frame::ok(),
+        client
+            .send_command(cmd!("SET", "key", "value"))
+            .await
+            .unwrap()
+    );
+    assert_eq!(
+        Frame::Bulk("value".into()),
+        client.send_command(cmd!("GET", "key")).await.unwrap()
+    );
+    assert!(matches!(
+        client.send_command(cmd!("NOPE")).await.unwrap(),
+        Frame::Error(_)
+    ));
+}
+
+#[tokio::test]
+async fn test_ping_raw_socket() {
+    let mut socket = TcpStream::connect(start().await).await.unwrap();
+
+    assert_eq!(
+        "+PONG\r\n",
+        roundtrip(&mut socket, "*1\r\n$4\r\nPING\r\n").await
+    );
+}
+
+#[tokio::test]
+async fn test_unknown_command_replies_error() {
+    let mut socket = TcpStream::connect(start().await).await.unwrap();
+
+    let reply = roundtrip(&mut socket, "*1\r\n$4\r\nNOPE\r\n").await;
+    assert!(reply.starts_with("-ERR"), "unexpected reply {:?}", reply);
+    assert_eq!("+PONG\r\n", roundtrip(&mut socket, "PING\r\n").await);
+}
+
+#[tokio::test]
+async fn test_concurrent_connections() {
+    let addr = start().await;
+    let mut first = TcpStream::connect(&addr).await.unwrap();
+    let mut second = TcpStream::connect(&addr).await.unwrap();
+
+    assert_eq!("$3\r\none\r\n", roundtrip(&mut first, "ECHO one\r\n").await);
+    assert_eq!(
+        "$3\r\ntwo\r\n",
+        roundtrip(&mut second, "ECHO two\r\n").await
+    );
+    assert_eq!("+PONG\r\n", roundtrip(&mut first, "PING\r\n").await);
+}
+
+#[tokio::test]
+async fn test_hello_switches_null_encoding() {
+    let addr = start().await;
+    let mut resp2 = TcpStream::connect(&addr).await.unwrap();
+    let mut resp3 = TcpStream::connect(&addr).await.unwrap();
+
+    let reply = roundtrip(&mut resp3, "HELLO 3\r\n").await;
+    assert!(reply.starts_with("%7\r\n$6\r\nserver\r\n$5\r\nyarrs\r\n"));
+    assert!(reply.contains("$5\r\nproto\r\n:3\r\n"));
+
+    assert_eq!("_\r\n", roundtrip(&mut resp3, "GET missing\r\n").await);
+    assert_eq!("$-1\r\n", roundtrip(&mut resp2, "GET missing\r\n").await);
+}
+
+#[tokio::test]
+async fn test_hello_unsupported_version() {
+    let mut socket = TcpStream::connect(start().await).await.unwrap();
+
+    let reply = roundtrip(&mut socket, "HELLO 4\r\n").await;
+    assert!(
+        reply.starts_with("-NOPROTO"),
+        "unexpected reply {:?}",
+        reply
+    );
+}
+
+#[tokio::test]
+async fn test_pipelined_commands() {
+    let mut socket = TcpStream::connect(start().await).await.unwrap();
+    let expected = "+PONG\r\n+OK\r\n$1\r\n1\r\n";
+
+    socket
+        .write_all(b"*1\r\n$4\r\nPING\r\n*3\r\n$3\r\nSET\r\n$1\r\na\r\n$1\r\n1\r\n*2\r\n$3\r\nGET\r\n$1\r\na\r\n")
+        .await
+        .unwrap();
+    let mut buf = vec![0; expected.len()];
+    socket.read_exact(&mut buf).await.unwrap();
+
+    assert_eq!(expected, String::from_utf8_lossy(&buf));
+}
+
+#[tokio::test]
+async fn test_transaction() {
+    let mut connection = spawn().await;
+    let mut pipe = redis::pipe();
+    pipe.atomic()
+        .cmd("SET")
+        .arg("counter")
+        .arg("10")
+        .cmd("INCRBY")
+        .arg("counter")
+        .arg("5")
+        .cmd("GET")
+        .arg("counter");
+
+    let result: (String, i64, String) = pipe
+        .query_async(&mut connection)
+        .await
+        .expect("Error running transaction");
+
+    assert_eq!(("OK".to_string(), 15, "15".to_string()), result);
+}
+
+#[tokio::test]
+async fn test_publish_subscribe() {
+    let address = start().await;
+    let subscribed = "*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n";
+    let message = "*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n";
+
+    let mut subscribers = Vec::new();
+    for _ in 0..2 {
+        let mut socket = TcpStream::connect(&address).await.unwrap();
+        socket
+            .write_all(b"*2\r\n$9\r\nSUBSCRIBE\r\n$4\r\nnews\r\n")
+            .await
+            .unwrap();
+        let mut buf = vec![0; subscribed.len()];
+        socket.read_exact(&mut buf).await.unwrap();
+        assert_eq!(subscribed, String::from_utf8_lossy(&buf));
+        subscribers.push(socket);
+    }
+
+    let mut publisher = TcpStream::connect(&address).await.unwrap();
+    let reply = roundtrip(
+        &mut publisher,
+        "*3\r\n$7\r\nPUBLISH\r\n$4\r\nnews\r\n$5\r\nhello\r\n",
+    )
+    .await;
+    assert_eq!(":2\r\n", reply);
+
+    for socket in subscribers.iter_mut() {
+        let mut buf = vec![0; message.len()];
+        socket.read_exact(&mut buf).await.unwrap();
+        assert_eq!(message, String::from_utf8_lossy(&buf));
+    }
+}
+
+#[tokio::test]
+async fn test_quit() {
+    let address = start().await;
+    let mut socket = TcpStream::connect(&address).await.unwrap();
+
+    // Replies pipelined before QUIT are sent, the ones after it are not
+    socket
+        .write_all(b"ECHO one\r\nQUIT\r\nECHO two\r\n")
+        .await
+        .unwrap();
+    let mut replies = String::new();
+    socket.read_to_string(&mut replies).await.unwrap();
+    assert_eq!("$3\r\none\r\n+OK\r\n", replies);
+
+    let mut subscriber = TcpStream::connect(&address).await.unwrap();
+    roundtrip(&mut subscriber, "SUBSCRIBE news\r\n").await;
+    assert_eq!("+OK\r\n", roundtrip(&mut subscriber, "QUIT\r\n").await);
+    assert_eq!(0, subscriber.read(&mut [0; 16]).await.unwrap());
+
+    let mut publisher = TcpStream::connect(&address).await.unwrap();
+    assert_eq!(
+        ":0\r\n",
+        roundtrip(&mut publisher, "PUBLISH news hello\r\n").await
+    );
+}
+
+#[tokio::test]
+async fn test_unix_socket_ping() {
+    let path = std::env::temp_dir().join(format!("yarrs-{}.sock", std::process::id()));
+    let mut listener = bind_unix(&path);
+    let mut server = Server::new("0.0.0.0".into(), 0);
+    let sender = server.sender.clone();
+    let shutdown = server.shutdown.clone();
+    tokio::spawn(async move {
+        run_unix_listener(&mut listener, sender, shutdown).await;
+    });
+    tokio::spawn(async move {
+        server.run().await;
+    });
+
+    let mut client = Client::new(UnixStream::connect(&path).await.unwrap());
+
+    assert_eq!(
+        Frame::simple("PONG"),
+        client.send_command(cmd!("PING")).await.unwrap()
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_tls_ping() {
+    let certified = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+    let dir = std::env::temp_dir().join(format!("yarrs-{}-tls", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (cert, key) = (dir.join("cert.pem"), dir.join("key.pem"));
+    std::fs::write(&cert, certified.cert.pem()).unwrap();
+    std::fs::write(&key, certified.key_pair.serialize_pem()).unwrap();
+
+    let acceptor = tls_acceptor(&cert, &key).unwrap();
+    let mut listener = bind("127.0.0.1".into(), 0).await;
+    let address = listener.local_addr().unwrap();
+    let mut server = Server::new("127.0.0.1".into(), address.port());
+    let sender = server.sender.clone();
+    let shutdown = server.shutdown.clone();
+    tokio::spawn(async move {
+        run_tls_listener(&mut listener, acceptor, sender, shutdown).await;
+    });
+    tokio::spawn(async move {
+        server.run().await;
+    });
+
+    // A client not speaking TLS is dropped without affecting the others
+    let mut plain = TcpStream::connect(address).await.unwrap();
+    plain.write_all(b"PING\r\n").await.unwrap();
+    let mut buf = [0; 64];
+    let n = plain.read(&mut buf).await.unwrap_or(0);
+    assert!(!buf[..n].starts_with(b"+PONG"));
+
+    let mut roots = RootCertStore::empty();
+    roots.add(certified.cert.der().clone()).unwrap();
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .unwrap()
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    let stream = TlsConnector::from(Arc::new(config))
+        .connect(
+            ServerName::try_from("localhost").unwrap(),
+            TcpStream::connect(address).await.unwrap(),
+        )
+        .await
+        .unwrap();
+    let mut client = Client::new(stream);
+
+    assert_eq!(
+        Frame::simple("PONG"),
+        client.send_command(cmd!("PING")).await.unwrap()
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_shutdown_drains_connections() {
+    let mut listener = bind("127.0.0.1".into(), 0).await;
+    let address = listener.local_addr().unwrap();
+    let mut server = Server::new("127.0.0.1".into(), address.port());
+    let shutdown = server.shutdown.clone();
+    let sender = server.sender.clone();
+    let accepting = {
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move { run_listener(&mut listener, sender, shutdown).await })
+    };
+    let serving = tokio::spawn(async move {
+        server.run().await;
+        server
+    });
+
+    let mut idle = TcpStream::connect(address).await.unwrap();
+    assert_eq!("+PONG\r\n", roundtrip(&mut idle, "PING\r\n").await);
+    let mut blocked = Client::connect(address).await.unwrap();
+    let waiting =
+        tokio::spawn(async move { blocked.send_command(cmd!("BLPOP", "queue", "0")).await });
+    // Give the BLPOP time to reach the server
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    shutdown.cancel();
+
+    let timeout = Duration::from_secs(1);
+    tokio::time::timeout(timeout, accepting)
+        .await
+        .expect("the accept loop should stop")
+        .unwrap();
+    let server = tokio::time::timeout(timeout, serving)
+        .await
+        .expect("the server should stop")
+        .unwrap();
+    assert!(server.clients.is_empty());
+
+    // Both connections were closed by the server
+    let mut buf = [0; 16];
+    assert_eq!(0, idle.read(&mut buf).await.unwrap());
+    assert!(waiting.await.unwrap().is_err());
+    assert!(TcpStream::connect(address).await.is_err());
+}
+
+#[tokio::test]
+async fn test_blpop_woken_by_push() {
+    let address = start().await;
+    let mut blocked = Client::connect(&address).await.unwrap();
+    let mut pusher = Client::connect(&address).await.unwrap();
+
+    let waiting = tokio::spawn(async move {
+        let reply = blocked
+            .send_command(cmd!("BLPOP", "queue", "5"))
+            .await
+            .unwrap();
+        (blocked, reply)
+    });
+    // Give the BLPOP time to reach the server first
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(
+        Frame::Integer(1),
+        pusher
+            .send_command(cmd!("LPUSH", "queue", "job"))
+            .await
+            .unwrap()
+    );
+
+    let (mut blocked, reply) = waiting.await.unwrap();
+    assert_eq!(cmd!("queue", "job"), reply);
+    assert_eq!(
+        Frame::Null,
+        blocked
+            .send_command(cmd!("BLPOP", "queue", "0.01"))
+            .await
+            .unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_config_set_parse_limits() {
+    let mut socket = TcpStream::connect(start().await).await.unwrap();
+
+    assert_eq!(
+        "+OK\r\n",
+        roundtrip(&mut socket, "CONFIG SET proto-max-bulk-len 8\r\n").await
+    );
+    let reply = roundtrip(&mut socket, "*2\r\n$4\r\nECHO\r\n$9\r\ntoo large\r\n").await;
+    assert!(
+        reply.starts_with("-ERR Protocol error"),
+        "unexpected reply {:?}",
+        reply
+    );
+}
+
+#[tokio::test]
+async fn test_auth() {
+    let address = start().await;
+    let mut admin = TcpStream::connect(&address).await.unwrap();
+    assert_eq!(
+        "+OK\r\n",
+        roundtrip(&mut admin, "CONFIG SET requirepass secret\r\n").await
+    );
+    assert_eq!("+PONG\r\n", roundtrip(&mut admin, "PING\r\n").await);
+
+    let mut socket = TcpStream::connect(&address).await.unwrap();
+    assert_eq!(
+        "-NOAUTH Authentication required.\r\n",
+        roundtrip(&mut socket, "PING\r\n").await
+    );
+    assert!(roundtrip(&mut socket, "AUTH wrong\r\n")
+        .await
+        .starts_with("-WRONGPASS"));
+    assert_eq!("+OK\r\n", roundtrip(&mut socket, "AUTH secret\r\n").await);
+    assert_eq!("+PONG\r\n", roundtrip(&mut socket, "PING\r\n").await);
+}
+
+// Write a request and read back whatever the server replies with
+async fn roundtrip(socket: &mut TcpStream, request: &str) -> String {
+    socket.write_all(request.as_bytes()).await.unwrap();
+    let mut buf = vec![0; 1024];
+    let n = socket.read(&mut buf).await.unwrap();
+    String::from_utf8_lossy(&buf[..n]).into_owned()
+}
+
+// Start listener and server on a random port, returning its address
+async fn start() -> String {
     let mut listener = bind("0.0.0.0".into(), 0).await;
     let mut server = Server::new("0.0.0.0".into(), listener.local_addr().unwrap().port());
     let sender = server.sender.clone();
     let addr = server.info.address();
 
+    let shutdown = server.shutdown.clone();
     tokio::spawn(async move {
-        run_listener(&mut listener, sender).await;
+        run_listener(&mut listener, sender, shutdown).await;
     });
 
     tokio::spawn(async move {
         server.run().await;
     });
 
+    addr
+}
+
+async fn spawn() -> MultiplexedConnection {
+    let addr = start().await;
     let client =
         redis::Client::open(format!("redis://{}/", &addr)).expect("Could not create redis client");
 